@@ -0,0 +1,223 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal "hello guest" hypervisor.
+//!
+//! Runs a guest of a few instructions in VS-mode with Bare `hgatp`, so guest
+//! physical addresses are host physical addresses. The guest makes two
+//! hypercalls and then asks for a system reset through SBI:
+//!
+//! 1. It prints 42 through hypercall 1.
+//! 2. The hypervisor injects a virtual supervisor software interrupt, and the
+//!    guest prints its `sip`, showing the injected SSIP bit.
+//! 3. The hypervisor turns the guest's SRST call into a shutdown.
+//!
+//! The example needs HS-mode on a bare-metal RISC-V target below an SBI
+//! implementation (RV64), e.g. QEMU `virt` with OpenSBI:
+//!
+//! ```text
+//! RUSTFLAGS="-C link-arg=-Ttext=0x80200000" \
+//!     cargo build --example hello_guest --target riscv64gc-unknown-none-elf
+//! qemu-system-riscv64 -machine virt -cpu rv64,h=true -nographic \
+//!     -kernel target/riscv64gc-unknown-none-elf/debug/examples/hello_guest
+//! ```
+//!
+//! On any other target it only prints these instructions.
+
+#![cfg_attr(all(target_os = "none", target_arch = "riscv64"), no_std, no_main)]
+
+#[cfg(not(all(target_os = "none", target_arch = "riscv64")))]
+fn main() {
+    println!("hello_guest runs in HS-mode on a bare-metal RISC-V target;");
+    println!("see the example's documentation for how to run it in QEMU.");
+}
+
+#[cfg(all(target_os = "none", target_arch = "riscv64"))]
+mod hypervisor {
+    use core::fmt::Write;
+    use riscv_h::abi::{SbiCall, SbiRet, complete_hypercall, complete_sbi_call};
+    use riscv_h::entry::GuestEntry;
+    use riscv_h::frame::HTrapFrame;
+    use riscv_h::register::{hgatp, hideleg, hvip, vsatp, vsstatus};
+    use riscv_h::trap::{GuestTrap, TrapInfo};
+
+    /// Extension ID of this example's hypercalls (firmware-specific range).
+    const HYPERCALL_EID: usize = 0x0A00_0000;
+    /// Hypercall printing `a0`.
+    const HC_PRINT: usize = 1;
+    /// SBI System Reset extension ID ("SRST").
+    const SBI_EXT_SRST: usize = 0x5352_5354;
+    /// Legacy SBI console putchar extension ID.
+    const SBI_EXT_PUTCHAR: usize = 0x01;
+
+    /// The guest program.
+    #[repr(C, align(4))]
+    struct GuestCode([u32; 13]);
+
+    static GUEST: GuestCode = GuestCode([
+        0x0A00_08B7, // lui   a7, 0x0A000      (HYPERCALL_EID)
+        0x0010_0813, // li    a6, 1            (HC_PRINT)
+        0x02A0_0513, // li    a0, 42
+        0x0000_0073, // ecall
+        0x1440_2573, // csrr  a0, sip
+        0x0000_0073, // ecall
+        0x5352_58B7, // lui   a7, 0x53525
+        0x3548_8893, // addi  a7, a7, 0x354    (SBI_EXT_SRST)
+        0x0000_0813, // li    a6, 0
+        0x0000_0513, // li    a0, 0
+        0x0000_0593, // li    a1, 0
+        0x0000_0073, // ecall
+        0x0000_006F, // j     .
+    ]);
+
+    const STACK_SIZE: usize = 0x4000;
+
+    #[repr(C, align(16))]
+    struct Stack([u8; STACK_SIZE]);
+
+    static mut STACK: Stack = Stack([0; STACK_SIZE]);
+    static mut FRAME: HTrapFrame = HTrapFrame {
+        gprs: [0; 31],
+        sepc: 0,
+        sstatus: 0,
+        hstatus: 0,
+    };
+
+    core::arch::global_asm!(
+        ".section .text.entry",
+        ".global _start",
+        "_start:",
+        "la sp, {stack}",
+        "li t0, {stack_size}",
+        "add sp, sp, t0",
+        "call {main}",
+        ".text",
+        ".align 2",
+        "guest_exit:",
+        // sscratch holds the frame address while the guest runs
+        "csrrw sp, sscratch, sp",
+        riscv_h::save_gprs!(2),
+        "csrr t0, sscratch",
+        "sd t0, {sp_offset}(sp)",
+        riscv_h::save_csrs!(2, 5),
+        "mv a0, sp",
+        "la sp, {stack}",
+        "li t0, {stack_size}",
+        "add sp, sp, t0",
+        "call {handle_exit}",
+        ".global guest_resume",
+        "guest_resume:",
+        "csrw sscratch, a0",
+        "mv sp, a0",
+        riscv_h::restore_csrs!(2, 5),
+        riscv_h::restore_gprs!(2),
+        "sret",
+        stack = sym STACK,
+        stack_size = const STACK_SIZE,
+        main = sym main,
+        handle_exit = sym handle_exit,
+        sp_offset = const HTrapFrame::gpr_offset(2),
+    );
+
+    unsafe extern "C" {
+        fn guest_exit();
+        fn guest_resume(frame: *mut HTrapFrame) -> !;
+    }
+
+    fn sbi_call(eid: usize, fid: usize, a0: usize, a1: usize) {
+        // SAFETY: Only used for console output and system reset
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                inlateout("a0") a0 => _,
+                inlateout("a1") a1 => _,
+                in("a6") fid,
+                in("a7") eid,
+            )
+        }
+    }
+
+    struct Console;
+
+    impl Write for Console {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            for b in s.bytes() {
+                sbi_call(SBI_EXT_PUTCHAR, 0, b as usize, 0);
+            }
+            Ok(())
+        }
+    }
+
+    fn shutdown() -> ! {
+        sbi_call(SBI_EXT_SRST, 0, 0, 0);
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+
+    extern "C" fn main() -> ! {
+        let _ = writeln!(Console, "hello_guest: entering guest");
+        // SAFETY: We are in HS-mode and no guest is running yet
+        unsafe {
+            // Guest physical addresses are host physical addresses; the
+            // translation stays installed for the rest of the program
+            core::mem::forget(hgatp::Hgatp::bare().install());
+            vsatp::Vsatp::from_bits(0).write();
+            // Let the guest see injected software interrupts in `sip`
+            hideleg::set_vssip();
+            core::arch::asm!("csrw stvec, {}", in(reg) guest_exit as usize);
+            core::arch::asm!("csrw sscratch, {}", in(reg) &raw mut FRAME);
+
+            GuestEntry::new()
+                .virtualized()
+                .supervisor()
+                .with_pc(GUEST.0.as_ptr() as usize)
+                .with_vsstatus(vsstatus::Vsstatus::from_bits(0))
+                .enter()
+        }
+    }
+
+    extern "C" fn handle_exit(frame: &mut HTrapFrame) -> ! {
+        match TrapInfo::capture().decode() {
+            GuestTrap::EnvCall => {
+                let call = SbiCall::from_frame(frame);
+                match call.as_hypercall(HYPERCALL_EID) {
+                    Some(hc) if hc.nr == HC_PRINT => {
+                        let _ = writeln!(Console, "guest says {:#x}", hc.args[0]);
+                        complete_hypercall(frame, 0);
+                        // SAFETY: VSSI is delegated to the guest
+                        unsafe { hvip::set_vssip() };
+                    }
+                    _ if call.eid == SBI_EXT_SRST => {
+                        let _ = writeln!(Console, "guest requested shutdown");
+                        shutdown();
+                    }
+                    _ => complete_sbi_call(frame, SbiRet::error(SbiRet::ERR_NOT_SUPPORTED)),
+                }
+            }
+            exit => {
+                let _ = writeln!(Console, "unexpected guest exit: {}", exit);
+                shutdown();
+            }
+        }
+        // SAFETY: The frame holds the state saved by `guest_exit`
+        unsafe { guest_resume(frame) }
+    }
+
+    #[panic_handler]
+    fn panic(info: &core::panic::PanicInfo) -> ! {
+        let _ = writeln!(Console, "{}", info);
+        shutdown()
+    }
+}
@@ -0,0 +1,189 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest Calling Convention.
+//!
+//! Guests request services from the hypervisor with `ECALL`, passing the
+//! extension and function IDs in `a7`/`a6` and arguments in `a0`–`a5`, and
+//! receive results in `a0`/`a1`. Every hypervisor lays out its saved guest
+//! registers differently, so the helpers here access them through the
+//! [`GuestAbiFrame`](crate::abi::GuestAbiFrame) trait instead of a fixed
+//! structure.
+
+/// Access to the guest registers involved in environment calls.
+pub trait GuestAbiFrame {
+    /// Returns argument register `a<n>` (`n` < 8).
+    fn arg(&self, n: usize) -> usize;
+    /// Sets argument register `a<n>` (`n` < 8).
+    fn set_arg(&mut self, n: usize, value: usize);
+    /// Returns the guest program counter.
+    fn pc(&self) -> usize;
+    /// Sets the guest program counter.
+    fn set_pc(&mut self, pc: usize);
+}
+
+/// Length in bytes of the `ECALL` instruction.
+pub const ECALL_LEN: usize = 4;
+
+/// An SBI call made by a guest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SbiCall {
+    /// Extension ID (`a7`).
+    pub eid: usize,
+    /// Function ID (`a6`).
+    pub fid: usize,
+    /// Arguments (`a0`–`a5`).
+    pub args: [usize; 6],
+}
+
+impl SbiCall {
+    /// Reads the call from the guest registers.
+    pub fn from_frame<F: GuestAbiFrame + ?Sized>(frame: &F) -> Self {
+        SbiCall {
+            eid: frame.arg(7),
+            fid: frame.arg(6),
+            args: core::array::from_fn(|n| frame.arg(n)),
+        }
+    }
+    /// Returns the call as a hypercall if it targets extension `eid`.
+    #[inline]
+    pub fn as_hypercall(&self, eid: usize) -> Option<Hypercall> {
+        (self.eid == eid).then_some(Hypercall {
+            nr: self.fid,
+            args: self.args,
+        })
+    }
+}
+
+/// A hypervisor-specific call, made as an SBI call to a hypervisor-chosen
+/// extension ID with the call number in the function ID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Hypercall {
+    /// Call number (`a6`).
+    pub nr: usize,
+    /// Arguments (`a0`–`a5`).
+    pub args: [usize; 6],
+}
+
+/// Result of an SBI call, returned in `a0` (error) and `a1` (value).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SbiRet {
+    /// Error code; zero on success.
+    pub error: isize,
+    /// Return value.
+    pub value: usize,
+}
+
+impl SbiRet {
+    /// Completed successfully.
+    pub const SUCCESS: isize = 0;
+    /// Failed.
+    pub const ERR_FAILED: isize = -1;
+    /// Not supported.
+    pub const ERR_NOT_SUPPORTED: isize = -2;
+    /// Invalid parameter.
+    pub const ERR_INVALID_PARAM: isize = -3;
+    /// Denied.
+    pub const ERR_DENIED: isize = -4;
+    /// Invalid address.
+    pub const ERR_INVALID_ADDRESS: isize = -5;
+
+    /// Returns a successful result carrying `value`.
+    #[inline]
+    pub const fn success(value: usize) -> Self {
+        SbiRet {
+            error: Self::SUCCESS,
+            value,
+        }
+    }
+    /// Returns a failed result with the given error code.
+    #[inline]
+    pub const fn error(error: isize) -> Self {
+        SbiRet { error, value: 0 }
+    }
+}
+
+/// Returns the result of an SBI call to the guest and skips its `ECALL`.
+pub fn complete_sbi_call<F: GuestAbiFrame + ?Sized>(frame: &mut F, ret: SbiRet) {
+    frame.set_arg(0, ret.error as usize);
+    frame.set_arg(1, ret.value);
+    frame.set_pc(frame.pc().wrapping_add(ECALL_LEN));
+}
+
+/// Returns the result of a hypercall in `a0` and skips its `ECALL`.
+pub fn complete_hypercall<F: GuestAbiFrame + ?Sized>(frame: &mut F, ret: usize) {
+    frame.set_arg(0, ret);
+    frame.set_pc(frame.pc().wrapping_add(ECALL_LEN));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Frame {
+        a: [usize; 8],
+        pc: usize,
+    }
+
+    impl GuestAbiFrame for Frame {
+        fn arg(&self, n: usize) -> usize {
+            self.a[n]
+        }
+        fn set_arg(&mut self, n: usize, value: usize) {
+            self.a[n] = value;
+        }
+        fn pc(&self) -> usize {
+            self.pc
+        }
+        fn set_pc(&mut self, pc: usize) {
+            self.pc = pc;
+        }
+    }
+
+    #[test]
+    fn test_sbi_call_round_trip() {
+        let mut frame = Frame {
+            a: [1, 2, 3, 4, 5, 6, 0, 0x10],
+            pc: 0x8020_0000,
+        };
+        let call = SbiCall::from_frame(&frame);
+        assert_eq!(call.eid, 0x10);
+        assert_eq!(call.fid, 0);
+        assert_eq!(call.args, [1, 2, 3, 4, 5, 6]);
+
+        complete_sbi_call(&mut frame, SbiRet::error(SbiRet::ERR_NOT_SUPPORTED));
+        assert_eq!(frame.a[0] as isize, -2);
+        assert_eq!(frame.a[1], 0);
+        assert_eq!(frame.pc, 0x8020_0004);
+    }
+
+    #[test]
+    fn test_hypercall() {
+        const EID: usize = 0x0A00_0000;
+        let mut frame = Frame::default();
+        frame.a[7] = EID;
+        frame.a[6] = 3;
+        frame.a[0] = 0xdead;
+        let call = SbiCall::from_frame(&frame);
+        assert!(call.as_hypercall(0x10).is_none());
+        let hc = call.as_hypercall(EID).unwrap();
+        assert_eq!(hc.nr, 3);
+        assert_eq!(hc.args[0], 0xdead);
+
+        complete_hypercall(&mut frame, 7);
+        assert_eq!(frame.a[0], 7);
+        assert_eq!(frame.pc, 4);
+    }
+}
@@ -0,0 +1,437 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Instruction Wrappers.
+//!
+//! Inline-assembly wrappers for the instructions added by the hypervisor
+//! extension. They are only valid in HS-mode (or M-mode), so all of them are
+//! `unsafe`. Each one enables the extension (and Svinval where needed) for the
+//! assembler with `.option arch`, so the crate builds for targets that do not
+//! include them.
+//!
+//! `hfence.gvma` takes guest physical addresses shifted right by two in
+//! `rs1`, so the `gpa` arguments here are plain addresses that the wrappers
+//! shift. `hfence.vvma` takes guest virtual addresses unshifted and applies
+//! to the VMID in `hgatp`. Passing `x0` for an operand means "all addresses",
+//! "all VMIDs" or "all ASIDs"; the wrappers without that argument do so.
+//!
+//! On cores with Svinval, a batch of `hinval.gvma`/`hinval.vvma` bracketed by
+//! `sfence.w.inval` and `sfence.inval.ir` replaces a series of full fences;
+//! [`hinval_batch`](crate::asm::hinval_batch) emits that sequence for a list of
+//! [`Hinval`](crate::asm::Hinval)s.
+//!
+//! The `hlv_*` and `hsv_*` functions read and write guest memory through the two-stage
+//! translation of the current guest, as if accessed from the guest privilege
+//! level in `hstatus.SPVP`, honoring `vsstatus.SUM`/`MXR` and the guest
+//! endianness in `hstatus.VSBE`. A failed translation raises a guest-page
+//! fault or access fault in HS-mode, which the caller's trap handler must be
+//! prepared for. The `hlvx_*` variants require execute rather than read
+//! permission, as an instruction fetch would, and serve to read the guest
+//! instruction that trapped.
+
+macro_rules! fence_insn {
+    ($(#[$doc:meta])* $name:ident, $arch:literal, $insn:literal) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        ///
+        /// Must be executed in HS-mode or M-mode.
+        #[inline]
+        pub unsafe fn $name() {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            // SAFETY: Caller ensures this is safe to execute
+            unsafe {
+                core::arch::asm!(with_arch!($arch, $insn), options(nostack))
+            };
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+    ($(#[$doc:meta])* $name:ident, $arch:literal, $insn:literal, $($arg:ident => $value:expr),+) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        ///
+        /// Must be executed in HS-mode or M-mode.
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        pub unsafe fn $name($($arg: usize),+) {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            // SAFETY: Caller ensures this is safe to execute
+            unsafe {
+                core::arch::asm!(
+                    with_arch!($arch, $insn),
+                    $($arg = in(reg) $value,)+
+                    options(nostack),
+                )
+            };
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+macro_rules! hlv {
+    ($(#[$doc:meta])* $name:ident, $insn:literal, $ty:ty) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        ///
+        /// Must be executed in HS-mode or M-mode with `hgatp` and `vsatp`
+        /// describing the guest, and a trap handler in place for faults.
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        pub unsafe fn $name(gva: usize) -> $ty {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            {
+                let value: usize;
+                // SAFETY: Caller ensures this is safe to execute
+                unsafe {
+                    core::arch::asm!(
+                        with_arch!("+h", concat!($insn, " {value}, ({gva})")),
+                        value = out(reg) value,
+                        gva = in(reg) gva,
+                        options(nostack, readonly),
+                    )
+                };
+                value as $ty
+            }
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+macro_rules! hsv {
+    ($(#[$doc:meta])* $name:ident, $insn:literal, $ty:ty) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        ///
+        /// Must be executed in HS-mode or M-mode with `hgatp` and `vsatp`
+        /// describing the guest, and a trap handler in place for faults.
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        pub unsafe fn $name(gva: usize, value: $ty) {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            // SAFETY: Caller ensures this is safe to execute
+            unsafe {
+                core::arch::asm!(
+                    with_arch!("+h", concat!($insn, " {value}, ({gva})")),
+                    value = in(reg) value as usize,
+                    gva = in(reg) gva,
+                    options(nostack),
+                )
+            };
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+fence_insn!(
+    /// Invalidates all G-stage translations of every VMID.
+    hfence_gvma_all, "+h", "hfence.gvma zero, zero"
+);
+
+fence_insn!(
+    /// Invalidates all G-stage translations of `vmid`.
+    hfence_gvma_vmid, "+h", "hfence.gvma zero, {vmid}", vmid => vmid
+);
+
+fence_insn!(
+    /// Invalidates the G-stage translations of guest physical address `gpa`
+    /// for every VMID.
+    hfence_gvma_gpa, "+h", "hfence.gvma {gpa}, zero", gpa => gpa >> 2
+);
+
+fence_insn!(
+    /// Invalidates the G-stage translations of guest physical address `gpa`
+    /// for `vmid`.
+    hfence_gvma, "+h", "hfence.gvma {gpa}, {vmid}", gpa => gpa >> 2, vmid => vmid
+);
+
+fence_insn!(
+    /// Invalidates all VS-stage translations of the current VMID.
+    hfence_vvma_all, "+h", "hfence.vvma zero, zero"
+);
+
+fence_insn!(
+    /// Invalidates the non-global VS-stage translations of guest address
+    /// space `asid` in the current VMID.
+    hfence_vvma_asid, "+h", "hfence.vvma zero, {asid}", asid => asid
+);
+
+fence_insn!(
+    /// Invalidates the VS-stage translations of guest virtual address `gva`
+    /// for every ASID of the current VMID.
+    hfence_vvma_gva, "+h", "hfence.vvma {gva}, zero", gva => gva
+);
+
+fence_insn!(
+    /// Invalidates the non-global VS-stage translations of guest virtual
+    /// address `gva` in guest address space `asid` of the current VMID.
+    hfence_vvma, "+h", "hfence.vvma {gva}, {asid}", gva => gva, asid => asid
+);
+
+fence_insn!(
+    /// Orders preceding stores to page tables before following `hinval`s (Svinval).
+    sfence_w_inval, "+svinval", "sfence.w.inval"
+);
+
+fence_insn!(
+    /// Orders preceding `hinval`s before following implicit page-table
+    /// accesses (Svinval).
+    sfence_inval_ir, "+svinval", "sfence.inval.ir"
+);
+
+fence_insn!(
+    /// Invalidates all G-stage translations of every VMID (Svinval).
+    hinval_gvma_all, "+h, +svinval", "hinval.gvma zero, zero"
+);
+
+fence_insn!(
+    /// Invalidates all G-stage translations of `vmid` (Svinval).
+    hinval_gvma_vmid, "+h, +svinval", "hinval.gvma zero, {vmid}", vmid => vmid
+);
+
+fence_insn!(
+    /// Invalidates the G-stage translations of guest physical address `gpa`
+    /// for every VMID (Svinval).
+    hinval_gvma_gpa, "+h, +svinval", "hinval.gvma {gpa}, zero", gpa => gpa >> 2
+);
+
+fence_insn!(
+    /// Invalidates the G-stage translations of guest physical address `gpa`
+    /// for `vmid` (Svinval).
+    hinval_gvma, "+h, +svinval", "hinval.gvma {gpa}, {vmid}", gpa => gpa >> 2, vmid => vmid
+);
+
+fence_insn!(
+    /// Invalidates all VS-stage translations of the current VMID (Svinval).
+    hinval_vvma_all, "+h, +svinval", "hinval.vvma zero, zero"
+);
+
+fence_insn!(
+    /// Invalidates the non-global VS-stage translations of guest address
+    /// space `asid` in the current VMID (Svinval).
+    hinval_vvma_asid, "+h, +svinval", "hinval.vvma zero, {asid}", asid => asid
+);
+
+fence_insn!(
+    /// Invalidates the VS-stage translations of guest virtual address `gva`
+    /// for every ASID of the current VMID (Svinval).
+    hinval_vvma_gva, "+h, +svinval", "hinval.vvma {gva}, zero", gva => gva
+);
+
+fence_insn!(
+    /// Invalidates the non-global VS-stage translations of guest virtual
+    /// address `gva` in guest address space `asid` of the current VMID
+    /// (Svinval).
+    hinval_vvma, "+h, +svinval", "hinval.vvma {gva}, {asid}", gva => gva, asid => asid
+);
+
+/// One invalidation of an [`hinval_batch`].
+///
+/// `None` selects all addresses, VMIDs or ASIDs, like `x0` does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hinval {
+    /// Invalidates G-stage translations, like `hfence.gvma`.
+    Gvma {
+        /// Guest physical address, or `None` for all addresses.
+        gpa: Option<usize>,
+        /// VMID, or `None` for all VMIDs.
+        vmid: Option<usize>,
+    },
+    /// Invalidates VS-stage translations of the current VMID, like `hfence.vvma`.
+    Vvma {
+        /// Guest virtual address, or `None` for all addresses.
+        gva: Option<usize>,
+        /// Guest ASID, or `None` for all ASIDs.
+        asid: Option<usize>,
+    },
+}
+
+impl Hinval {
+    /// Issues the matching `hinval` instruction.
+    ///
+    /// On its own this is not ordered against page-table updates or later
+    /// accesses; use [`hinval_batch`] unless the caller emits the
+    /// `sfence.w.inval`/`sfence.inval.ir` pair itself.
+    ///
+    /// # Safety
+    ///
+    /// Must be executed in HS-mode or M-mode on a core with Svinval.
+    #[inline]
+    pub unsafe fn issue(&self) {
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe {
+            match *self {
+                Hinval::Gvma {
+                    gpa: None,
+                    vmid: None,
+                } => hinval_gvma_all(),
+                Hinval::Gvma {
+                    gpa: None,
+                    vmid: Some(vmid),
+                } => hinval_gvma_vmid(vmid),
+                Hinval::Gvma {
+                    gpa: Some(gpa),
+                    vmid: None,
+                } => hinval_gvma_gpa(gpa),
+                Hinval::Gvma {
+                    gpa: Some(gpa),
+                    vmid: Some(vmid),
+                } => hinval_gvma(gpa, vmid),
+                Hinval::Vvma {
+                    gva: None,
+                    asid: None,
+                } => hinval_vvma_all(),
+                Hinval::Vvma {
+                    gva: None,
+                    asid: Some(asid),
+                } => hinval_vvma_asid(asid),
+                Hinval::Vvma {
+                    gva: Some(gva),
+                    asid: None,
+                } => hinval_vvma_gva(gva),
+                Hinval::Vvma {
+                    gva: Some(gva),
+                    asid: Some(asid),
+                } => hinval_vvma(gva, asid),
+            }
+        }
+    }
+}
+
+/// Issues a batch of invalidations in the order Svinval requires.
+///
+/// Emits `sfence.w.inval` so preceding page-table stores are visible, every
+/// invalidation in `list`, and `sfence.inval.ir` so later implicit accesses
+/// see their effect.
+///
+/// # Safety
+///
+/// Must be executed in HS-mode or M-mode on a core with Svinval.
+pub unsafe fn hinval_batch(list: &[Hinval]) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        sfence_w_inval();
+        for inval in list {
+            inval.issue();
+        }
+        sfence_inval_ir();
+    }
+}
+
+hlv!(
+    /// Loads a sign-extended byte from guest virtual address `gva`.
+    hlv_b, "hlv.b", i8
+);
+
+hlv!(
+    /// Loads a zero-extended byte from guest virtual address `gva`.
+    hlv_bu, "hlv.bu", u8
+);
+
+hlv!(
+    /// Loads a sign-extended halfword from guest virtual address `gva`.
+    hlv_h, "hlv.h", i16
+);
+
+hlv!(
+    /// Loads a zero-extended halfword from guest virtual address `gva`.
+    hlv_hu, "hlv.hu", u16
+);
+
+hlv!(
+    /// Loads a sign-extended word from guest virtual address `gva`.
+    hlv_w, "hlv.w", i32
+);
+
+#[cfg(not(target_arch = "riscv32"))]
+hlv!(
+    /// Loads a zero-extended word from guest virtual address `gva` (RV64).
+    hlv_wu, "hlv.wu", u32
+);
+
+#[cfg(not(target_arch = "riscv32"))]
+hlv!(
+    /// Loads a doubleword from guest virtual address `gva` (RV64).
+    hlv_d, "hlv.d", u64
+);
+
+hsv!(
+    /// Stores a byte to guest virtual address `gva`.
+    hsv_b, "hsv.b", u8
+);
+
+hsv!(
+    /// Stores a halfword to guest virtual address `gva`.
+    hsv_h, "hsv.h", u16
+);
+
+hsv!(
+    /// Stores a word to guest virtual address `gva`.
+    hsv_w, "hsv.w", u32
+);
+
+#[cfg(not(target_arch = "riscv32"))]
+hsv!(
+    /// Stores a doubleword to guest virtual address `gva` (RV64).
+    hsv_d, "hsv.d", u64
+);
+
+hlv!(
+    /// Loads a zero-extended halfword from guest virtual address `gva`,
+    /// checking execute instead of read permission.
+    hlvx_hu, "hlvx.hu", u16
+);
+
+hlv!(
+    /// Loads a zero-extended word from guest virtual address `gva`, checking
+    /// execute instead of read permission.
+    hlvx_wu, "hlvx.wu", u32
+);
+
+/// Fetches the guest instruction at `pc` with execute permission semantics.
+///
+/// The second halfword is only read for 32-bit instructions, so a compressed
+/// instruction at the end of a page does not fault on the next page. Returns
+/// the instruction bits; its length follows from
+/// [`instruction_len`](crate::breakpoint::instruction_len).
+///
+/// # Safety
+///
+/// Same as [`hlvx_hu`].
+pub unsafe fn fetch_guest_insn(pc: usize) -> u32 {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        let low = hlvx_hu(pc);
+        if crate::breakpoint::instruction_len(low) == 2 {
+            low as u32
+        } else {
+            low as u32 | (hlvx_hu(pc.wrapping_add(2)) as u32) << 16
+        }
+    }
+}
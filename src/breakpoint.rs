@@ -0,0 +1,257 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest Software-Breakpoint Debugging.
+//!
+//! Hypervisors normally delegate breakpoint exceptions to VS-mode through
+//! `hedeleg` bit 3, so a guest `EBREAK` never reaches HS-mode. A debugger
+//! attached to the guest needs the opposite: the breakpoint has to trap to the
+//! hypervisor, be reported, and then be skipped, retried or handed back to the
+//! guest as if it had been delegated in the first place.
+//!
+//! The flow provided here is:
+//! 1. [`intercept`](crate::breakpoint::intercept) un-delegates breakpoints
+//!    while a debugger is attached.
+//! 2. [`GuestBreakpoint::capture`](crate::breakpoint::GuestBreakpoint::capture)
+//!    recognizes a guest `EBREAK` in the trap handler.
+//! 3. A [`BreakpointHandler`](crate::breakpoint::BreakpointHandler) decides how
+//!    to [`Resume`](crate::breakpoint::Resume) the guest.
+//! 4. [`release`](crate::breakpoint::release) restores the original delegation
+//!    when the debugger detaches.
+
+use crate::register::{hedeleg, hstatus, vscause, vsepc, vsstatus, vstval, vstvec};
+use bit_field::BitField;
+use riscv::register::{scause, sepc, sstatus, stval};
+
+/// Exception code of the breakpoint exception.
+pub const BREAKPOINT_CAUSE: usize = 3;
+/// Encoding of the 32-bit `EBREAK` instruction.
+pub const EBREAK: u32 = 0x0010_0073;
+/// Encoding of the 16-bit `C.EBREAK` instruction.
+pub const C_EBREAK: u16 = 0x9002;
+
+/// Stops delegating breakpoint exceptions to VS-mode.
+///
+/// Returns whether breakpoints were delegated before the call, to be passed
+/// back to [`release`] once the debugger detaches.
+///
+/// # Safety
+///
+/// Guest `EBREAK`s trap to HS-mode afterwards, so the caller must have a trap
+/// handler in place that services them.
+#[inline]
+pub unsafe fn intercept() -> bool {
+    let was_delegated = hedeleg::read().ex3();
+    // SAFETY: Caller ensures breakpoint traps are handled in HS-mode
+    unsafe { hedeleg::clear_ex3() };
+    was_delegated
+}
+
+/// Restores the breakpoint delegation saved by [`intercept`].
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which mode handles guest
+/// breakpoint exceptions.
+#[inline]
+pub unsafe fn release(was_delegated: bool) {
+    if was_delegated {
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe { hedeleg::set_ex3() };
+    }
+}
+
+/// Returns the length in bytes of the instruction whose low half-word is `parcel`.
+#[inline]
+pub fn instruction_len(parcel: u16) -> usize {
+    if parcel & 0b11 == 0b11 { 4 } else { 2 }
+}
+
+/// How to continue a guest after a breakpoint has been reported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Resume {
+    /// Re-execute the trapping instruction, e.g. after the debugger restored
+    /// the original instruction in guest memory.
+    Retry,
+    /// Skip over the breakpoint instruction of the given length in bytes.
+    Skip(usize),
+    /// Forward the breakpoint to the guest's own exception handler.
+    Reinject,
+}
+
+/// Debugger callback invoked for every intercepted guest breakpoint.
+pub trait BreakpointHandler {
+    /// Reports the breakpoint and chooses how the guest continues.
+    fn on_breakpoint(&mut self, bp: &GuestBreakpoint) -> Resume;
+}
+
+/// A breakpoint exception taken from VS-mode or VU-mode.
+#[derive(Copy, Clone, Debug)]
+pub struct GuestBreakpoint {
+    pc: usize,
+    tval: usize,
+    from_vs: bool,
+}
+
+impl GuestBreakpoint {
+    /// Recognizes a guest breakpoint from already-read trap CSR values.
+    ///
+    /// Returns `None` if the trap is not a breakpoint exception or did not
+    /// originate from a virtualized mode.
+    pub fn from_trap(
+        scause: usize,
+        sepc: usize,
+        stval: usize,
+        hstatus: hstatus::Hstatus,
+    ) -> Option<Self> {
+        let interrupt = scause.get_bit(usize::BITS as usize - 1);
+        if interrupt || scause != BREAKPOINT_CAUSE || !hstatus.spv() {
+            return None;
+        }
+        Some(GuestBreakpoint {
+            pc: sepc,
+            tval: stval,
+            from_vs: hstatus.spvp(),
+        })
+    }
+    /// Reads the trap CSRs and recognizes a guest breakpoint.
+    #[inline]
+    pub fn capture() -> Option<Self> {
+        Self::from_trap(
+            scause::read().bits(),
+            sepc::read(),
+            stval::read(),
+            hstatus::read(),
+        )
+    }
+    /// Returns the guest virtual address of the breakpoint instruction.
+    #[inline]
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+    /// Returns the trap value reported for the breakpoint.
+    #[inline]
+    pub fn tval(&self) -> usize {
+        self.tval
+    }
+    /// Returns whether the breakpoint was hit in VS-mode rather than VU-mode.
+    #[inline]
+    pub fn from_vs(&self) -> bool {
+        self.from_vs
+    }
+    /// Returns the guest PC to resume at for a non-reinjecting action.
+    #[inline]
+    pub fn resume_pc(&self, action: Resume) -> usize {
+        match action {
+            Resume::Skip(len) => self.pc.wrapping_add(len),
+            Resume::Retry | Resume::Reinject => self.pc,
+        }
+    }
+    /// Forwards the breakpoint to the guest's exception handler.
+    ///
+    /// Updates the VS-level trap CSRs the way a delegated trap would and
+    /// returns the guest handler address to be written to `sepc`.
+    ///
+    /// # Safety
+    ///
+    /// Must be called from the HS-mode trap handler of the breakpoint, before
+    /// any other trap overwrites the guest state.
+    pub unsafe fn reinject(&self) -> usize {
+        let mut vss = vsstatus::read();
+        vss.set_spp(self.from_vs);
+        vss.set_spie(vss.sie());
+        vss.set_sie(false);
+        // SAFETY: Caller ensures the guest trap state is ours to modify
+        unsafe {
+            vss.write();
+            vsepc::Vsepc::from_bits(self.pc).write(vsepc::Ialign::Bits16);
+            vscause::Vscause::from_bits(BREAKPOINT_CAUSE).write();
+            vstval::write(self.tval);
+            sstatus::set_spp(sstatus::SPP::Supervisor);
+        }
+        // Exceptions always enter at the base address, even in vectored mode.
+        vstvec::read().base() << 2
+    }
+}
+
+/// Services an intercepted guest breakpoint, if the current trap is one.
+///
+/// Reports it to `handler`, applies the chosen [`Resume`] action and updates
+/// `sepc`. Returns the new guest PC, or `None` if the trap was not a guest
+/// breakpoint.
+///
+/// # Safety
+///
+/// Must be called from the HS-mode trap handler with the trap CSRs intact.
+pub unsafe fn handle<H: BreakpointHandler>(handler: &mut H) -> Option<usize> {
+    let bp = GuestBreakpoint::capture()?;
+    let pc = match handler.on_breakpoint(&bp) {
+        // SAFETY: Caller ensures we are in the trap handler
+        Resume::Reinject => unsafe { bp.reinject() },
+        action => bp.resume_pc(action),
+    };
+    // SAFETY: Caller ensures we are in the trap handler
+    unsafe { sepc::write(pc) };
+    Some(pc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn guest_hstatus(vs: bool) -> hstatus::Hstatus {
+        let mut hstatus = hstatus::Hstatus::from_bits(0);
+        hstatus.set_spv(true);
+        hstatus.set_spvp(vs);
+        hstatus
+    }
+
+    #[test]
+    fn test_from_trap_guest_breakpoint() {
+        let bp =
+            GuestBreakpoint::from_trap(3, 0x8020_0000, 0x8020_0000, guest_hstatus(true)).unwrap();
+        assert_eq!(bp.pc(), 0x8020_0000);
+        assert_eq!(bp.tval(), 0x8020_0000);
+        assert!(bp.from_vs());
+
+        let bp = GuestBreakpoint::from_trap(3, 0x1000, 0, guest_hstatus(false)).unwrap();
+        assert!(!bp.from_vs());
+    }
+
+    #[test]
+    fn test_from_trap_rejects_other_traps() {
+        // Not a breakpoint
+        assert!(GuestBreakpoint::from_trap(2, 0x1000, 0, guest_hstatus(true)).is_none());
+        // Interrupt with the same code
+        let irq = (1 << (usize::BITS - 1)) | 3;
+        assert!(GuestBreakpoint::from_trap(irq, 0x1000, 0, guest_hstatus(true)).is_none());
+        // Breakpoint taken by the hypervisor itself
+        let host = hstatus::Hstatus::from_bits(0);
+        assert!(GuestBreakpoint::from_trap(3, 0x1000, 0, host).is_none());
+    }
+
+    #[test]
+    fn test_resume_pc() {
+        let bp = GuestBreakpoint::from_trap(3, 0x2000, 0, guest_hstatus(true)).unwrap();
+        assert_eq!(bp.resume_pc(Resume::Retry), 0x2000);
+        assert_eq!(bp.resume_pc(Resume::Skip(2)), 0x2002);
+        assert_eq!(bp.resume_pc(Resume::Skip(4)), 0x2004);
+    }
+
+    #[test]
+    fn test_instruction_len() {
+        assert_eq!(instruction_len(EBREAK as u16), 4);
+        assert_eq!(instruction_len(C_EBREAK), 2);
+    }
+}
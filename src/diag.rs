@@ -0,0 +1,281 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interrupt Delivery Diagnostics.
+//!
+//! A guest that hangs waiting for an interrupt is most often the victim of a
+//! misconfigured delivery path rather than a missing interrupt: the interrupt
+//! is pending in `hip` but neither delegated to VS-mode through `hideleg` nor
+//! enabled for HS-mode in `hie`, so nobody ever takes it.
+//!
+//! [`InterruptState`](crate::diag::InterruptState) snapshots the relevant CSRs
+//! and [`InterruptState::check`](crate::diag::InterruptState::check) reports
+//! every such contradiction as an [`Issue`](crate::diag::Issue).
+
+use crate::gdb::GuestCsrs;
+use crate::register::{hgeie, hgeip, hideleg, hie, hip, hstatus, hvip};
+
+/// A VS-level interrupt source.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VsInterrupt {
+    /// Virtual supervisor software interrupt.
+    Software,
+    /// Virtual supervisor timer interrupt.
+    Timer,
+    /// Virtual supervisor external interrupt.
+    External,
+}
+
+impl VsInterrupt {
+    /// All VS-level interrupt sources in `hip` bit order.
+    pub const ALL: [VsInterrupt; 3] = [Self::Software, Self::Timer, Self::External];
+
+    /// Returns the bit of this interrupt in `hip`, `hie`, `hvip` and `hideleg`.
+    #[inline]
+    pub fn mask(self) -> usize {
+        match self {
+            Self::Software => 1 << 2,
+            Self::Timer => 1 << 6,
+            Self::External => 1 << 10,
+        }
+    }
+}
+
+/// A contradiction found in the interrupt delivery configuration.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Issue {
+    /// The interrupt is pending but neither delegated to VS-mode nor enabled
+    /// for HS-mode, so it is never taken.
+    Stuck(VsInterrupt),
+    /// The interrupt is injected through `hvip` but neither delegated to
+    /// VS-mode nor enabled for HS-mode.
+    InjectedUnreachable(VsInterrupt),
+    /// A guest external interrupt is pending but `hie.SGEIE` is clear, so the
+    /// supervisor guest external interrupt is never taken.
+    SgeiMasked {
+        /// Pending and enabled bits of `hgeip & hgeie`.
+        files: usize,
+    },
+    /// The guest interrupt file selected by `hstatus.VGEIN` is also enabled in
+    /// `hgeie`, so its interrupts are reported to both HS-mode and VS-mode.
+    VgeinAlsoHgeie {
+        /// The guest interrupt file number.
+        vgein: usize,
+    },
+    /// `hstatus.VGEIN` selects a guest interrupt file that is not implemented.
+    VgeinUnimplemented {
+        /// The guest interrupt file number.
+        vgein: usize,
+    },
+}
+
+/// A snapshot of the CSRs involved in VS-level interrupt delivery.
+#[derive(Copy, Clone, Debug)]
+pub struct InterruptState {
+    /// Value of `hip`.
+    pub hip: usize,
+    /// Value of `hvip`.
+    pub hvip: usize,
+    /// Value of `hie`.
+    pub hie: usize,
+    /// Value of `hideleg`.
+    pub hideleg: usize,
+    /// Value of `hstatus.VGEIN`.
+    pub vgein: usize,
+    /// Value of `hgeie`.
+    pub hgeie: usize,
+    /// Value of `hgeip`.
+    pub hgeip: usize,
+    /// Mask of implemented guest interrupt files (bits `1..=GEILEN`).
+    pub geilen_mask: usize,
+}
+
+impl InterruptState {
+    /// Reads the interrupt delivery CSRs of the current hart.
+    ///
+    /// `geilen` is the number of implemented guest interrupt files.
+    pub fn capture(geilen: usize) -> Self {
+        InterruptState {
+            hip: hip::read().bits(),
+            hvip: hvip::read().bits(),
+            hie: hie::read().bits(),
+            hideleg: hideleg::read().bits(),
+            vgein: hstatus::read().vgein_raw(),
+            hgeie: hgeie::read().bits(),
+            hgeip: hgeip::read().bits(),
+            geilen_mask: geilen_mask(geilen),
+        }
+    }
+    /// Reads the interrupt delivery CSRs through a [`GuestCsrs`] backend.
+    ///
+    /// CSRs the backend does not provide read as zero.
+    pub fn capture_from<C: GuestCsrs + ?Sized>(csrs: &C, geilen: usize) -> Self {
+        let read = |csr| csrs.read_csr(csr).unwrap_or(0);
+        InterruptState {
+            hip: read(0x644),
+            hvip: read(0x645),
+            hie: read(0x604),
+            hideleg: read(0x603),
+            vgein: hstatus::Hstatus::from_bits(read(0x600)).vgein_raw(),
+            hgeie: read(0x607),
+            hgeip: read(0xE12),
+            geilen_mask: geilen_mask(geilen),
+        }
+    }
+    /// Returns every contradiction in the snapshot.
+    pub fn check(&self) -> impl Iterator<Item = Issue> + '_ {
+        let delivered = self.hideleg | self.hie;
+        let vs = VsInterrupt::ALL.into_iter().filter_map(move |irq| {
+            if delivered & irq.mask() != 0 {
+                None
+            } else if self.hvip & irq.mask() != 0 {
+                Some(Issue::InjectedUnreachable(irq))
+            } else if self.hip & irq.mask() != 0 {
+                Some(Issue::Stuck(irq))
+            } else {
+                None
+            }
+        });
+        let files = self.hgeip & self.hgeie & self.geilen_mask;
+        let sgei = (files != 0 && self.hie & (1 << 12) == 0).then_some(Issue::SgeiMasked { files });
+        let vgein = match self.vgein {
+            0 => None,
+            n if self.geilen_mask & vgein_bit(n) == 0 => {
+                Some(Issue::VgeinUnimplemented { vgein: n })
+            }
+            n if self.hgeie & vgein_bit(n) != 0 => Some(Issue::VgeinAlsoHgeie { vgein: n }),
+            _ => None,
+        };
+        vs.chain(sgei).chain(vgein)
+    }
+    /// Logs every contradiction as a warning and returns how many were found.
+    pub fn report(&self) -> usize {
+        self.check()
+            .inspect(|issue| log::warn!("interrupt delivery: {:?} in {:x?}", issue, self))
+            .count()
+    }
+}
+
+/// Returns the mask of guest interrupt files `1..=geilen`.
+#[inline]
+pub(crate) fn geilen_mask(geilen: usize) -> usize {
+    let geilen = geilen.min(usize::BITS as usize - 1);
+    ((1usize << geilen) - 1) << 1
+}
+
+/// Returns the `hgeie`/`hgeip` bit of guest interrupt file `vgein`, or 0 if
+/// the register is too narrow to hold it, e.g. VGEIN 32 or above on RV32.
+#[inline]
+fn vgein_bit(vgein: usize) -> usize {
+    u32::try_from(vgein)
+        .ok()
+        .and_then(|n| 1usize.checked_shl(n))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet() -> InterruptState {
+        InterruptState {
+            hip: 0,
+            hvip: 0,
+            hie: 0,
+            hideleg: 0,
+            vgein: 0,
+            hgeie: 0,
+            hgeip: 0,
+            geilen_mask: geilen_mask(7),
+        }
+    }
+
+    #[test]
+    fn test_quiet_state_is_consistent() {
+        assert_eq!(quiet().check().count(), 0);
+    }
+
+    #[test]
+    fn test_stuck_vseip() {
+        let mut state = quiet();
+        state.hip = VsInterrupt::External.mask();
+        assert_eq!(
+            state.check().next(),
+            Some(Issue::Stuck(VsInterrupt::External))
+        );
+        assert_eq!(state.check().count(), 1);
+
+        // Delegating or enabling it resolves the contradiction
+        state.hideleg = VsInterrupt::External.mask();
+        assert_eq!(state.check().count(), 0);
+        state.hideleg = 0;
+        state.hie = VsInterrupt::External.mask();
+        assert_eq!(state.check().count(), 0);
+    }
+
+    #[test]
+    fn test_injected_unreachable() {
+        let mut state = quiet();
+        state.hvip = VsInterrupt::Timer.mask();
+        state.hip = VsInterrupt::Timer.mask();
+        assert_eq!(
+            state.check().next(),
+            Some(Issue::InjectedUnreachable(VsInterrupt::Timer))
+        );
+    }
+
+    #[test]
+    fn test_sgei_masked() {
+        let mut state = quiet();
+        state.hgeie = 0b110;
+        state.hgeip = 0b100;
+        assert_eq!(
+            state.check().next(),
+            Some(Issue::SgeiMasked { files: 0b100 })
+        );
+        state.hie = 1 << 12;
+        assert_eq!(state.check().count(), 0);
+    }
+
+    #[test]
+    fn test_vgein() {
+        let mut state = quiet();
+        state.vgein = 3;
+        assert_eq!(state.check().count(), 0);
+        state.hgeie = 1 << 3;
+        assert_eq!(
+            state.check().next(),
+            Some(Issue::VgeinAlsoHgeie { vgein: 3 })
+        );
+        state.vgein = 9;
+        assert_eq!(
+            state.check().next(),
+            Some(Issue::VgeinUnimplemented { vgein: 9 })
+        );
+        // Wider than the register, as VGEIN 32..64 is on RV32
+        let wide = usize::BITS as usize;
+        state.vgein = wide;
+        assert_eq!(
+            state.check().next(),
+            Some(Issue::VgeinUnimplemented { vgein: wide })
+        );
+    }
+
+    #[test]
+    fn test_geilen_mask() {
+        assert_eq!(geilen_mask(0), 0);
+        assert_eq!(geilen_mask(1), 0b10);
+        assert_eq!(geilen_mask(7), 0xfe);
+    }
+}
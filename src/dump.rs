@@ -0,0 +1,314 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest Crash Dumps.
+//!
+//! [`emit`](crate::dump::emit) serializes a guest's
+//! [`VsCpuContext`](crate::vcpu::VsCpuContext), its general-purpose registers
+//! and the last trap into a compact, self-describing byte stream for
+//! post-mortem analysis. [`CrashDump::parse`](crate::dump::CrashDump::parse)
+//! reads it back, on the target or on a development host, and its `Display`
+//! implementation pretty-prints the state using the typed register values of
+//! this crate.
+//!
+//! # Format
+//!
+//! A dump starts with the 4-byte magic `RVHD` and a version byte, followed by
+//! records and a terminating zero byte. Each record is a name length byte, the
+//! UTF-8 name and a little-endian 64-bit value. Readers skip records they do
+//! not know, so new records can be added without breaking older tools. The
+//! last trap is stored as its raw CSRs and decoded when printed.
+
+use crate::gdb::GuestCsrs;
+use crate::trap::TrapInfo;
+use crate::vcpu::VsCpuContext;
+use core::fmt;
+
+/// Magic bytes at the start of a dump.
+pub const MAGIC: [u8; 4] = *b"RVHD";
+/// Format version written by [`emit`].
+pub const VERSION: u8 = 1;
+
+/// ABI names of `x0..x31`, used as record names.
+pub const GPR_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Errors reading or writing a dump.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DumpError {
+    /// The output buffer is too small.
+    BufferTooSmall,
+    /// The input does not start with a known header.
+    BadHeader,
+    /// The input ends in the middle of a record.
+    Truncated,
+    /// A record name is not valid UTF-8.
+    BadName,
+}
+
+/// Incremental writer of dump records.
+#[derive(Debug)]
+pub struct DumpWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> DumpWriter<'a> {
+    /// Starts a dump in `buf` by writing the header.
+    pub fn new(buf: &'a mut [u8]) -> Result<Self, DumpError> {
+        let mut w = DumpWriter { buf, len: 0 };
+        w.put(&MAGIC)?;
+        w.put(&[VERSION])?;
+        Ok(w)
+    }
+    /// Appends a record.
+    pub fn record(&mut self, name: &str, value: u64) -> Result<(), DumpError> {
+        debug_assert!(!name.is_empty() && name.len() <= u8::MAX as usize);
+        self.put(&[name.len() as u8])?;
+        self.put(name.as_bytes())?;
+        self.put(&value.to_le_bytes())
+    }
+    /// Terminates the dump and returns its length in bytes.
+    pub fn finish(mut self) -> Result<usize, DumpError> {
+        self.put(&[0])?;
+        Ok(self.len)
+    }
+    fn put(&mut self, bytes: &[u8]) -> Result<(), DumpError> {
+        let end = self.len + bytes.len();
+        self.buf
+            .get_mut(self.len..end)
+            .ok_or(DumpError::BufferTooSmall)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Writes a complete dump into `buf` and returns its length in bytes.
+pub fn emit(
+    buf: &mut [u8],
+    ctx: &VsCpuContext,
+    gprs: &[usize; 32],
+    pc: usize,
+    trap: &TrapInfo,
+) -> Result<usize, DumpError> {
+    let mut w = DumpWriter::new(buf)?;
+    w.record("pc", pc as u64)?;
+    for (name, &value) in GPR_NAMES.iter().zip(gprs).skip(1) {
+        w.record(name, value as u64)?;
+    }
+    for (name, csr) in VsCpuContext::CSRS {
+        w.record(name, ctx.read_csr(csr).unwrap_or_default() as u64)?;
+    }
+    w.record("scause", trap.scause as u64)?;
+    w.record("stval", trap.stval as u64)?;
+    w.record("htval", trap.htval as u64)?;
+    w.record("htinst", trap.htinst as u64)?;
+    w.record("hstatus", trap.hstatus as u64)?;
+    w.finish()
+}
+
+/// Iterator over the `(name, value)` records of a dump.
+#[derive(Clone, Debug)]
+pub struct Records<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Records<'a> {
+    /// Checks the header of `dump` and iterates over its records.
+    pub fn new(dump: &'a [u8]) -> Result<Self, DumpError> {
+        match dump {
+            [m0, m1, m2, m3, VERSION, rest @ ..] if [*m0, *m1, *m2, *m3] == MAGIC => {
+                Ok(Records { rest })
+            }
+            _ => Err(DumpError::BadHeader),
+        }
+    }
+}
+
+impl<'a> Iterator for Records<'a> {
+    type Item = Result<(&'a str, u64), DumpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&len, rest) = match self.rest.split_first() {
+            None => {
+                self.rest = &[0];
+                return Some(Err(DumpError::Truncated));
+            }
+            Some((0, _)) => return None,
+            Some(split) => split,
+        };
+        let len = len as usize;
+        let Some((name, value)) = rest.get(..len).zip(rest.get(len..len + 8)) else {
+            self.rest = &[0];
+            return Some(Err(DumpError::Truncated));
+        };
+        self.rest = &rest[len + 8..];
+        let value = u64::from_le_bytes(value.try_into().unwrap());
+        Some(
+            core::str::from_utf8(name)
+                .map(|name| (name, value))
+                .map_err(|_| DumpError::BadName),
+        )
+    }
+}
+
+/// Guest state recovered from a dump.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct CrashDump {
+    /// VS-level CSR state.
+    pub ctx: VsCpuContext,
+    /// General-purpose registers `x0..x31`.
+    pub gprs: [usize; 32],
+    /// Guest program counter.
+    pub pc: usize,
+    /// Last trap taken from the guest.
+    pub trap: TrapInfo,
+}
+
+impl CrashDump {
+    /// Parses a dump, ignoring unknown records.
+    pub fn parse(dump: &[u8]) -> Result<Self, DumpError> {
+        let mut out = CrashDump::default();
+        for record in Records::new(dump)? {
+            let (name, value) = record?;
+            let value = value as usize;
+            let trap = &mut out.trap;
+            match name {
+                "pc" => out.pc = value,
+                "scause" => trap.scause = value,
+                "stval" => trap.stval = value,
+                "htval" => trap.htval = value,
+                "htinst" => trap.htinst = value,
+                "hstatus" => trap.hstatus = value,
+                _ => {
+                    if let Some(i) = GPR_NAMES.iter().position(|&n| n == name) {
+                        out.gprs[i] = value;
+                    } else if let Some(&(_, csr)) =
+                        VsCpuContext::CSRS.iter().find(|(n, _)| *n == name)
+                    {
+                        out.ctx.write_csr(csr, value);
+                    }
+                }
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl fmt::Display for CrashDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "trap: {}", self.trap.decode())?;
+        writeln!(f, "pc: {:#x}", self.pc)?;
+        for (i, (name, value)) in GPR_NAMES.iter().zip(self.gprs).enumerate().skip(1) {
+            write!(f, "{:>4}: {:#018x}", name, value)?;
+            f.write_str(if i % 4 == 3 { "\n" } else { "  " })?;
+        }
+        let ctx = &self.ctx;
+        writeln!(f, "{}", ctx.vsstatus)?;
+        writeln!(f, "{}", ctx.vsie)?;
+        writeln!(f, "{}", ctx.vstvec)?;
+        writeln!(f, "vsscratch: {:#x}", ctx.vsscratch)?;
+        writeln!(f, "vsepc: {:#x}", ctx.vsepc)?;
+        writeln!(f, "{}", ctx.vscause)?;
+        writeln!(f, "vstval: {:#x}", ctx.vstval)?;
+        writeln!(f, "{}", ctx.vsatp)?;
+        write!(f, "{}", ctx.hvip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+
+    fn sample() -> (VsCpuContext, [usize; 32], TrapInfo) {
+        let mut ctx = VsCpuContext::default();
+        ctx.vsepc = 0x8020_0000;
+        ctx.vsstatus.set_sie(true);
+        let mut gprs = [0; 32];
+        for (i, gpr) in gprs.iter_mut().enumerate().skip(1) {
+            *gpr = i * 0x100;
+        }
+        let trap = TrapInfo {
+            scause: 21,
+            stval: 0x1000,
+            htval: 0x400,
+            htinst: 0x3003,
+            hstatus: 1 << 7,
+        };
+        (ctx, gprs, trap)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (ctx, gprs, trap) = sample();
+        let mut buf = [0; 1024];
+        let len = emit(&mut buf, &ctx, &gprs, 0x8020_0004, &trap).unwrap();
+
+        let dump = CrashDump::parse(&buf[..len]).unwrap();
+        assert_eq!(dump.pc, 0x8020_0004);
+        assert_eq!(dump.gprs, gprs);
+        assert_eq!(dump.ctx.vsepc, 0x8020_0000);
+        assert!(dump.ctx.vsstatus.sie());
+        assert_eq!(dump.trap, trap);
+
+        let text = format!("{}", dump);
+        assert!(text.starts_with("trap: Load guest-page fault at gpa 0x1000\n"));
+        assert!(text.contains("  a0: 0x0000000000000a00"));
+        assert!(text.contains("\nVsstatus(0x2) "));
+    }
+
+    #[test]
+    fn test_unknown_records_are_skipped() {
+        let mut buf = [0; 64];
+        let mut w = DumpWriter::new(&mut buf).unwrap();
+        w.record("future", 7).unwrap();
+        w.record("pc", 0x1234).unwrap();
+        let len = w.finish().unwrap();
+        assert_eq!(CrashDump::parse(&buf[..len]).unwrap().pc, 0x1234);
+    }
+
+    #[test]
+    fn test_records_end_after_truncation() {
+        for dump in [&b"RVHD\x01"[..], b"RVHD\x01\x02pc\x00"] {
+            let mut records = Records::new(dump).unwrap();
+            assert_eq!(records.next(), Some(Err(DumpError::Truncated)));
+            assert_eq!(records.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let (ctx, gprs, trap) = sample();
+        let mut small = [0; 16];
+        assert_eq!(
+            emit(&mut small, &ctx, &gprs, 0, &trap),
+            Err(DumpError::BufferTooSmall)
+        );
+        assert_eq!(
+            CrashDump::parse(b"ELF\x7f\x01").unwrap_err(),
+            DumpError::BadHeader
+        );
+        assert_eq!(
+            CrashDump::parse(b"RVHD\x01\x02pc\x00").unwrap_err(),
+            DumpError::Truncated
+        );
+    }
+}
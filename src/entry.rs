@@ -0,0 +1,236 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typestate Guest Entry.
+//!
+//! Entering a guest with `sret` requires `hstatus.SPV`, `hstatus.SPVP`,
+//! `sstatus.SPP`, `sepc` and `vsstatus` to be set up consistently; forgetting
+//! one of them silently returns to HS-mode or runs the guest at the wrong
+//! privilege level. [`GuestEntry`](crate::entry::GuestEntry) tracks which of
+//! them have been configured in its type, so
+//! [`GuestEntry::enter`](crate::entry::GuestEntry::enter) only exists once all
+//! of them are:
+//!
+//! ```no_run
+//! use riscv_h::entry::GuestEntry;
+//! use riscv_h::register::vsstatus::Vsstatus;
+//!
+//! let entry = GuestEntry::new()
+//!     .virtualized()
+//!     .supervisor()
+//!     .with_pc(0x8020_0000)
+//!     .with_vsstatus(Vsstatus::from_bits(0));
+//! unsafe { entry.enter() }
+//! ```
+//!
+//! Skipping a step is a compile error:
+//!
+//! ```compile_fail
+//! use riscv_h::entry::GuestEntry;
+//!
+//! unsafe { GuestEntry::new().supervisor().with_pc(0x8020_0000).enter() }
+//! ```
+
+use crate::register::{hstatus, vsstatus};
+use core::marker::PhantomData;
+use riscv::register::{sepc, sstatus};
+
+/// Typestate marker of a step that has not been configured yet.
+#[derive(Copy, Clone, Debug)]
+pub struct Unset;
+/// Typestate marker of a configured step.
+#[derive(Copy, Clone, Debug)]
+pub struct Set;
+
+/// Builder for an `sret` into a guest.
+///
+/// The type parameters record whether virtualization (`V`), the guest
+/// privilege (`P`), the entry PC (`Pc`) and `vsstatus` (`S`) are configured.
+#[derive(Copy, Clone, Debug)]
+pub struct GuestEntry<V, P, Pc, S> {
+    supervisor: bool,
+    pc: usize,
+    vsstatus: vsstatus::Vsstatus,
+    _state: PhantomData<(V, P, Pc, S)>,
+}
+
+impl GuestEntry<Unset, Unset, Unset, Unset> {
+    /// Starts configuring a guest entry.
+    #[inline]
+    pub fn new() -> Self {
+        GuestEntry {
+            supervisor: false,
+            pc: 0,
+            vsstatus: vsstatus::Vsstatus::from_bits(0),
+            _state: PhantomData,
+        }
+    }
+}
+
+impl Default for GuestEntry<Unset, Unset, Unset, Unset> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V, P, Pc, S> GuestEntry<V, P, Pc, S> {
+    #[inline]
+    fn cast<V2, P2, Pc2, S2>(self) -> GuestEntry<V2, P2, Pc2, S2> {
+        GuestEntry {
+            supervisor: self.supervisor,
+            pc: self.pc,
+            vsstatus: self.vsstatus,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<P, Pc, S> GuestEntry<Unset, P, Pc, S> {
+    /// Makes `sret` enter a virtualized mode (`hstatus.SPV`).
+    #[inline]
+    pub fn virtualized(self) -> GuestEntry<Set, P, Pc, S> {
+        self.cast()
+    }
+}
+
+impl<V, Pc, S> GuestEntry<V, Unset, Pc, S> {
+    /// Enters the guest in VS-mode.
+    #[inline]
+    pub fn supervisor(mut self) -> GuestEntry<V, Set, Pc, S> {
+        self.supervisor = true;
+        self.cast()
+    }
+    /// Enters the guest in VU-mode.
+    #[inline]
+    pub fn user(mut self) -> GuestEntry<V, Set, Pc, S> {
+        self.supervisor = false;
+        self.cast()
+    }
+}
+
+impl<V, P, S> GuestEntry<V, P, Unset, S> {
+    /// Sets the guest virtual address to start executing at.
+    #[inline]
+    pub fn with_pc(mut self, pc: usize) -> GuestEntry<V, P, Set, S> {
+        self.pc = pc;
+        self.cast()
+    }
+}
+
+impl<V, P, Pc> GuestEntry<V, P, Pc, Unset> {
+    /// Sets the guest's `vsstatus`.
+    #[inline]
+    pub fn with_vsstatus(mut self, vsstatus: vsstatus::Vsstatus) -> GuestEntry<V, P, Pc, Set> {
+        self.vsstatus = vsstatus;
+        self.cast()
+    }
+}
+
+impl GuestEntry<Set, Set, Set, Set> {
+    /// Returns the entry PC.
+    #[inline]
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+    /// Returns whether the guest is entered in VS-mode rather than VU-mode.
+    #[inline]
+    pub fn is_supervisor(&self) -> bool {
+        self.supervisor
+    }
+    /// Returns `current` with the fields for this entry applied.
+    #[inline]
+    pub fn hstatus(&self, current: hstatus::Hstatus) -> hstatus::Hstatus {
+        let mut hstatus = current;
+        hstatus.set_spv(true);
+        hstatus.set_spvp(self.supervisor);
+        hstatus
+    }
+    /// Writes the entry state to the CSRs without executing `sret`.
+    ///
+    /// For world-switch routines that restore guest registers and execute
+    /// `sret` themselves.
+    ///
+    /// # Safety
+    ///
+    /// The guest's G-stage translation and VS-level state must be installed;
+    /// the next `sret` enters the guest.
+    pub unsafe fn prepare(&self) {
+        let spp = if self.supervisor {
+            sstatus::SPP::Supervisor
+        } else {
+            sstatus::SPP::User
+        };
+        // SAFETY: Caller ensures the guest is ready to run
+        unsafe {
+            self.hstatus(hstatus::read()).write();
+            self.vsstatus.write();
+            sstatus::set_spp(spp);
+            sepc::write(self.pc);
+        }
+    }
+    /// Enters the guest.
+    ///
+    /// General-purpose registers are passed to the guest unchanged; control
+    /// comes back through the HS-mode trap vector.
+    ///
+    /// # Safety
+    ///
+    /// See [`GuestEntry::prepare`]. In addition, `stvec` must point to a trap
+    /// handler able to service guest exits.
+    pub unsafe fn enter(self) -> ! {
+        // SAFETY: Caller ensures the guest is ready to run
+        unsafe { self.prepare() };
+        #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+        // SAFETY: All entry CSRs were written by `prepare`
+        unsafe {
+            core::arch::asm!("sret", options(noreturn))
+        }
+        #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+        crate::unsupported_arch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_entry() {
+        let mut vss = vsstatus::Vsstatus::from_bits(0);
+        vss.set_sie(true);
+        let entry = GuestEntry::new()
+            .with_vsstatus(vss)
+            .user()
+            .with_pc(0x1000)
+            .virtualized();
+        assert_eq!(entry.pc(), 0x1000);
+        assert!(!entry.is_supervisor());
+
+        let hstatus = entry.hstatus(hstatus::Hstatus::from_bits(1 << 8));
+        assert!(hstatus.spv());
+        assert!(!hstatus.spvp());
+    }
+
+    #[test]
+    fn test_supervisor_entry_sets_spvp() {
+        let entry = GuestEntry::new()
+            .virtualized()
+            .supervisor()
+            .with_pc(0x8020_0000)
+            .with_vsstatus(vsstatus::Vsstatus::from_bits(0));
+        let hstatus = entry.hstatus(hstatus::Hstatus::from_bits(0));
+        assert!(hstatus.spv());
+        assert!(hstatus.spvp());
+    }
+}
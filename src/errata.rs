@@ -0,0 +1,259 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Per-Hart Errata Workarounds.
+//!
+//! Some implementations need register workarounds that the specification does
+//! not call for: extra fences after `hgatp` writes, or `hgatp` encodings that
+//! are accepted but broken. Errata are identified by the hart's
+//! `mvendorid`/`marchid`/`mimpid` triple
+//! ([`MachineId`](crate::errata::MachineId)), which HS-mode learns from the SBI
+//! base extension.
+//!
+//! [`apply_errata`](crate::errata::apply_errata) matches the hart against the
+//! built-in database and every [`Erratum`](crate::errata::Erratum) added by
+//! downstream crates through [`register`](crate::errata::register), runs their
+//! init-time fixups and returns the [`Workarounds`](crate::errata::Workarounds)
+//! hot paths must honour.
+
+use crate::register::hgatp::{Hgatp, HgatpValues};
+use bitflags::bitflags;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// Identity of a hart implementation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MachineId {
+    /// JEDEC vendor ID (`mvendorid`).
+    pub mvendorid: usize,
+    /// Microarchitecture ID (`marchid`).
+    pub marchid: usize,
+    /// Implementation version (`mimpid`).
+    pub mimpid: usize,
+}
+
+/// SBI base extension ID.
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const SBI_EXT_BASE: usize = 0x10;
+
+impl MachineId {
+    /// Queries the machine ID of the current hart from the SBI base extension.
+    pub fn from_sbi() -> Self {
+        MachineId {
+            mvendorid: sbi_base_call(4),
+            marchid: sbi_base_call(5),
+            mimpid: sbi_base_call(6),
+        }
+    }
+}
+
+#[cfg_attr(
+    not(any(target_arch = "riscv32", target_arch = "riscv64")),
+    allow(unused_variables)
+)]
+fn sbi_base_call(fid: usize) -> usize {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let value: usize;
+        // SAFETY: SBI base extension queries have no side effects
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                lateout("a0") _,
+                lateout("a1") value,
+                in("a6") fid,
+                in("a7") SBI_EXT_BASE,
+                options(nostack),
+            );
+        }
+        value
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+bitflags! {
+    /// Workarounds that hot paths must honour on an affected hart.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    pub struct Workarounds: u32 {
+        /// Issue `hfence.gvma` after every `hgatp` write.
+        const HFENCE_AFTER_HGATP_WRITE = 1 << 0;
+        /// Never use the Sv48x4 G-stage translation mode.
+        const NO_SV48X4 = 1 << 1;
+        /// Never use a non-zero VMID.
+        const NO_VMID = 1 << 2;
+    }
+}
+
+impl Workarounds {
+    /// Returns whether `hgatp` may be programmed with `mode`.
+    #[inline]
+    pub fn allows_mode(self, mode: HgatpValues) -> bool {
+        !(matches!(mode, HgatpValues::Sv48x4) && self.contains(Self::NO_SV48X4))
+    }
+    /// Writes `hgatp`, applying the VMID and fence workarounds.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it changes guest address translation.
+    pub unsafe fn write_hgatp(self, mut value: Hgatp) {
+        if self.contains(Self::NO_VMID) {
+            value.set_vmid(0);
+        }
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe {
+            value.write();
+            if self.contains(Self::HFENCE_AFTER_HGATP_WRITE) {
+                crate::asm::hfence_gvma_all();
+            }
+        }
+    }
+}
+
+/// A known implementation erratum and its workaround.
+#[derive(Copy, Clone, Debug)]
+pub struct Erratum {
+    /// Short human-readable name, e.g. for boot logs.
+    pub name: &'static str,
+    /// Affected vendor.
+    pub mvendorid: usize,
+    /// Affected microarchitecture, or `None` for all of the vendor's.
+    pub marchid: Option<usize>,
+    /// Affected implementation version, or `None` for all.
+    pub mimpid: Option<usize>,
+    /// Workarounds hot paths must honour.
+    pub workarounds: Workarounds,
+    /// One-time register fixup run by [`apply_errata`].
+    pub fixup: Option<unsafe fn()>,
+}
+
+impl Erratum {
+    /// Returns whether the erratum affects the given hart.
+    #[inline]
+    pub fn matches(&self, id: &MachineId) -> bool {
+        self.mvendorid == id.mvendorid
+            && self.marchid.is_none_or(|x| x == id.marchid)
+            && self.mimpid.is_none_or(|x| x == id.mimpid)
+    }
+}
+
+/// Errata shipped with this crate.
+///
+/// Entries are only added once an erratum is confirmed by the vendor.
+static BUILTIN: &[Erratum] = &[];
+
+/// Maximum number of errata that can be added through [`register`].
+pub const MAX_REGISTERED: usize = 16;
+
+static REGISTERED: [AtomicPtr<Erratum>; MAX_REGISTERED] =
+    [const { AtomicPtr::new(ptr::null_mut()) }; MAX_REGISTERED];
+
+/// Adds an erratum to the database consulted by [`apply_errata`].
+///
+/// Returns the erratum back if the registration table is full.
+pub fn register(erratum: &'static Erratum) -> Result<(), &'static Erratum> {
+    let new = erratum as *const Erratum as *mut Erratum;
+    for slot in &REGISTERED {
+        if slot
+            .compare_exchange(ptr::null_mut(), new, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            return Ok(());
+        }
+    }
+    Err(erratum)
+}
+
+/// Returns the built-in and registered errata.
+pub fn errata() -> impl Iterator<Item = &'static Erratum> {
+    let registered = REGISTERED.iter().filter_map(|slot| {
+        // SAFETY: Only `&'static Erratum`s are ever stored in the table
+        unsafe { slot.load(Ordering::Acquire).as_ref() }
+    });
+    BUILTIN.iter().chain(registered)
+}
+
+/// Returns the combined workarounds of every erratum affecting `id`.
+pub fn workarounds_for(id: &MachineId) -> Workarounds {
+    errata()
+        .filter(|e| e.matches(id))
+        .fold(Workarounds::empty(), |acc, e| acc | e.workarounds)
+}
+
+/// Applies every erratum affecting the current hart.
+///
+/// Runs the fixups once and returns the workarounds to keep for the hart.
+///
+/// # Safety
+///
+/// Must be called during hart initialization, before any guest runs, with
+/// `id` describing the current hart.
+pub unsafe fn apply_errata(id: &MachineId) -> Workarounds {
+    let mut workarounds = Workarounds::empty();
+    for erratum in errata().filter(|e| e.matches(id)) {
+        log::info!("applying erratum workaround: {}", erratum.name);
+        if let Some(fixup) = erratum.fixup {
+            // SAFETY: Caller ensures we are in hart initialization
+            unsafe { fixup() };
+        }
+        workarounds |= erratum.workarounds;
+    }
+    workarounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: MachineId = MachineId {
+        mvendorid: 0x5b7,
+        marchid: 0x8000_0000_0000_0007u64 as usize,
+        mimpid: 3,
+    };
+
+    static TEST_ERRATUM: Erratum = Erratum {
+        name: "test: broken sv48x4",
+        mvendorid: 0x5b7,
+        marchid: None,
+        mimpid: Some(3),
+        workarounds: Workarounds::NO_SV48X4,
+        fixup: None,
+    };
+
+    #[test]
+    fn test_matches() {
+        assert!(TEST_ERRATUM.matches(&ID));
+        assert!(!TEST_ERRATUM.matches(&MachineId { mimpid: 4, ..ID }));
+        assert!(!TEST_ERRATUM.matches(&MachineId { mvendorid: 0, ..ID }));
+    }
+
+    #[test]
+    fn test_register() {
+        register(&TEST_ERRATUM).unwrap();
+        assert!(errata().any(|e| ptr::eq(e, &TEST_ERRATUM)));
+        assert_eq!(workarounds_for(&ID), Workarounds::NO_SV48X4);
+        assert_eq!(
+            workarounds_for(&MachineId { mvendorid: 1, ..ID }),
+            Workarounds::empty()
+        );
+    }
+
+    #[test]
+    fn test_allows_mode() {
+        let w = Workarounds::NO_SV48X4;
+        assert!(w.allows_mode(HgatpValues::Sv39x4));
+        assert!(!w.allows_mode(HgatpValues::Sv48x4));
+        assert!(Workarounds::empty().allows_mode(HgatpValues::Sv48x4));
+    }
+}
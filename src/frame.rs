@@ -0,0 +1,316 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest Trap Frame.
+//!
+//! [`HTrapFrame`](crate::frame::HTrapFrame) is the canonical layout of the
+//! guest state saved on a trap into HS-mode: the 31 general-purpose registers
+//! `x1..x31` followed by `sepc`, `sstatus` and `hstatus`. The
+//! [`save_gprs!`](crate::save_gprs), [`restore_gprs!`](crate::restore_gprs),
+//! [`save_csrs!`](crate::save_csrs) and [`restore_csrs!`](crate::restore_csrs)
+//! macros expand to assembly text for that layout, so a world-switch routine
+//! written with them, the GDB stub and the SBI helpers all use the same
+//! offsets.
+//!
+//! The macros take the number of the base register holding the frame address,
+//! e.g. `2` for `sp`:
+//!
+//! ```ignore
+//! core::arch::global_asm!(
+//!     ".global guest_exit",
+//!     "guest_exit:",
+//!     "csrrw sp, sscratch, sp",
+//!     riscv_h::save_gprs!(2),
+//!     riscv_h::save_csrs!(2, 5),
+//!     // ...
+//!     riscv_h::restore_csrs!(2, 5),
+//!     riscv_h::restore_gprs!(2),
+//!     "sret",
+//! );
+//! ```
+//!
+//! `save_gprs!` stores the base register's current value, so a routine that
+//! swaps in the frame address first must fix up that slot itself.
+//! `restore_gprs!` loads the base register last.
+
+use crate::abi::GuestAbiFrame;
+use crate::gdb::GdbRegister;
+use core::mem::{offset_of, size_of};
+
+/// Guest registers saved on a trap into HS-mode.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HTrapFrame {
+    /// General-purpose registers `x1..x31`.
+    pub gprs: [usize; 31],
+    /// Guest program counter.
+    pub sepc: usize,
+    /// `sstatus` at the time of the trap.
+    pub sstatus: usize,
+    /// `hstatus` at the time of the trap.
+    pub hstatus: usize,
+}
+
+impl HTrapFrame {
+    /// Byte offset of `sepc`.
+    pub const SEPC: usize = offset_of!(HTrapFrame, sepc);
+    /// Byte offset of `sstatus`.
+    pub const SSTATUS: usize = offset_of!(HTrapFrame, sstatus);
+    /// Byte offset of `hstatus`.
+    pub const HSTATUS: usize = offset_of!(HTrapFrame, hstatus);
+
+    /// Returns the byte offset of `x<n>` (`1 <= n < 32`).
+    #[inline]
+    pub const fn gpr_offset(n: usize) -> usize {
+        (n - 1) * size_of::<usize>()
+    }
+    /// Returns `x<n>`; `x0` reads as zero.
+    #[inline]
+    pub fn gpr(&self, n: usize) -> usize {
+        if n == 0 { 0 } else { self.gprs[n - 1] }
+    }
+    /// Sets `x<n>`; writes to `x0` are ignored.
+    #[inline]
+    pub fn set_gpr(&mut self, n: usize, value: usize) {
+        if n != 0 {
+            self.gprs[n - 1] = value;
+        }
+    }
+    /// Returns `x0..x31`, e.g. for a crash dump.
+    #[inline]
+    pub fn all_gprs(&self) -> [usize; 32] {
+        core::array::from_fn(|n| self.gpr(n))
+    }
+    /// Returns a general-purpose register or the PC for a GDB stub.
+    #[inline]
+    pub fn gdb_register(&self, reg: GdbRegister) -> Option<usize> {
+        match reg {
+            GdbRegister::Gpr(n) => Some(self.gpr(n as usize)),
+            GdbRegister::Pc => Some(self.sepc),
+            _ => None,
+        }
+    }
+}
+
+impl GuestAbiFrame for HTrapFrame {
+    #[inline]
+    fn arg(&self, n: usize) -> usize {
+        self.gpr(10 + n)
+    }
+    #[inline]
+    fn set_arg(&mut self, n: usize, value: usize) {
+        self.set_gpr(10 + n, value);
+    }
+    #[inline]
+    fn pc(&self) -> usize {
+        self.sepc
+    }
+    #[inline]
+    fn set_pc(&mut self, pc: usize) {
+        self.sepc = pc;
+    }
+}
+
+// The assembly generated by the macros below hard-codes this layout.
+const _: () = {
+    let xlenb = size_of::<usize>();
+    assert!(HTrapFrame::gpr_offset(31) == 30 * xlenb);
+    assert!(HTrapFrame::SEPC == 31 * xlenb);
+    assert!(HTrapFrame::SSTATUS == 32 * xlenb);
+    assert!(HTrapFrame::HSTATUS == 33 * xlenb);
+    assert!(size_of::<HTrapFrame>() == 34 * xlenb);
+};
+
+#[cfg(target_pointer_width = "64")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __frame_xlen {
+    (store) => {
+        "sd"
+    };
+    (load) => {
+        "ld"
+    };
+    (bytes) => {
+        "8"
+    };
+}
+
+#[cfg(target_pointer_width = "32")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __frame_xlen {
+    (store) => {
+        "sw"
+    };
+    (load) => {
+        "lw"
+    };
+    (bytes) => {
+        "4"
+    };
+}
+
+/// Expands to assembly storing `x1..x31` into the
+/// [`HTrapFrame`](crate::frame::HTrapFrame) addressed by register `x<base>`.
+#[macro_export]
+macro_rules! save_gprs {
+    ($base:literal) => {
+        concat!(
+            ".irp n, 1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31\n",
+            $crate::__frame_xlen!(store), " x\\n, (\\n - 1) * ", $crate::__frame_xlen!(bytes), "(x", stringify!($base), ")\n",
+            ".endr\n",
+        )
+    };
+}
+
+/// Expands to assembly loading `x1..x31` from the
+/// [`HTrapFrame`](crate::frame::HTrapFrame) addressed by register `x<base>`,
+/// loading `x<base>` itself last.
+#[macro_export]
+macro_rules! restore_gprs {
+    ($base:literal) => {
+        concat!(
+            ".irp n, 1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31\n",
+            ".if \\n - ", stringify!($base), "\n",
+            $crate::__frame_xlen!(load), " x\\n, (\\n - 1) * ", $crate::__frame_xlen!(bytes), "(x", stringify!($base), ")\n",
+            ".endif\n",
+            ".endr\n",
+            $crate::__frame_xlen!(load), " x", stringify!($base), ", (", stringify!($base), " - 1) * ",
+            $crate::__frame_xlen!(bytes), "(x", stringify!($base), ")\n",
+        )
+    };
+}
+
+/// Expands to assembly storing `sepc`, `sstatus` and `hstatus` into the
+/// [`HTrapFrame`](crate::frame::HTrapFrame) addressed by register `x<base>`,
+/// clobbering `x<tmp>`.
+#[macro_export]
+macro_rules! save_csrs {
+    ($base:literal, $tmp:literal) => {
+        concat!(
+            "csrr x",
+            stringify!($tmp),
+            ", sepc\n",
+            $crate::__frame_xlen!(store),
+            " x",
+            stringify!($tmp),
+            ", 31 * ",
+            $crate::__frame_xlen!(bytes),
+            "(x",
+            stringify!($base),
+            ")\n",
+            "csrr x",
+            stringify!($tmp),
+            ", sstatus\n",
+            $crate::__frame_xlen!(store),
+            " x",
+            stringify!($tmp),
+            ", 32 * ",
+            $crate::__frame_xlen!(bytes),
+            "(x",
+            stringify!($base),
+            ")\n",
+            "csrr x",
+            stringify!($tmp),
+            ", hstatus\n",
+            $crate::__frame_xlen!(store),
+            " x",
+            stringify!($tmp),
+            ", 33 * ",
+            $crate::__frame_xlen!(bytes),
+            "(x",
+            stringify!($base),
+            ")\n",
+        )
+    };
+}
+
+/// Expands to assembly loading `sepc`, `sstatus` and `hstatus` from the
+/// [`HTrapFrame`](crate::frame::HTrapFrame) addressed by register `x<base>`,
+/// clobbering `x<tmp>`.
+#[macro_export]
+macro_rules! restore_csrs {
+    ($base:literal, $tmp:literal) => {
+        concat!(
+            $crate::__frame_xlen!(load),
+            " x",
+            stringify!($tmp),
+            ", 31 * ",
+            $crate::__frame_xlen!(bytes),
+            "(x",
+            stringify!($base),
+            ")\n",
+            "csrw sepc, x",
+            stringify!($tmp),
+            "\n",
+            $crate::__frame_xlen!(load),
+            " x",
+            stringify!($tmp),
+            ", 32 * ",
+            $crate::__frame_xlen!(bytes),
+            "(x",
+            stringify!($base),
+            ")\n",
+            "csrw sstatus, x",
+            stringify!($tmp),
+            "\n",
+            $crate::__frame_xlen!(load),
+            " x",
+            stringify!($tmp),
+            ", 33 * ",
+            $crate::__frame_xlen!(bytes),
+            "(x",
+            stringify!($base),
+            ")\n",
+            "csrw hstatus, x",
+            stringify!($tmp),
+            "\n",
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpr_access() {
+        let mut frame = HTrapFrame::default();
+        frame.set_gpr(0, 1);
+        frame.set_gpr(10, 0xa0);
+        frame.set_gpr(31, 0x1f);
+        assert_eq!(frame.gpr(0), 0);
+        assert_eq!(frame.arg(0), 0xa0);
+        assert_eq!(frame.all_gprs()[31], 0x1f);
+        assert_eq!(HTrapFrame::gpr_offset(10), 9 * size_of::<usize>());
+
+        frame.sepc = 0x8020_0000;
+        assert_eq!(frame.gdb_register(GdbRegister::Pc), Some(0x8020_0000));
+        assert_eq!(frame.gdb_register(GdbRegister::Gpr(10)), Some(0xa0));
+        assert_eq!(frame.gdb_register(GdbRegister::Priv), None);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_asm_text() {
+        let save = crate::save_gprs!(2);
+        assert!(save.contains("sd x\\n, (\\n - 1) * 8(x2)"));
+        let restore = crate::restore_gprs!(2);
+        assert!(restore.contains(".if \\n - 2\n"));
+        assert!(restore.ends_with("ld x2, (2 - 1) * 8(x2)\n"));
+        assert!(crate::save_csrs!(2, 5).contains("sd x5, 31 * 8(x2)\n"));
+        assert!(crate::restore_csrs!(2, 5).ends_with("csrw hstatus, x5\n"));
+    }
+}
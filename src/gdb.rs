@@ -0,0 +1,402 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! GDB Remote-Protocol Register Mapping.
+//!
+//! GDB numbers RISC-V registers as `x0..x31` (0..=31), `pc` (32), `f0..f31`
+//! (33..=64) and then every CSR at `65 + csr_number`. This module maps the
+//! hypervisor and VS-level CSRs into that numbering, generates the matching
+//! `org.gnu.gdb.riscv.csr` target description and dispatches register reads
+//! and writes by GDB register number, so a gdbstub can expose guest state with
+//! little glue.
+//!
+//! CSR values come from a [`GuestCsrs`](crate::gdb::GuestCsrs) implementation:
+//! [`HardwareCsrs`](crate::gdb::HardwareCsrs) for a guest currently loaded on
+//! the hart, or the hypervisor's own saved context for a descheduled guest.
+//! CSRs of extensions the hart lacks are reported as unavailable and left out
+//! of the target description.
+
+use crate::hext::HExtInfo;
+use crate::register::{
+    hcontext, hcounteren, hedeleg, henvcfg, hgatp, hgeie, hgeip, hideleg, hie, hip, hstateen0,
+    hstateen1, hstateen2, hstateen3, hstatus, htimedelta, htinst, htval, hvictl, hvien, hvip,
+    hviprio1, hviprio2, vsatp, vscause, vsepc, vsie, vsip, vsscratch, vsstatus, vstimecmp, vstopei,
+    vstopi, vstval, vstvec,
+};
+use core::fmt;
+
+/// GDB register number of `pc`.
+pub const PC_REGNUM: usize = 32;
+/// GDB register number of `f0`.
+pub const FIRST_FP_REGNUM: usize = 33;
+/// GDB register number of CSR 0.
+pub const FIRST_CSR_REGNUM: usize = 65;
+/// GDB register number of the virtual `priv` register.
+pub const PRIV_REGNUM: usize = FIRST_CSR_REGNUM + 4096;
+
+/// A register in GDB's RISC-V numbering.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GdbRegister {
+    /// General-purpose register `x0..x31`.
+    Gpr(u8),
+    /// Program counter.
+    Pc,
+    /// Floating-point register `f0..f31`.
+    Fpr(u8),
+    /// Control and status register.
+    Csr(u16),
+    /// Virtual register holding the current privilege level.
+    Priv,
+}
+
+impl GdbRegister {
+    /// Decodes a GDB register number.
+    pub fn from_regnum(regnum: usize) -> Option<Self> {
+        match regnum {
+            0..PC_REGNUM => Some(Self::Gpr(regnum as u8)),
+            PC_REGNUM => Some(Self::Pc),
+            FIRST_FP_REGNUM..FIRST_CSR_REGNUM => Some(Self::Fpr((regnum - FIRST_FP_REGNUM) as u8)),
+            FIRST_CSR_REGNUM..PRIV_REGNUM => Some(Self::Csr((regnum - FIRST_CSR_REGNUM) as u16)),
+            PRIV_REGNUM => Some(Self::Priv),
+            _ => None,
+        }
+    }
+    /// Returns the GDB register number.
+    pub fn regnum(self) -> usize {
+        match self {
+            Self::Gpr(n) => n as usize,
+            Self::Pc => PC_REGNUM,
+            Self::Fpr(n) => FIRST_FP_REGNUM + n as usize,
+            Self::Csr(csr) => FIRST_CSR_REGNUM + csr as usize,
+            Self::Priv => PRIV_REGNUM,
+        }
+    }
+}
+
+/// Hypervisor and VS-level CSRs exposed to GDB, as `(name, csr number)`.
+pub static GUEST_CSRS: &[(&str, u16)] = &[
+    ("vsstatus", 0x200),
+    ("vsie", 0x204),
+    ("vstvec", 0x205),
+    ("vsscratch", 0x240),
+    ("vsepc", 0x241),
+    ("vscause", 0x242),
+    ("vstval", 0x243),
+    ("vsip", 0x244),
+    ("vstimecmp", 0x24D),
+    ("vstopei", 0x25C),
+    ("vsatp", 0x280),
+    ("hstatus", 0x600),
+    ("hedeleg", 0x602),
+    ("hideleg", 0x603),
+    ("hie", 0x604),
+    ("htimedelta", 0x605),
+    ("hcounteren", 0x606),
+    ("hgeie", 0x607),
+    ("hvien", 0x608),
+    ("hvictl", 0x609),
+    ("henvcfg", 0x60A),
+    ("hstateen0", 0x60C),
+    ("hstateen1", 0x60D),
+    ("hstateen2", 0x60E),
+    ("hstateen3", 0x60F),
+    ("htval", 0x643),
+    ("hip", 0x644),
+    ("hvip", 0x645),
+    ("hviprio1", 0x646),
+    ("hviprio2", 0x647),
+    ("htinst", 0x64A),
+    ("hgatp", 0x680),
+    ("hcontext", 0x6A8),
+    ("hgeip", 0xE12),
+    ("vstopi", 0xEB0),
+];
+
+/// Returns the GDB name of an exposed CSR.
+pub fn csr_name(csr: u16) -> Option<&'static str> {
+    GUEST_CSRS
+        .iter()
+        .find(|&&(_, number)| number == csr)
+        .map(|&(name, _)| name)
+}
+
+/// Source of guest CSR values for GDB register accesses.
+pub trait GuestCsrs {
+    /// Returns the value of `csr`, or `None` if it is not available.
+    fn read_csr(&self, csr: u16) -> Option<usize>;
+    /// Sets `csr` to `value`; returns `false` if it is not writable.
+    fn write_csr(&mut self, csr: u16, value: usize) -> bool;
+    /// Returns whether `csr` exists for this guest and belongs in the target
+    /// description.
+    fn has_csr(&self, csr: u16) -> bool {
+        let _ = csr;
+        true
+    }
+}
+
+/// Reads an exposed CSR by GDB register number.
+///
+/// Returns `None` for registers other than the CSRs in [`GUEST_CSRS`].
+pub fn read_register<C: GuestCsrs + ?Sized>(csrs: &C, regnum: usize) -> Option<usize> {
+    match GdbRegister::from_regnum(regnum)? {
+        GdbRegister::Csr(csr) if csr_name(csr).is_some() => csrs.read_csr(csr),
+        _ => None,
+    }
+}
+
+/// Writes an exposed CSR by GDB register number.
+///
+/// Returns `false` for registers other than the CSRs in [`GUEST_CSRS`].
+pub fn write_register<C: GuestCsrs + ?Sized>(csrs: &mut C, regnum: usize, value: usize) -> bool {
+    match GdbRegister::from_regnum(regnum) {
+        Some(GdbRegister::Csr(csr)) if csr_name(csr).is_some() => csrs.write_csr(csr, value),
+        _ => false,
+    }
+}
+
+/// Writes the `org.gnu.gdb.riscv.csr` target description feature for the
+/// CSRs in [`GUEST_CSRS`] that `csrs` has, with `xlen`-bit registers.
+pub fn write_csr_feature<W: fmt::Write, C: GuestCsrs + ?Sized>(
+    w: &mut W,
+    csrs: &C,
+    xlen: usize,
+) -> fmt::Result {
+    w.write_str("<?xml version=\"1.0\"?>\n")?;
+    w.write_str("<!DOCTYPE feature SYSTEM \"gdb-target.dtd\">\n")?;
+    w.write_str("<feature name=\"org.gnu.gdb.riscv.csr\">\n")?;
+    for &(name, csr) in GUEST_CSRS.iter().filter(|&&(_, csr)| csrs.has_csr(csr)) {
+        writeln!(
+            w,
+            "  <reg name=\"{}\" bitsize=\"{}\" regnum=\"{}\" save-restore=\"no\" type=\"int\" group=\"csr\"/>",
+            name,
+            xlen,
+            GdbRegister::Csr(csr).regnum()
+        )?;
+    }
+    w.write_str("</feature>\n")
+}
+
+/// The CSRs of the guest currently loaded on this hart.
+///
+/// Only the CSRs that `info` reports as implemented are accessed.
+#[derive(Debug)]
+pub struct HardwareCsrs {
+    info: HExtInfo,
+}
+
+impl HardwareCsrs {
+    /// Creates an accessor for the live CSRs of a hart with the capabilities
+    /// `info`.
+    ///
+    /// # Safety
+    ///
+    /// Writes go straight to the hardware CSRs; the caller must ensure the
+    /// debugged guest is the one loaded on this hart and is not running, and
+    /// `info` must have been probed on this hart.
+    pub unsafe fn new(info: &HExtInfo) -> Self {
+        HardwareCsrs { info: *info }
+    }
+}
+
+impl GuestCsrs for HardwareCsrs {
+    fn has_csr(&self, csr: u16) -> bool {
+        self.info.implements_csr(csr)
+    }
+    fn read_csr(&self, csr: u16) -> Option<usize> {
+        if !self.has_csr(csr) {
+            return None;
+        }
+        Some(match csr {
+            0x200 => vsstatus::read().bits(),
+            0x204 => vsie::read().bits(),
+            0x205 => vstvec::read().bits(),
+            0x240 => vsscratch::read(),
+            0x241 => vsepc::read().bits(),
+            0x242 => vscause::read().bits(),
+            0x243 => vstval::read(),
+            0x244 => vsip::read().bits(),
+            0x24D => vstimecmp::read().bits(),
+            // vstopei traps unless hstatus.VGEIN selects a guest file
+            0x25C => match hstatus::read().vgein() {
+                Some(n) if n.get() <= self.info.geilen() => vstopei::read().bits(),
+                _ => return None,
+            },
+            0x280 => vsatp::read().bits(),
+            0x600 => hstatus::read().bits(),
+            0x602 => hedeleg::read().bits(),
+            0x603 => hideleg::read().bits(),
+            0x604 => hie::read().bits(),
+            0x605 => htimedelta::read(),
+            0x606 => hcounteren::read().bits(),
+            0x607 => hgeie::read().bits(),
+            0x608 => hvien::read().bits(),
+            0x609 => hvictl::read().bits(),
+            0x60A => henvcfg::read().bits(),
+            0x60C => hstateen0::read().bits(),
+            0x60D => hstateen1::read().bits(),
+            0x60E => hstateen2::read().bits(),
+            0x60F => hstateen3::read().bits(),
+            0x643 => htval::read().bits(),
+            0x644 => hip::read().bits(),
+            0x645 => hvip::read().bits(),
+            0x646 => hviprio1::read().bits(),
+            0x647 => hviprio2::read().bits(),
+            0x64A => htinst::read().bits(),
+            0x680 => hgatp::read().bits(),
+            0x6A8 => hcontext::read().bits(),
+            0xE12 => hgeip::read().bits(),
+            0xEB0 => vstopi::read().bits(),
+            _ => return None,
+        })
+    }
+    fn write_csr(&mut self, csr: u16, value: usize) -> bool {
+        if !self.has_csr(csr) {
+            return false;
+        }
+        // SAFETY: `HardwareCsrs::new` guarantees the debugged guest is loaded
+        unsafe {
+            match csr {
+                0x200 => vsstatus::Vsstatus::from_bits(value).write(),
+                0x204 => vsie::Vsie::from_bits(value).write(),
+                0x205 => vstvec::Vstvec::from_bits(value).write(),
+                0x240 => vsscratch::write(value),
+                0x241 => vsepc::Vsepc::from_bits(value).write(vsepc::Ialign::Bits16),
+                0x242 => vscause::Vscause::from_bits(value).write(),
+                0x243 => vstval::write(value),
+                0x244 => vsip::Vsip::from_bits(value).write(),
+                0x24D => vstimecmp::Vstimecmp::from_bits(value).write(),
+                0x280 => vsatp::Vsatp::from_bits(value).write(),
+                0x600 => hstatus::Hstatus::from_bits(value).write(),
+                0x602 => hedeleg::Hedeleg::from_bits(value).write(),
+                0x603 => hideleg::Hideleg::from_bits(value).write(),
+                0x604 => hie::Hie::from_bits(value).write(),
+                0x605 => htimedelta::write(value),
+                0x606 => hcounteren::Hcounteren::from_bits(value).write(),
+                0x607 => hgeie::Hgeie::from_bits(value).write(),
+                0x608 => hvien::Hvien::from_bits(value).write(),
+                0x609 => hvictl::Hvictl::from_bits(value).write(),
+                0x60A => henvcfg::Henvcfg::from_bits(value).write(),
+                0x60C => hstateen0::Hstateen0::from_bits(value).write(),
+                0x60D => hstateen1::Hstateen1::from_bits(value).write(),
+                0x60E => hstateen2::Hstateen2::from_bits(value).write(),
+                0x60F => hstateen3::Hstateen3::from_bits(value).write(),
+                0x643 => htval::Htval::from_bits(value).write(),
+                0x644 => hip::Hip::from_bits(value).write(),
+                0x645 => hvip::Hvip::from_bits(value).write(),
+                0x646 => hviprio1::Hviprio1::from_bits(value).write(),
+                0x647 => hviprio2::Hviprio2::from_bits(value).write(),
+                0x64A => htinst::Htinst::from_bits(value).write(),
+                0x680 => hgatp::Hgatp::from_bits(value).write(),
+                0x6A8 => hcontext::Hcontext::from_bits(value).write(),
+                // Writing vstopei claims the top interrupt, so it is left
+                // out like the read-only hgeip and vstopi
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::string::String;
+
+    struct Saved {
+        vsepc: usize,
+    }
+
+    impl GuestCsrs for Saved {
+        fn read_csr(&self, csr: u16) -> Option<usize> {
+            (csr == 0x241).then_some(self.vsepc)
+        }
+        fn write_csr(&mut self, csr: u16, value: usize) -> bool {
+            if csr == 0x241 {
+                self.vsepc = value;
+            }
+            csr == 0x241
+        }
+    }
+
+    #[test]
+    fn test_regnum_round_trip() {
+        for regnum in [0, 31, 32, 33, 64, 65, 65 + 0x600, PRIV_REGNUM] {
+            let reg = GdbRegister::from_regnum(regnum).unwrap();
+            assert_eq!(reg.regnum(), regnum);
+        }
+        assert_eq!(GdbRegister::from_regnum(5), Some(GdbRegister::Gpr(5)));
+        assert_eq!(GdbRegister::from_regnum(40), Some(GdbRegister::Fpr(7)));
+        assert_eq!(
+            GdbRegister::from_regnum(65 + 0x241),
+            Some(GdbRegister::Csr(0x241))
+        );
+        assert_eq!(GdbRegister::from_regnum(PRIV_REGNUM + 1), None);
+    }
+
+    #[test]
+    fn test_dispatch() {
+        let mut saved = Saved { vsepc: 0x8020_0000 };
+        let regnum = FIRST_CSR_REGNUM + 0x241;
+        assert_eq!(read_register(&saved, regnum), Some(0x8020_0000));
+        assert!(write_register(&mut saved, regnum, 0x1000));
+        assert_eq!(saved.vsepc, 0x1000);
+
+        // Non-CSR and unexposed CSR register numbers
+        assert_eq!(read_register(&saved, PC_REGNUM), None);
+        assert_eq!(read_register(&saved, FIRST_CSR_REGNUM + 0x100), None);
+        assert!(!write_register(&mut saved, 3, 0));
+    }
+
+    #[test]
+    fn test_csr_feature() {
+        let mut xml = String::new();
+        write_csr_feature(&mut xml, &Saved { vsepc: 0 }, 64).unwrap();
+        assert!(xml.contains("<feature name=\"org.gnu.gdb.riscv.csr\">"));
+        assert!(xml.contains("<reg name=\"hstatus\" bitsize=\"64\" regnum=\"1601\""));
+        assert_eq!(xml.matches("<reg ").count(), GUEST_CSRS.len());
+        assert!(xml.ends_with("</feature>\n"));
+    }
+
+    #[test]
+    fn test_csr_feature_skips_missing_csrs() {
+        struct NoAia;
+
+        impl GuestCsrs for NoAia {
+            fn read_csr(&self, _csr: u16) -> Option<usize> {
+                None
+            }
+            fn write_csr(&mut self, _csr: u16, _value: usize) -> bool {
+                false
+            }
+            fn has_csr(&self, csr: u16) -> bool {
+                !matches!(csr, 0x25C | 0x608 | 0x609 | 0x646 | 0x647 | 0xEB0)
+            }
+        }
+
+        let mut xml = String::new();
+        write_csr_feature(&mut xml, &NoAia, 64).unwrap();
+        assert!(xml.contains("<reg name=\"hstatus\""));
+        assert!(!xml.contains("<reg name=\"vstopei\""));
+        assert!(!xml.contains("<reg name=\"hvictl\""));
+        assert_eq!(xml.matches("<reg ").count(), GUEST_CSRS.len() - 6);
+    }
+
+    #[test]
+    fn test_csr_name() {
+        assert_eq!(csr_name(0x680), Some("hgatp"));
+        assert_eq!(csr_name(0x100), None);
+    }
+}
@@ -0,0 +1,508 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fault-Tolerant Guest Memory Access.
+//!
+//! [`copy_from_guest`](crate::guest_mem::copy_from_guest) and
+//! [`copy_to_guest`](crate::guest_mem::copy_to_guest) move bytes between
+//! hypervisor buffers and guest virtual memory with HLV/HSV instructions, so
+//! both translation stages of the current guest apply. The copy proceeds in the
+//! largest naturally aligned chunks that fit, up to XLEN bits.
+//!
+//! A guest-page fault or access fault does not reach the hypervisor's trap
+//! handler: each access runs with `sstatus.SIE` cleared and `stvec` pointing at
+//! a local recovery path, which records the fault and restores `stvec`, `sepc`,
+//! `hstatus` and `sstatus`. `scause`, `stval`, `htval` and `htinst` are
+//! clobbered. The fault is then offered to the hook registered with
+//! [`set_fixup_hook`](crate::guest_mem::set_fixup_hook), which may fix the
+//! mapping (e.g. populate a G-stage page) and ask for the access to be retried
+//! once.
+//!
+//! On top of the byte copies, [`read_guest`](crate::guest_mem::read_guest) and
+//! [`write_guest`](crate::guest_mem::write_guest) transfer plain-data values
+//! such as SBI argument blocks, and
+//! [`read_guest_cstr`](crate::guest_mem::read_guest_cstr) reads a
+//! NUL-terminated string into a bounded buffer.
+
+use crate::mmio::PAGE_SIZE;
+use crate::once::Once;
+use core::ffi::CStr;
+use core::mem::{MaybeUninit, size_of};
+
+/// A guest access that faulted during a copy.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GuestAccessFault {
+    /// Guest virtual address of the faulting access.
+    pub gva: usize,
+    /// Exception cause reported for the fault.
+    pub scause: usize,
+    /// Guest physical address shifted right by two, as reported in `htval`,
+    /// or zero if unknown.
+    pub htval: usize,
+    /// Number of bytes copied before the fault.
+    pub copied: usize,
+}
+
+/// What to do about a faulting guest access.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Fixup {
+    /// The hook resolved the fault; retry the access once.
+    Retry,
+    /// Abort the copy and return the fault.
+    Fail,
+}
+
+/// Hook consulted for every faulting guest access.
+pub type FixupHook = fn(&GuestAccessFault) -> Fixup;
+
+static FIXUP_HOOK: Once<FixupHook> = Once::new();
+
+/// Registers the fixup hook for all harts.
+///
+/// Returns the hook back if one was already registered.
+pub fn set_fixup_hook(hook: FixupHook) -> Result<(), FixupHook> {
+    FIXUP_HOOK.set(hook)
+}
+
+/// Returns the width in bytes of the next chunk at `gva` with `remaining`
+/// bytes left: the largest naturally aligned power of two up to XLEN bits.
+#[inline]
+pub fn chunk_width(gva: usize, remaining: usize) -> usize {
+    let mut width = core::mem::size_of::<usize>();
+    while width > 1 && (gva & (width - 1) != 0 || remaining < width) {
+        width /= 2;
+    }
+    width
+}
+
+/// Trap recorded by a guarded access: `scause` and `htval`.
+type Trap = (usize, usize);
+
+/// Runs `access(offset, gva, width)` over `len` bytes at `gva` chunk by chunk.
+fn copy_chunks(
+    gva: usize,
+    len: usize,
+    hook: Option<FixupHook>,
+    mut access: impl FnMut(usize, usize, usize) -> Result<(), Trap>,
+) -> Result<usize, GuestAccessFault> {
+    let mut copied = 0;
+    let mut retried = false;
+    while copied < len {
+        let addr = gva.wrapping_add(copied);
+        let width = chunk_width(addr, len - copied);
+        match access(copied, addr, width) {
+            Ok(()) => {
+                copied += width;
+                retried = false;
+            }
+            Err((scause, htval)) => {
+                let fault = GuestAccessFault {
+                    gva: addr,
+                    scause,
+                    htval,
+                    copied,
+                };
+                match hook.map(|hook| hook(&fault)) {
+                    Some(Fixup::Retry) if !retried => retried = true,
+                    _ => return Err(fault),
+                }
+            }
+        }
+    }
+    Ok(len)
+}
+
+macro_rules! guarded_load {
+    ($name:ident, $insn:literal) => {
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        unsafe fn $name(gva: usize) -> Result<usize, Trap> {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            {
+                let (value, scause, htval): (usize, usize, usize);
+                // SAFETY: Caller ensures the guest translation is loaded; the
+                // local trap vector catches faults of the access
+                unsafe {
+                    core::arch::asm!(
+                        "csrrci {sstatus}, sstatus, 2",
+                        "csrr {hstatus}, hstatus",
+                        "csrr {sepc}, sepc",
+                        "la {tmp}, 2f",
+                        "csrrw {stvec}, stvec, {tmp}",
+                        "li {scause}, 0",
+                        with_arch!("+h", concat!($insn, " {value}, ({gva})")),
+                        "j 3f",
+                        ".align 2",
+                        "2:",
+                        "csrr {scause}, scause",
+                        "csrr {htval}, htval",
+                        "3:",
+                        "csrw stvec, {stvec}",
+                        "csrw sepc, {sepc}",
+                        "csrw hstatus, {hstatus}",
+                        "csrw sstatus, {sstatus}",
+                        gva = in(reg) gva,
+                        value = out(reg) value,
+                        scause = out(reg) scause,
+                        htval = out(reg) htval,
+                        sstatus = out(reg) _,
+                        hstatus = out(reg) _,
+                        sepc = out(reg) _,
+                        stvec = out(reg) _,
+                        tmp = out(reg) _,
+                        options(nostack),
+                    )
+                };
+                if scause == 0 { Ok(value) } else { Err((scause, htval)) }
+            }
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+macro_rules! guarded_store {
+    ($name:ident, $insn:literal) => {
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        unsafe fn $name(gva: usize, value: usize) -> Result<(), Trap> {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            {
+                let (scause, htval): (usize, usize);
+                // SAFETY: Caller ensures the guest translation is loaded; the
+                // local trap vector catches faults of the access
+                unsafe {
+                    core::arch::asm!(
+                        "csrrci {sstatus}, sstatus, 2",
+                        "csrr {hstatus}, hstatus",
+                        "csrr {sepc}, sepc",
+                        "la {tmp}, 2f",
+                        "csrrw {stvec}, stvec, {tmp}",
+                        "li {scause}, 0",
+                        with_arch!("+h", concat!($insn, " {value}, ({gva})")),
+                        "j 3f",
+                        ".align 2",
+                        "2:",
+                        "csrr {scause}, scause",
+                        "csrr {htval}, htval",
+                        "3:",
+                        "csrw stvec, {stvec}",
+                        "csrw sepc, {sepc}",
+                        "csrw hstatus, {hstatus}",
+                        "csrw sstatus, {sstatus}",
+                        gva = in(reg) gva,
+                        value = in(reg) value,
+                        scause = out(reg) scause,
+                        htval = out(reg) htval,
+                        sstatus = out(reg) _,
+                        hstatus = out(reg) _,
+                        sepc = out(reg) _,
+                        stvec = out(reg) _,
+                        tmp = out(reg) _,
+                        options(nostack),
+                    )
+                };
+                if scause == 0 { Ok(()) } else { Err((scause, htval)) }
+            }
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+guarded_load!(load_u8, "hlv.bu");
+guarded_load!(load_u16, "hlv.hu");
+#[cfg(target_arch = "riscv32")]
+guarded_load!(load_u32, "hlv.w");
+#[cfg(not(target_arch = "riscv32"))]
+guarded_load!(load_u32, "hlv.wu");
+#[cfg(not(target_arch = "riscv32"))]
+guarded_load!(load_u64, "hlv.d");
+
+guarded_store!(store_u8, "hsv.b");
+guarded_store!(store_u16, "hsv.h");
+guarded_store!(store_u32, "hsv.w");
+#[cfg(not(target_arch = "riscv32"))]
+guarded_store!(store_u64, "hsv.d");
+
+/// Copies `dst.len()` bytes from guest virtual address `gva` into `dst`.
+///
+/// Returns the number of bytes copied, or the fault that stopped the copy;
+/// `dst` then holds the bytes copied so far.
+///
+/// # Safety
+///
+/// Must be called in HS-mode with `hgatp`, `vsatp` and `hstatus.SPVP`
+/// describing the guest access. Clobbers `scause`, `stval`, `htval` and
+/// `htinst`.
+pub unsafe fn copy_from_guest(dst: &mut [u8], gva: usize) -> Result<usize, GuestAccessFault> {
+    copy_chunks(gva, dst.len(), FIXUP_HOOK.get(), |offset, gva, width| {
+        let dst = &mut dst[offset..offset + width];
+        // SAFETY: Caller ensures the guest access is valid to attempt
+        unsafe {
+            match width {
+                1 => dst[0] = load_u8(gva)? as u8,
+                2 => dst.copy_from_slice(&(load_u16(gva)? as u16).to_ne_bytes()),
+                4 => dst.copy_from_slice(&(load_u32(gva)? as u32).to_ne_bytes()),
+                #[cfg(not(target_arch = "riscv32"))]
+                8 => dst.copy_from_slice(&(load_u64(gva)? as u64).to_ne_bytes()),
+                _ => unreachable!(),
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Copies `src` to guest virtual address `gva`.
+///
+/// Returns the number of bytes copied, or the fault that stopped the copy.
+///
+/// # Safety
+///
+/// Must be called in HS-mode with `hgatp`, `vsatp` and `hstatus.SPVP`
+/// describing the guest access. Clobbers `scause`, `stval`, `htval` and
+/// `htinst`.
+pub unsafe fn copy_to_guest(src: &[u8], gva: usize) -> Result<usize, GuestAccessFault> {
+    copy_chunks(gva, src.len(), FIXUP_HOOK.get(), |offset, gva, width| {
+        let src = &src[offset..offset + width];
+        // SAFETY: Caller ensures the guest access is valid to attempt
+        unsafe {
+            match width {
+                1 => store_u8(gva, src[0] as usize),
+                2 => store_u16(gva, u16::from_ne_bytes([src[0], src[1]]) as usize),
+                4 => store_u32(gva, u32::from_ne_bytes(src.try_into().unwrap()) as usize),
+                #[cfg(not(target_arch = "riscv32"))]
+                8 => store_u64(gva, u64::from_ne_bytes(src.try_into().unwrap()) as usize),
+                _ => unreachable!(),
+            }
+        }
+    })
+}
+
+/// Types that are valid for any bit pattern and can be read from guest memory.
+///
+/// # Safety
+///
+/// Every byte sequence of `size_of::<Self>()` bytes must be a valid value.
+pub unsafe trait FromBytes: Copy {}
+
+/// Types without padding bytes whose value can be written to guest memory.
+///
+/// # Safety
+///
+/// `Self` must not contain padding or other uninitialized bytes.
+pub unsafe trait AsBytes: Copy {}
+
+macro_rules! impl_plain {
+    ($($ty:ty),*) => {
+        $(
+            // SAFETY: Every bit pattern of a primitive integer is a valid value
+            unsafe impl FromBytes for $ty {}
+            // SAFETY: Primitive integers consist of initialized value bytes only
+            unsafe impl AsBytes for $ty {}
+        )*
+    };
+}
+
+impl_plain!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+// SAFETY: `[T; N]` is laid out as `N` consecutive `T`s with no padding, so
+// its bytes split into `N` byte sequences of `size_of::<T>()`, each of which
+// is a valid `T` by the `FromBytes` contract of `T`
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+// SAFETY: `[T; N]` adds no padding between or after its elements, and each
+// `T` has no padding by the `AsBytes` contract of `T`
+unsafe impl<T: AsBytes, const N: usize> AsBytes for [T; N] {}
+
+/// Reads a `T` from guest virtual address `gva`.
+///
+/// # Safety
+///
+/// Same as [`copy_from_guest`].
+pub unsafe fn read_guest<T: FromBytes>(gva: usize) -> Result<T, GuestAccessFault> {
+    let mut value = MaybeUninit::<T>::zeroed();
+    // SAFETY: The zeroed value is `size_of::<T>()` initialized bytes
+    let bytes =
+        unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr().cast::<u8>(), size_of::<T>()) };
+    // SAFETY: Caller ensures the guest access is valid to attempt
+    unsafe { copy_from_guest(bytes, gva)? };
+    // SAFETY: `T: FromBytes` accepts whatever bytes the guest held
+    Ok(unsafe { value.assume_init() })
+}
+
+/// Writes `value` to guest virtual address `gva`.
+///
+/// # Safety
+///
+/// Same as [`copy_to_guest`].
+pub unsafe fn write_guest<T: AsBytes>(gva: usize, value: T) -> Result<(), GuestAccessFault> {
+    // SAFETY: `T: AsBytes` has no uninitialized bytes
+    let bytes =
+        unsafe { core::slice::from_raw_parts((&value as *const T).cast::<u8>(), size_of::<T>()) };
+    // SAFETY: Caller ensures the guest access is valid to attempt
+    unsafe { copy_to_guest(bytes, gva)? };
+    Ok(())
+}
+
+/// Error reading a guest string.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CstrError {
+    /// A guest access faulted before the terminating NUL was found.
+    Fault(GuestAccessFault),
+    /// The string including its NUL does not fit into the buffer.
+    TooLong,
+}
+
+impl From<GuestAccessFault> for CstrError {
+    fn from(fault: GuestAccessFault) -> Self {
+        CstrError::Fault(fault)
+    }
+}
+
+/// Reads a NUL-terminated string at `gva` through `read`, one page at a time.
+fn read_cstr_with(
+    gva: usize,
+    buf: &mut [u8],
+    mut read: impl FnMut(&mut [u8], usize) -> Result<usize, GuestAccessFault>,
+) -> Result<&CStr, CstrError> {
+    let mut len = 0;
+    while len < buf.len() {
+        let addr = gva.wrapping_add(len);
+        let chunk = (PAGE_SIZE - addr % PAGE_SIZE).min(buf.len() - len);
+        read(&mut buf[len..len + chunk], addr)?;
+        if let Some(nul) = buf[len..len + chunk].iter().position(|&b| b == 0) {
+            return Ok(CStr::from_bytes_with_nul(&buf[..=len + nul]).unwrap());
+        }
+        len += chunk;
+    }
+    Err(CstrError::TooLong)
+}
+
+/// Reads a NUL-terminated string at guest virtual address `gva` into `buf`.
+///
+/// Memory is only read up to the page holding the NUL, so a string that
+/// ends right before an unmapped page is read successfully.
+///
+/// # Safety
+///
+/// Same as [`copy_from_guest`].
+pub unsafe fn read_guest_cstr(gva: usize, buf: &mut [u8]) -> Result<&CStr, CstrError> {
+    // SAFETY: Caller ensures the guest access is valid to attempt
+    read_cstr_with(gva, buf, |chunk, gva| unsafe {
+        copy_from_guest(chunk, gva)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_chunk_width() {
+        assert_eq!(chunk_width(0x1000, 64), 8);
+        assert_eq!(chunk_width(0x1004, 64), 4);
+        assert_eq!(chunk_width(0x1006, 64), 2);
+        assert_eq!(chunk_width(0x1001, 64), 1);
+        assert_eq!(chunk_width(0x1000, 7), 4);
+        assert_eq!(chunk_width(0x1000, 1), 1);
+    }
+
+    #[test]
+    fn test_copy_chunks_aligned_plan() {
+        let mut plan = Vec::new();
+        let copied = copy_chunks(0x1003, 14, None, |offset, gva, width| {
+            plan.push((offset, gva, width));
+            Ok(())
+        });
+        assert_eq!(copied, Ok(14));
+        assert_eq!(
+            plan,
+            [
+                (0, 0x1003, 1),
+                (1, 0x1004, 4),
+                (5, 0x1008, 8),
+                (13, 0x1010, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_copy_chunks_fault() {
+        let fault = copy_chunks(0x2000, 16, None, |_, gva, _| {
+            if gva == 0x2008 {
+                Err((13, 0x800))
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(
+            fault,
+            Err(GuestAccessFault {
+                gva: 0x2008,
+                scause: 13,
+                htval: 0x800,
+                copied: 8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_copy_chunks_fixup_retries_once() {
+        let mut attempts = 0;
+        let copied = copy_chunks(0x3000, 8, Some(|_| Fixup::Retry), |_, _, _| {
+            attempts += 1;
+            if attempts == 1 { Err((21, 0)) } else { Ok(()) }
+        });
+        assert_eq!(copied, Ok(8));
+
+        let fault = copy_chunks(0x3000, 8, Some(|_| Fixup::Retry), |_, _, _| Err((21, 0)));
+        assert_eq!(fault.unwrap_err().copied, 0);
+    }
+
+    #[test]
+    fn test_read_cstr_stops_at_page_with_nul() {
+        let mut guest = [0; 64];
+        guest[..5].copy_from_slice(b"hello");
+        let mut reads = Vec::new();
+        let mut buf = [0xff; 64];
+        let s = read_cstr_with(0x1ffc, &mut buf, |chunk, gva| {
+            reads.push((gva, chunk.len()));
+            let start = gva - 0x1ffc;
+            chunk.copy_from_slice(&guest[start..start + chunk.len()]);
+            Ok(chunk.len())
+        })
+        .unwrap();
+        assert_eq!(s.to_bytes(), b"hello");
+        assert_eq!(reads, [(0x1ffc, 4), (0x2000, 60)]);
+    }
+
+    #[test]
+    fn test_read_cstr_too_long() {
+        let mut buf = [0; 8];
+        let err = read_cstr_with(0x1000, &mut buf, |chunk, _| {
+            chunk.fill(b'a');
+            Ok(chunk.len())
+        });
+        assert_eq!(err, Err(CstrError::TooLong));
+    }
+}
@@ -0,0 +1,228 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Extension Capabilities.
+//!
+//! Capabilities such as GEILEN and VMIDLEN are only discoverable through WARL
+//! probes, which write and restore live CSRs and must not run on hot paths.
+//! [`HExtInfo::get`](crate::hext::HExtInfo::get) probes once per hart and
+//! caches the result in a [`HExtCache`](crate::hext::HExtCache) that the caller
+//! keeps in its hart-local storage, so later lookups are a plain load without
+//! locks or CSR accesses. To keep the results of all harts in one `static`, use
+//! [`PerHart`](crate::once::PerHart) instead.
+//!
+//! The probe also records which optional extensions add hypervisor CSRs on
+//! this hart (AIA, Sstc, Smstateen, Sdtrig and `henvcfg`), by reading one CSR
+//! of each under a local trap vector.
+
+use crate::register::hgatp::{self, HgatpValues};
+use crate::selftest::{probe_csr, probe_geilen, probe_hgatp_mode};
+use core::cell::OnceCell;
+
+/// Hypervisor extension capabilities of a hart.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HExtInfo {
+    geilen: usize,
+    vmidlen: usize,
+    sv39x4: bool,
+    sv48x4: bool,
+    sv57x4: bool,
+    aia: bool,
+    sstc: bool,
+    smstateen: bool,
+    sdtrig: bool,
+    henvcfg: bool,
+}
+
+impl HExtInfo {
+    /// Probes the capabilities of the current hart.
+    ///
+    /// # Safety
+    ///
+    /// Must be called in HS-mode with interrupts disabled and no guest running
+    /// on this hart.
+    pub unsafe fn probe() -> Self {
+        // SAFETY: Caller ensures probing is allowed
+        unsafe {
+            HExtInfo {
+                geilen: probe_geilen(),
+                vmidlen: hgatp::vmid_len(),
+                sv39x4: probe_hgatp_mode(HgatpValues::Sv39x4 as usize),
+                sv48x4: probe_hgatp_mode(HgatpValues::Sv48x4 as usize),
+                sv57x4: probe_hgatp_mode(HgatpValues::Sv57x4 as usize),
+                aia: probe_csr::<0x609>(),
+                sstc: probe_csr::<0x24D>(),
+                smstateen: probe_csr::<0x60C>(),
+                sdtrig: probe_csr::<0x6A8>(),
+                henvcfg: probe_csr::<0x60A>(),
+            }
+        }
+    }
+    /// Returns the capabilities of the current hart, probing on first use.
+    #[inline]
+    pub fn get(cache: &HExtCache) -> &HExtInfo {
+        cache.info.get_or_init(|| {
+            // SAFETY: Guaranteed by the contract of `HExtCache::new`
+            unsafe { Self::probe() }
+        })
+    }
+    /// Returns the number of implemented guest interrupt files.
+    #[inline]
+    pub fn geilen(&self) -> usize {
+        self.geilen
+    }
+    /// Returns the number of implemented VMID bits.
+    #[inline]
+    pub fn vmidlen(&self) -> usize {
+        self.vmidlen
+    }
+    /// Returns the largest usable VMID.
+    #[inline]
+    pub fn max_vmid(&self) -> usize {
+        (1 << self.vmidlen) - 1
+    }
+    /// Returns whether `hgatp` supports the given translation mode.
+    #[inline]
+    pub fn supports(&self, mode: HgatpValues) -> bool {
+        match mode {
+            HgatpValues::Bare => true,
+            HgatpValues::Sv39x4 => self.sv39x4,
+            HgatpValues::Sv48x4 => self.sv48x4,
+            HgatpValues::Sv57x4 => self.sv57x4,
+        }
+    }
+    /// Returns whether `hgatp` supports the Sv57x4 translation mode.
+    #[inline]
+    pub fn sv57x4(&self) -> bool {
+        self.sv57x4
+    }
+    /// Returns whether the hart implements the AIA hypervisor CSRs.
+    #[inline]
+    pub fn aia(&self) -> bool {
+        self.aia
+    }
+    /// Returns whether the hart implements `vstimecmp` (Sstc).
+    #[inline]
+    pub fn sstc(&self) -> bool {
+        self.sstc
+    }
+    /// Returns whether the hart implements `hstateen0`..`hstateen3` (Smstateen).
+    #[inline]
+    pub fn smstateen(&self) -> bool {
+        self.smstateen
+    }
+    /// Returns whether the hart implements `hcontext` (Sdtrig).
+    #[inline]
+    pub fn sdtrig(&self) -> bool {
+        self.sdtrig
+    }
+    /// Returns whether the hart implements `henvcfg`.
+    #[inline]
+    pub fn henvcfg(&self) -> bool {
+        self.henvcfg
+    }
+    /// Returns whether the hart implements the hypervisor or VS-level CSR
+    /// `csr`.
+    ///
+    /// CSRs of the base hypervisor extension are always implemented.
+    /// `vstopei` additionally needs at least one guest interrupt file.
+    pub fn implements_csr(&self, csr: u16) -> bool {
+        match csr {
+            0x25C => self.aia && self.geilen > 0,
+            0x608 | 0x609 | 0x646 | 0x647 | 0xEB0 => self.aia,
+            0x24D => self.sstc,
+            0x60C..=0x60F => self.smstateen,
+            0x6A8 => self.sdtrig,
+            0x60A => self.henvcfg,
+            _ => true,
+        }
+    }
+}
+
+/// Hart-local storage for a lazily probed [`HExtInfo`].
+///
+/// The cache is not `Sync`: each hart owns its own instance.
+#[derive(Debug)]
+pub struct HExtCache {
+    info: OnceCell<HExtInfo>,
+}
+
+impl HExtCache {
+    /// Creates an empty cache.
+    ///
+    /// # Safety
+    ///
+    /// The cache must only be used on a single hart, and the first
+    /// [`HExtInfo::get`](crate::hext::HExtInfo::get) on it must run where
+    /// [`HExtInfo::probe`] is allowed.
+    pub const unsafe fn new() -> Self {
+        HExtCache {
+            info: OnceCell::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: HExtInfo = HExtInfo {
+        geilen: 7,
+        vmidlen: 14,
+        sv39x4: true,
+        sv48x4: true,
+        sv57x4: false,
+        aia: true,
+        sstc: false,
+        smstateen: true,
+        sdtrig: false,
+        henvcfg: true,
+    };
+
+    #[test]
+    fn test_get_uses_cached_info() {
+        // SAFETY: The cache is filled before the first `get`, so no probe runs
+        let cache = unsafe { HExtCache::new() };
+        cache.info.set(INFO).unwrap();
+        assert_eq!(HExtInfo::get(&cache), &INFO);
+    }
+
+    #[test]
+    fn test_accessors() {
+        assert_eq!(INFO.geilen(), 7);
+        assert_eq!(INFO.vmidlen(), 14);
+        assert_eq!(INFO.max_vmid(), 0x3fff);
+        assert!(INFO.supports(HgatpValues::Bare));
+        assert!(INFO.supports(HgatpValues::Sv48x4));
+        assert!(!INFO.sv57x4());
+        assert!(!INFO.supports(HgatpValues::Sv57x4));
+
+        let no_vmid = HExtInfo { vmidlen: 0, ..INFO };
+        assert_eq!(no_vmid.max_vmid(), 0);
+    }
+
+    #[test]
+    fn test_implements_csr() {
+        assert!(INFO.implements_csr(0x600));
+        assert!(INFO.implements_csr(0x609));
+        assert!(INFO.implements_csr(0x25C));
+        assert!(!INFO.implements_csr(0x24D));
+        assert!(INFO.implements_csr(0x60F));
+        assert!(!INFO.implements_csr(0x6A8));
+
+        let no_files = HExtInfo { geilen: 0, ..INFO };
+        assert!(!no_files.implements_csr(0x25C));
+        assert!(no_files.implements_csr(0xEB0));
+    }
+}
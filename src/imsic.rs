@@ -0,0 +1,428 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IMSIC Interrupt File Layout.
+//!
+//! Each hart's supervisor-level Incoming MSI Controller (IMSIC) exposes one
+//! 4 KiB page per interrupt file: the supervisor file first, followed by one
+//! guest interrupt file per implemented guest external interrupt (GEILEN).
+//! Writing an interrupt identity to a file's `seteipnum` register makes that
+//! identity pending in the file, which is how MSIs are delivered.
+//!
+//! The types here describe that layout so a hypervisor that maps the IMSIC
+//! pages into its own address space can deliver MSIs directly into a guest
+//! interrupt file.
+//!
+//! [`MsiTarget`](crate::imsic::MsiTarget) bridges APLIC configuration code in
+//! MSI delivery mode: it turns the hart index, guest index (the guest's
+//! `hstatus.VGEIN`) and interrupt identity of an
+//! [`AplicTarget`](crate::imsic::AplicTarget) into the physical MSI write that
+//! reaches that guest interrupt file, following the system's
+//! [`ImsicLayout`](crate::imsic::ImsicLayout).
+
+use bit_field::BitField;
+use core::mem::size_of;
+use core::ptr::NonNull;
+
+/// Size of one interrupt file page.
+pub const INTERRUPT_FILE_SIZE: usize = 0x1000;
+
+/// Register layout of one IMSIC interrupt file.
+#[repr(C)]
+struct Registers {
+    seteipnum_le: u32,
+    seteipnum_be: u32,
+    _reserved: [u32; 1022],
+}
+
+const _: () = assert!(size_of::<Registers>() == INTERRUPT_FILE_SIZE);
+
+/// Memory-mapped registers of one IMSIC interrupt file.
+///
+/// This is a pointer to the mapping rather than a reference, so the compiler
+/// never reads the page on its own; every register access is a single
+/// volatile store.
+#[derive(Copy, Clone, Debug)]
+pub struct InterruptFile {
+    regs: NonNull<Registers>,
+}
+
+// SAFETY: All accesses to the registers are single volatile stores.
+unsafe impl Send for InterruptFile {}
+// SAFETY: All accesses to the registers are single volatile stores.
+unsafe impl Sync for InterruptFile {}
+
+impl InterruptFile {
+    /// Returns the interrupt file mapped at virtual address `va`.
+    ///
+    /// # Safety
+    ///
+    /// `va` must be the page-aligned mapping of an IMSIC interrupt file that
+    /// stays mapped while the returned value is used.
+    #[inline]
+    pub unsafe fn from_addr(va: usize) -> Self {
+        InterruptFile {
+            // SAFETY: Caller ensures `va` maps an interrupt file, so it is
+            // not null
+            regs: unsafe { NonNull::new_unchecked(va as *mut Registers) },
+        }
+    }
+    /// Writes `id` to the little-endian `seteipnum` register.
+    #[inline]
+    pub fn set_eipnum_le(&self, id: u32) {
+        // SAFETY: `from_addr` guarantees the register is mapped
+        unsafe {
+            core::ptr::addr_of_mut!((*self.regs.as_ptr()).seteipnum_le).write_volatile(id.to_le())
+        };
+    }
+    /// Writes `id` to the big-endian `seteipnum` register.
+    #[inline]
+    pub fn set_eipnum_be(&self, id: u32) {
+        // SAFETY: `from_addr` guarantees the register is mapped
+        unsafe {
+            core::ptr::addr_of_mut!((*self.regs.as_ptr()).seteipnum_be).write_volatile(id.to_be())
+        };
+    }
+    /// Makes interrupt identity `id` pending in this file.
+    #[inline]
+    pub fn send(&self, id: u32) {
+        self.set_eipnum_le(id);
+    }
+}
+
+/// The supervisor-level interrupt files of one hart.
+#[derive(Copy, Clone, Debug)]
+pub struct HartInterruptFiles {
+    base: usize,
+    geilen: usize,
+}
+
+impl HartInterruptFiles {
+    /// Describes the files mapped at virtual address `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must map the hart's supervisor interrupt file followed by its
+    /// `geilen` guest interrupt files, and stay mapped while this value is used.
+    #[inline]
+    pub unsafe fn new(base: usize, geilen: usize) -> Self {
+        HartInterruptFiles { base, geilen }
+    }
+    /// Returns the number of guest interrupt files.
+    #[inline]
+    pub fn geilen(&self) -> usize {
+        self.geilen
+    }
+    /// Returns the supervisor interrupt file.
+    #[inline]
+    pub fn supervisor(&self) -> InterruptFile {
+        // SAFETY: `new` guarantees the mapping
+        unsafe { InterruptFile::from_addr(self.base) }
+    }
+    /// Returns the virtual address of guest interrupt file `n` (1-based, as
+    /// in `hstatus.VGEIN`).
+    #[inline]
+    pub fn guest_addr(&self, n: usize) -> Option<usize> {
+        (n != 0 && n <= self.geilen).then(|| self.base + n * INTERRUPT_FILE_SIZE)
+    }
+    /// Returns guest interrupt file `n` (1-based, as in `hstatus.VGEIN`).
+    #[inline]
+    pub fn guest(&self, n: usize) -> Option<InterruptFile> {
+        // SAFETY: `new` guarantees the mapping and `n` is in range
+        self.guest_addr(n)
+            .map(|va| unsafe { InterruptFile::from_addr(va) })
+    }
+}
+
+/// Placement of the supervisor-level IMSICs in the physical address space.
+///
+/// Mirrors the fields of the APLIC `smsiaddrcfg`/`smsiaddrcfgh` registers: a
+/// hart index is split into a lower part of `lhxw` bits, placed at page
+/// shift `lhxs`, and a higher (group) part of `hhxw` bits, placed at address
+/// bit `hhxs + 24`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ImsicGeometry {
+    /// Lower hart index width.
+    pub lhxw: u32,
+    /// Higher hart index width.
+    pub hhxw: u32,
+    /// Lower hart index shift, in pages.
+    pub lhxs: u32,
+    /// Higher hart index shift, in address bits above 24.
+    pub hhxs: u32,
+}
+
+/// Physical location of all supervisor-level interrupt files.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ImsicLayout {
+    /// Physical page number of hart 0's supervisor interrupt file.
+    pub base_ppn: usize,
+    /// Spacing of the per-hart IMSICs.
+    pub geometry: ImsicGeometry,
+}
+
+impl ImsicLayout {
+    /// Returns the physical address of interrupt file `guest` of `hart`.
+    ///
+    /// Guest index 0 selects the supervisor file itself.
+    fn file_addr(&self, hart: usize, guest: usize) -> usize {
+        let g = self.geometry;
+        let low = hart & ((1 << g.lhxw) - 1);
+        let high = (hart >> g.lhxw) & ((1 << g.hhxw) - 1);
+        let ppn = self.base_ppn | (high << (g.hhxs + 12)) | (low << g.lhxs) | guest;
+        ppn << 12
+    }
+}
+
+/// The target of a wired interrupt forwarded by an APLIC as an MSI.
+///
+/// The fields are checked against the widths of the APLIC `target[i]`
+/// register on construction, so every value can be encoded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AplicTarget {
+    hart: usize,
+    guest: usize,
+    eiid: u32,
+}
+
+impl AplicTarget {
+    /// Targets the guest interrupt file selected by `vgein` on `hart`.
+    ///
+    /// Fails if `hart`, `vgein` or `eiid` is wider than the 14-bit Hart
+    /// Index, 6-bit Guest Index or 11-bit EIID field of `target[i]`.
+    #[inline]
+    pub fn for_guest(hart: usize, vgein: usize, eiid: u32) -> Result<Self, AplicTargetError> {
+        if hart >= 1 << 14 {
+            return Err(AplicTargetError::HartTooLarge);
+        }
+        if vgein >= 1 << 6 {
+            return Err(AplicTargetError::GuestTooLarge);
+        }
+        if eiid >= 1 << 11 {
+            return Err(AplicTargetError::EiidTooLarge);
+        }
+        Ok(AplicTarget {
+            hart,
+            guest: vgein,
+            eiid,
+        })
+    }
+    /// Hart index of the destination hart.
+    #[inline]
+    pub fn hart(&self) -> usize {
+        self.hart
+    }
+    /// Guest interrupt file of the destination, 0 for the supervisor file.
+    #[inline]
+    pub fn guest(&self) -> usize {
+        self.guest
+    }
+    /// External interrupt identity written as MSI data.
+    #[inline]
+    pub fn eiid(&self) -> u32 {
+        self.eiid
+    }
+    /// Returns the APLIC `target[i]` register value in MSI delivery mode.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        let mut bits = 0u32;
+        bits.set_bits(18..32, self.hart as u32);
+        bits.set_bits(12..18, self.guest as u32);
+        bits.set_bits(0..11, self.eiid);
+        bits
+    }
+}
+
+/// Errors building an [`AplicTarget`] or resolving it to an MSI.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AplicTargetError {
+    /// The hart index is wider than the 14-bit Hart Index field.
+    HartTooLarge,
+    /// The guest index is wider than the 6-bit Guest Index field.
+    GuestTooLarge,
+    /// The interrupt identity is wider than the 11-bit EIID field.
+    EiidTooLarge,
+    /// The guest index selects a page beyond the hart's `1 << lhxs` files.
+    GuestOutOfLayout,
+}
+
+/// A message-signaled interrupt write.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MsiMessage {
+    /// Physical address to write.
+    pub addr: usize,
+    /// 32-bit value to write.
+    pub data: u32,
+}
+
+/// Resolves APLIC targets to the MSI writes that reach them.
+pub trait MsiTarget {
+    /// Returns the MSI that delivers `target`.
+    fn msi_message(&self, target: AplicTarget) -> Result<MsiMessage, AplicTargetError>;
+}
+
+impl MsiTarget for ImsicLayout {
+    fn msi_message(&self, target: AplicTarget) -> Result<MsiMessage, AplicTargetError> {
+        if target.guest >> self.geometry.lhxs != 0 {
+            return Err(AplicTargetError::GuestOutOfLayout);
+        }
+        Ok(MsiMessage {
+            addr: self.file_addr(target.hart, target.guest),
+            data: target.eiid,
+        })
+    }
+}
+
+/// Computes the MSI that makes `eiid` pending in a guest interrupt file.
+///
+/// Implements the AIA MSI address formula for supervisor-level IMSICs:
+/// `((base_ppn | group << (HHXS + 12) | hart << LHXS | guest_file) << 12)`,
+/// where `hart` is split into its lower `lhxw` and higher (group) `hhxw`
+/// bits. Device-passthrough code can program the returned address and data
+/// into a PCIe MSI-X entry so the device signals the guest's interrupt file
+/// directly.
+///
+/// Returns the `(addr, data)` pair of the MSI write, or an error if the
+/// target cannot be encoded or lies outside `geometry`.
+#[inline]
+pub fn imsic_guest_msi_target(
+    hart: usize,
+    guest_file: usize,
+    eiid: u32,
+    base_ppn: usize,
+    geometry: ImsicGeometry,
+) -> Result<(usize, u32), AplicTargetError> {
+    let target = AplicTarget::for_guest(hart, guest_file, eiid)?;
+    let msi = ImsicLayout { base_ppn, geometry }.msi_message(target)?;
+    Ok((msi.addr, msi.data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::mem::offset_of;
+
+    #[test]
+    fn test_interrupt_file_layout() {
+        assert_eq!(offset_of!(Registers, seteipnum_le), 0x0);
+        assert_eq!(offset_of!(Registers, seteipnum_be), 0x4);
+    }
+
+    #[test]
+    fn test_interrupt_file_writes() {
+        // SAFETY: All-zero is a valid value for every field
+        let mut regs: Registers = unsafe { core::mem::zeroed() };
+        // SAFETY: `regs` outlives `file`
+        let file = unsafe { InterruptFile::from_addr(&mut regs as *mut Registers as usize) };
+        file.send(0x2A);
+        file.set_eipnum_be(0x2B);
+        assert_eq!(u32::from_le(regs.seteipnum_le), 0x2A);
+        assert_eq!(u32::from_be(regs.seteipnum_be), 0x2B);
+    }
+
+    #[test]
+    fn test_guest_file_addresses() {
+        let files = unsafe { HartInterruptFiles::new(0x2800_0000, 3) };
+        assert_eq!(files.guest_addr(1), Some(0x2800_1000));
+        assert_eq!(files.guest_addr(3), Some(0x2800_3000));
+        assert_eq!(files.guest_addr(0), None);
+        assert_eq!(files.guest_addr(4), None);
+    }
+
+    const LAYOUT: ImsicLayout = ImsicLayout {
+        // QEMU virt: supervisor IMSICs at 0x2800_0000, 8 pages per hart
+        base_ppn: 0x2800_0000 >> 12,
+        geometry: ImsicGeometry {
+            lhxw: 3,
+            hhxw: 0,
+            lhxs: 3,
+            hhxs: 0,
+        },
+    };
+
+    #[test]
+    fn test_msi_message() {
+        let target = AplicTarget::for_guest(0, 0, 5).unwrap();
+        assert_eq!(
+            LAYOUT.msi_message(target),
+            Ok(MsiMessage {
+                addr: 0x2800_0000,
+                data: 5
+            })
+        );
+
+        let target = AplicTarget::for_guest(2, 1, 7).unwrap();
+        let msi = LAYOUT.msi_message(target).unwrap();
+        assert_eq!(msi.addr, 0x2800_0000 + 2 * 0x8000 + 0x1000);
+        assert_eq!(msi.data, 7);
+    }
+
+    #[test]
+    fn test_msi_message_groups() {
+        let layout = ImsicLayout {
+            base_ppn: 0x2800_0000 >> 12,
+            geometry: ImsicGeometry {
+                lhxw: 1,
+                hhxw: 1,
+                lhxs: 3,
+                hhxs: 0,
+            },
+        };
+        // Hart 3 is hart 1 of group 1
+        let target = AplicTarget::for_guest(3, 2, 1).unwrap();
+        let msi = layout.msi_message(target).unwrap();
+        assert_eq!(msi.addr, 0x2800_0000 | (1 << 24) | (1 << 15) | (2 << 12));
+    }
+
+    #[test]
+    fn test_imsic_guest_msi_target() {
+        let (addr, data) =
+            imsic_guest_msi_target(1, 3, 0x20, LAYOUT.base_ppn, LAYOUT.geometry).unwrap();
+        assert_eq!(addr, 0x2800_0000 + 0x8000 + 0x3000);
+        assert_eq!(data, 0x20);
+    }
+
+    #[test]
+    fn test_msi_message_guest_out_of_layout() {
+        // LHXS of 3 leaves room for the supervisor file and 7 guest files
+        let target = AplicTarget::for_guest(0, 8, 1).unwrap();
+        assert_eq!(
+            LAYOUT.msi_message(target),
+            Err(AplicTargetError::GuestOutOfLayout)
+        );
+    }
+
+    #[test]
+    fn test_aplic_target_bits() {
+        let target = AplicTarget::for_guest(3, 2, 0x45).unwrap();
+        assert_eq!(target.bits(), (3 << 18) | (2 << 12) | 0x45);
+    }
+
+    #[test]
+    fn test_aplic_target_limits() {
+        assert!(AplicTarget::for_guest((1 << 14) - 1, 63, 2047).is_ok());
+        assert_eq!(
+            AplicTarget::for_guest(1 << 14, 0, 1),
+            Err(AplicTargetError::HartTooLarge)
+        );
+        assert_eq!(
+            AplicTarget::for_guest(0, 64, 1),
+            Err(AplicTargetError::GuestTooLarge)
+        );
+        assert_eq!(
+            AplicTarget::for_guest(0, 0, 2048),
+            Err(AplicTargetError::EiidTooLarge)
+        );
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Register Introspection.
+//!
+//! Static metadata of every typed hypervisor CSR, so an on-target monitor or a
+//! debugger script can decode and pretty-print any register value without
+//! knowing its layout:
+//!
+//! ```
+//! use riscv_h::introspection;
+//!
+//! let info = introspection::by_name("hstatus").unwrap();
+//! assert_eq!(info.csr(), 0x600);
+//! let text = format!("{}", info.decode(0x2_0000_0080));
+//! assert!(text.contains("spv"));
+//! ```
+
+use crate::register::{
+    Access, RegisterInfo, hcontext, hcounteren, hedeleg, henvcfg, hgatp, hgeie, hgeip, hideleg,
+    hie, hip, hstateen0, hstateen1, hstateen2, hstateen3, hstatus, htinst, htval, hvictl, hvien,
+    hvip, hviprio1, hviprio2, vsatp, vscause, vsie, vsip, vsstatus, vstimecmp, vstopei, vstopi,
+    vstvec,
+};
+use core::fmt;
+
+/// Every typed register of this crate.
+pub static REGISTERS: &[&RegisterInfo] = &[
+    &hstatus::Hstatus::INFO,
+    &hedeleg::Hedeleg::INFO,
+    &hideleg::Hideleg::INFO,
+    &hie::Hie::INFO,
+    &hcounteren::Hcounteren::INFO,
+    &hgeie::Hgeie::INFO,
+    &hgeip::Hgeip::INFO,
+    &htinst::Htinst::INFO,
+    &htval::Htval::INFO,
+    &hvien::Hvien::INFO,
+    &hvictl::Hvictl::INFO,
+    &hip::Hip::INFO,
+    &hvip::Hvip::INFO,
+    &hviprio1::Hviprio1::INFO,
+    &hviprio2::Hviprio2::INFO,
+    &hgatp::Hgatp::INFO,
+    &hcontext::Hcontext::INFO,
+    &hstateen0::Hstateen0::INFO,
+    &hstateen1::Hstateen1::INFO,
+    &hstateen2::Hstateen2::INFO,
+    &hstateen3::Hstateen3::INFO,
+    &henvcfg::Henvcfg::INFO,
+    &vsstatus::Vsstatus::INFO,
+    &vsie::Vsie::INFO,
+    &vstvec::Vstvec::INFO,
+    &vscause::Vscause::INFO,
+    &vsip::Vsip::INFO,
+    &vsatp::Vsatp::INFO,
+    &vstimecmp::Vstimecmp::INFO,
+    &vstopei::Vstopei::INFO,
+    &vstopi::Vstopi::INFO,
+];
+
+/// Looks up a register by CSR number.
+pub fn by_csr(csr: u16) -> Option<&'static RegisterInfo> {
+    REGISTERS.iter().copied().find(|info| info.csr() == csr)
+}
+
+/// Looks up a register by name, ignoring ASCII case.
+pub fn by_name(name: &str) -> Option<&'static RegisterInfo> {
+    REGISTERS
+        .iter()
+        .copied()
+        .find(|info| info.name().eq_ignore_ascii_case(name))
+}
+
+impl RegisterInfo {
+    /// Pairs the register description with a value for pretty-printing.
+    #[inline]
+    pub fn decode(&self, bits: usize) -> Decoded<'_> {
+        Decoded { info: self, bits }
+    }
+}
+
+/// A register value printed one field per line.
+#[derive(Copy, Clone, Debug)]
+pub struct Decoded<'a> {
+    info: &'a RegisterInfo,
+    bits: usize,
+}
+
+impl fmt::Display for Decoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let info = self.info;
+        write!(
+            f,
+            "{} (csr {:#05x}) = {:#x}",
+            info.name(),
+            info.csr(),
+            self.bits
+        )?;
+        for field in info.fields() {
+            let msb = field.lsb() + field.width() - 1;
+            if msb == field.lsb() {
+                write!(f, "\n  [{}]", msb)?;
+            } else {
+                write!(f, "\n  [{}:{}]", msb, field.lsb())?;
+            }
+            write!(
+                f,
+                " {} = {:#x}  {}",
+                field.name(),
+                field.get(self.bits),
+                field.description()
+            )?;
+            match field.access() {
+                Access::ReadWrite => {}
+                Access::ReadOnly => write!(f, " (read-only)")?,
+                Access::Warl => write!(f, " (WARL)")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(by_csr(0x680).unwrap().name(), "Hgatp");
+        assert_eq!(by_name("VSSTATUS").unwrap().csr(), 0x200);
+        assert!(by_csr(0x000).is_none());
+        assert!(by_name("mstatus").is_none());
+    }
+
+    #[test]
+    fn test_unique_csrs() {
+        for (i, a) in REGISTERS.iter().enumerate() {
+            for b in &REGISTERS[i + 1..] {
+                assert_ne!(a.csr(), b.csr(), "{} and {}", a.name(), b.name());
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let text = format!("{}", by_name("hip").unwrap().decode(1 << 10));
+        assert!(text.starts_with("Hip (csr 0x644) = 0x400"));
+        assert!(text.contains("\n  [10] vseip = 0x1  "));
+        assert!(text.contains("(read-only)"));
+
+        let text = format!("{}", hgatp::Hgatp::INFO.decode(8 << 60));
+        assert!(text.contains("[63:60] mode = 0x8"));
+        assert!(text.contains("(WARL)"));
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Scoped Interrupt Suppression.
+//!
+//! Updating `hvip`, `hgeie` or the delegation registers often takes several CSR
+//! accesses, and a VS-level or guest external interrupt arriving halfway
+//! through sees an inconsistent state. [`HieGuard`](crate::irq::HieGuard) and
+//! [`SieGuard`](crate::irq::SieGuard) clear selected enable bits for the
+//! lifetime of the guard and restore exactly the bits they cleared when
+//! dropped, so nested guards compose.
+//!
+//! ```no_run
+//! use riscv_h::irq::HieGuard;
+//! use riscv_h::register::hie::Hie;
+//!
+//! let _guard = unsafe { HieGuard::new(Hie::VSEIE | Hie::SGEIE) };
+//! // update hvip and hgeie here
+//! ```
+
+use crate::register::hie::Hie;
+
+macro_rules! csr_guard {
+    ($(#[$doc:meta])* $name:ident, $reg:literal, $fetch_clear:ident, $set:ident, $ty:ty) => {
+        $(#[$doc])*
+        #[derive(Debug)]
+        #[must_use = "interrupts are re-enabled as soon as the guard is dropped"]
+        pub struct $name {
+            cleared: usize,
+        }
+
+        impl $name {
+            #[doc = concat!("Clears the `", $reg, "` bits in `mask` until the guard is dropped.")]
+            ///
+            /// # Safety
+            ///
+            /// The guard must be dropped on the hart that created it, and the
+            /// masked bits must not be re-enabled behind its back.
+            #[inline]
+            pub unsafe fn new(mask: usize) -> Self {
+                // SAFETY: Caller ensures this is safe to execute
+                let old = unsafe { $fetch_clear(mask) };
+                $name {
+                    cleared: cleared_bits(old, mask),
+                }
+            }
+            /// Returns the enable bits that were set and will be restored.
+            #[inline]
+            pub fn cleared(&self) -> $ty {
+                <$ty>::from_bits(self.cleared)
+            }
+        }
+
+        impl Drop for $name {
+            #[inline]
+            fn drop(&mut self) {
+                if self.cleared != 0 {
+                    // SAFETY: Only restores bits that were set before `new`
+                    unsafe { $set(self.cleared) };
+                }
+            }
+        }
+    };
+}
+
+/// Returns the bits a guard clearing `mask` from a CSR holding `old` has to
+/// restore: only those that were set, so bits that were already clear stay
+/// clear when the guard is dropped.
+#[inline]
+const fn cleared_bits(old: usize, mask: usize) -> usize {
+    old & mask
+}
+
+macro_rules! csr_ops {
+    ($fetch_clear:ident, $set:ident, $csr:literal) => {
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        unsafe fn $fetch_clear(mask: usize) -> usize {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            {
+                let bits: usize;
+                // SAFETY: Caller ensures this is safe to execute
+                unsafe {
+                    core::arch::asm!(concat!("csrrc {0}, ", stringify!($csr), ", {1}"), out(reg) bits, in(reg) mask)
+                };
+                bits
+            }
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        unsafe fn $set(bits: usize) {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            // SAFETY: Caller ensures this is safe to execute
+            unsafe {
+                core::arch::asm!(concat!("csrs ", stringify!($csr), ", {0}"), in(reg) bits)
+            };
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+csr_ops!(hie_fetch_clear, hie_set, 0x604);
+csr_ops!(sie_fetch_clear, sie_set, 0x104);
+
+csr_guard!(
+    /// Suppresses VS-level and guest external interrupt enables in `hie`.
+    HieGuard, "hie", hie_fetch_clear, hie_set, Hie
+);
+
+csr_guard!(
+    /// Suppresses HS-level interrupt enables in `sie`.
+    SieGuard, "sie", sie_fetch_clear, sie_set, riscv::register::sie::Sie
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Applies a guard for `mask` to `csr` and returns the value while the
+    /// guard is held and the bits its drop sets again.
+    fn guard(csr: &mut usize, mask: usize) -> usize {
+        let cleared = cleared_bits(*csr, mask);
+        *csr &= !mask;
+        cleared
+    }
+
+    #[test]
+    fn test_restores_cleared_bit() {
+        let mut hie = Hie::VSEIE | Hie::VSTIE;
+        let cleared = guard(&mut hie, Hie::VSEIE);
+        assert_eq!(hie, Hie::VSTIE);
+        hie |= cleared;
+        assert_eq!(hie, Hie::VSEIE | Hie::VSTIE);
+    }
+
+    #[test]
+    fn test_keeps_clear_bit_clear() {
+        let mut hie = Hie::VSTIE;
+        let cleared = guard(&mut hie, Hie::VSEIE | Hie::SGEIE);
+        assert_eq!(cleared, 0);
+        hie |= cleared;
+        assert_eq!(hie, Hie::VSTIE);
+    }
+
+    #[test]
+    fn test_nested_guards() {
+        let mut hie = Hie::VSEIE | Hie::SGEIE;
+        let outer = guard(&mut hie, Hie::VSEIE);
+        let inner = guard(&mut hie, Hie::VSEIE | Hie::SGEIE);
+        assert_eq!(inner, Hie::SGEIE);
+        hie |= inner;
+        assert_eq!(hie, Hie::SGEIE);
+        hie |= outer;
+        assert_eq!(hie, Hie::VSEIE | Hie::SGEIE);
+    }
+}
@@ -0,0 +1,176 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! KVM Exit-Reason Mapping.
+//!
+//! VMM tooling and trace analyzers are commonly built around the exit reasons
+//! of Linux KVM (`KVM_EXIT_*` in `linux/kvm.h`).
+//! [`KvmExitReason`](crate::kvm::KvmExitReason) mirrors the subset that is
+//! meaningful on RISC-V, and converting a [`GuestTrap`](crate::trap::GuestTrap)
+//! into it classifies an exit the way KVM would report it to user space.
+
+use crate::trap::{FaultAccess, GuestTrap};
+
+/// Encoding of the `WFI` instruction.
+const WFI: usize = 0x1050_0073;
+/// Major opcode of the SYSTEM instructions, which include the CSR accesses.
+const OPCODE_SYSTEM: usize = 0b111_0011;
+/// SBI System Reset extension ID ("SRST").
+const SBI_EXT_SRST: usize = 0x5352_5354;
+
+/// KVM exit reasons, with their `KVM_EXIT_*` values.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum KvmExitReason {
+    /// `KVM_EXIT_UNKNOWN`
+    Unknown = 0,
+    /// `KVM_EXIT_EXCEPTION`
+    Exception = 1,
+    /// `KVM_EXIT_IO`
+    Io = 2,
+    /// `KVM_EXIT_HYPERCALL`
+    Hypercall = 3,
+    /// `KVM_EXIT_DEBUG`
+    Debug = 4,
+    /// `KVM_EXIT_HLT`
+    Hlt = 5,
+    /// `KVM_EXIT_MMIO`
+    Mmio = 6,
+    /// `KVM_EXIT_SHUTDOWN`
+    Shutdown = 8,
+    /// `KVM_EXIT_FAIL_ENTRY`
+    FailEntry = 9,
+    /// `KVM_EXIT_INTR`
+    Intr = 10,
+    /// `KVM_EXIT_INTERNAL_ERROR`
+    InternalError = 17,
+    /// `KVM_EXIT_SYSTEM_EVENT`
+    SystemEvent = 24,
+    /// `KVM_EXIT_RISCV_SBI`
+    RiscvSbi = 35,
+    /// `KVM_EXIT_RISCV_CSR`
+    RiscvCsr = 36,
+}
+
+impl KvmExitReason {
+    /// Returns the `KVM_EXIT_*` value.
+    #[inline]
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+    /// Returns the `KVM_EXIT_*` constant name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "KVM_EXIT_UNKNOWN",
+            Self::Exception => "KVM_EXIT_EXCEPTION",
+            Self::Io => "KVM_EXIT_IO",
+            Self::Hypercall => "KVM_EXIT_HYPERCALL",
+            Self::Debug => "KVM_EXIT_DEBUG",
+            Self::Hlt => "KVM_EXIT_HLT",
+            Self::Mmio => "KVM_EXIT_MMIO",
+            Self::Shutdown => "KVM_EXIT_SHUTDOWN",
+            Self::FailEntry => "KVM_EXIT_FAIL_ENTRY",
+            Self::Intr => "KVM_EXIT_INTR",
+            Self::InternalError => "KVM_EXIT_INTERNAL_ERROR",
+            Self::SystemEvent => "KVM_EXIT_SYSTEM_EVENT",
+            Self::RiscvSbi => "KVM_EXIT_RISCV_SBI",
+            Self::RiscvCsr => "KVM_EXIT_RISCV_CSR",
+        }
+    }
+    /// Classifies an SBI call by its extension ID (`a7`).
+    ///
+    /// System reset requests are system events; every other call is reported
+    /// as an SBI exit.
+    #[inline]
+    pub fn from_sbi_call(eid: usize) -> Self {
+        if eid == SBI_EXT_SRST {
+            Self::SystemEvent
+        } else {
+            Self::RiscvSbi
+        }
+    }
+}
+
+impl From<&GuestTrap> for KvmExitReason {
+    /// Classifies a guest trap without looking at guest registers.
+    ///
+    /// Environment calls map to [`KvmExitReason::RiscvSbi`]; use
+    /// [`KvmExitReason::from_sbi_call`] to single out system events. Load and
+    /// store guest-page faults are assumed to target MMIO regions.
+    fn from(trap: &GuestTrap) -> Self {
+        match *trap {
+            GuestTrap::Interrupt(_) => Self::Intr,
+            GuestTrap::EnvCall => Self::RiscvSbi,
+            GuestTrap::VirtualInstruction { insn: WFI } => Self::Hlt,
+            GuestTrap::VirtualInstruction { insn }
+                if insn & 0x7f == OPCODE_SYSTEM
+                    && matches!((insn >> 12) & 0b111, 1..=3 | 5..=7) =>
+            {
+                Self::RiscvCsr
+            }
+            GuestTrap::VirtualInstruction { .. } => Self::Exception,
+            GuestTrap::GuestPageFault(fault) => match fault.access() {
+                FaultAccess::Load | FaultAccess::Store => Self::Mmio,
+                FaultAccess::Fetch => Self::Exception,
+            },
+            GuestTrap::Exception { code: 3, .. } => Self::Debug,
+            GuestTrap::Exception { .. } => Self::Exception,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trap::TrapInfo;
+
+    fn decode(scause: usize, stval: usize) -> GuestTrap {
+        TrapInfo {
+            scause,
+            stval,
+            hstatus: 1 << 7,
+            ..Default::default()
+        }
+        .decode()
+    }
+
+    #[test]
+    fn test_from_guest_trap() {
+        let kvm = |trap: GuestTrap| KvmExitReason::from(&trap);
+        assert_eq!(kvm(GuestTrap::Interrupt(9)), KvmExitReason::Intr);
+        assert_eq!(kvm(GuestTrap::EnvCall), KvmExitReason::RiscvSbi);
+        assert_eq!(kvm(decode(22, WFI)), KvmExitReason::Hlt);
+        // csrr a0, 0x000 (ustatus)
+        assert_eq!(kvm(decode(22, 0x0000_2573)), KvmExitReason::RiscvCsr);
+        // hlv.w a0, (a1) shares the SYSTEM opcode but is no CSR access
+        assert_eq!(kvm(decode(22, 0x6805_C573)), KvmExitReason::Exception);
+        assert_eq!(kvm(decode(21, 0)), KvmExitReason::Mmio);
+        assert_eq!(kvm(decode(23, 0)), KvmExitReason::Mmio);
+        assert_eq!(kvm(decode(20, 0)), KvmExitReason::Exception);
+        assert_eq!(kvm(decode(3, 0)), KvmExitReason::Debug);
+        assert_eq!(kvm(decode(2, 0)), KvmExitReason::Exception);
+    }
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(KvmExitReason::Mmio.code(), 6);
+        assert_eq!(KvmExitReason::RiscvSbi.code(), 35);
+        assert_eq!(KvmExitReason::SystemEvent.name(), "KVM_EXIT_SYSTEM_EVENT");
+        assert_eq!(
+            KvmExitReason::from_sbi_call(SBI_EXT_SRST),
+            KvmExitReason::SystemEvent
+        );
+        assert_eq!(KvmExitReason::from_sbi_call(0x10), KvmExitReason::RiscvSbi);
+    }
+}
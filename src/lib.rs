@@ -35,5 +35,75 @@
 #![no_std]
 #![allow(missing_docs)]
 
+mod macros;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use riscv;
+}
+
+/// Stands in for a RISC-V instruction when the crate is built for another
+/// architecture, e.g. for host-side unit tests.
+#[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+#[cold]
+#[inline(never)]
+#[track_caller]
+pub(crate) fn unsupported_arch() -> ! {
+    panic!("RISC-V instruction executed on a non-RISC-V target")
+}
+
 /// RISC-V hypervisor extension register definitions and access functions
 pub mod register;
+
+/// Guest calling convention and SBI call helpers
+pub mod abi;
+/// Hypervisor instruction wrappers
+pub mod asm;
+/// Guest software-breakpoint interception helpers
+pub mod breakpoint;
+/// Interrupt delivery diagnostics
+pub mod diag;
+/// Guest crash dumps
+#[cfg(feature = "crash-dump")]
+pub mod dump;
+/// Typestate guest entry configuration
+pub mod entry;
+/// Per-hart errata workarounds
+pub mod errata;
+/// Guest trap frame layout and save/restore assembly
+pub mod frame;
+/// GDB remote-protocol register mapping
+pub mod gdb;
+/// Fault-tolerant guest memory access
+pub mod guest_mem;
+/// Hypervisor extension capability discovery
+pub mod hext;
+/// IMSIC interrupt file memory layout
+pub mod imsic;
+/// Register metadata for debuggers and monitors
+#[cfg(feature = "introspection")]
+pub mod introspection;
+/// Scoped interrupt suppression
+pub mod irq;
+/// KVM-compatible exit-reason mapping
+pub mod kvm;
+/// Page-spanning guest access emulation
+pub mod mmio;
+/// Race-free once-initialized storage
+pub mod once;
+/// Boot-time hypervisor self-test
+pub mod selftest;
+/// Simulated hypervisor CSR file
+#[cfg(all(feature = "sim", target_pointer_width = "64"))]
+pub mod sim;
+/// VM-exit statistics
+pub mod stats;
+/// Atomic multi-CSR updates
+#[cfg(feature = "critical-section")]
+pub mod sync;
+/// Guest timer virtualization
+pub mod timer;
+/// Guest trap decoding
+pub mod trap;
+/// Virtual CPU state
+pub mod vcpu;
@@ -0,0 +1,397 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Register Definition and CSR Access Macros.
+
+/// Defines a typed CSR value and its access functions from a field list.
+///
+/// Each field names its getter, setter and mask constant, followed by its bit
+/// position (`[n]` for a flag, `[lo..hi]` for a multi-bit field) and, for
+/// enumerated fields, `as Type`. `Type` must provide `fn from(usize) -> Type`
+/// and be castable to `usize`. Fields that are not plain read/write end with
+/// their [`Access`](crate::register::Access) variant, e.g. `, Warl`, which is
+/// also noted in the setter documentation. `ReadOnly` fields still name a
+/// setter in the list, but none is generated.
+/// The one-line doc comment of a field is used in the generated accessor
+/// documentation and in [`Field`](crate::register::Field) metadata.
+///
+/// The macro generates the register struct with `bits`/`from_bits`/`write`,
+/// the accessors, a mask constant per field, a `FIELDS` table of
+/// [`Field`](crate::register::Field)s, an `INFO`
+/// [`RegisterInfo`](crate::register::RegisterInfo), `Debug` and `Display`
+/// implementations, the `read`/`write`/`set`/`clear` CSR functions and layout
+/// tests. It is meant to be invoked once per module.
+///
+/// A read-only CSR is declared with `, ReadOnly` after its number. Its fields
+/// list only a getter and a mask constant, and no `write`, setters or
+/// `set`/`clear` functions are generated, since writing the CSR traps.
+///
+/// Masks are computed for the XLEN of the target: on RV32, fields above bit
+/// 31 have an empty mask, read as zero and ignore writes.
+///
+/// # Example
+///
+/// ```
+/// mod hcontext {
+///     riscv_h::register! {
+///         /// Hypervisor Context Register.
+///         Hcontext: 0x6A8 {
+///             /// hypervisor context ID
+///             hcontext, set_hcontext, HCONTEXT: [0..14];
+///         }
+///     }
+/// }
+///
+/// let mut value = hcontext::Hcontext::from_bits(0);
+/// value.set_hcontext(5);
+/// assert_eq!(value.bits() & hcontext::Hcontext::HCONTEXT, 5);
+/// ```
+#[macro_export]
+macro_rules! register {
+    (
+        $(#[$attr:meta])*
+        $name:ident: $csr:literal, ReadOnly {
+            $(
+                #[doc = $doc:literal]
+                $get:ident, $mask:ident: [$($range:tt)+] $(as $ty:ty)?;
+            )*
+        }
+    ) => {
+        $crate::register!(@common [$(#[$attr])*] $name, $csr, [$(
+            $crate::register!(@field $get, $doc, [$($range)+])
+                .with_access($crate::register::Access::ReadOnly),
+        )*]);
+
+        impl $name {
+            $($crate::register!(@getter $doc, $get, $mask, [$($range)+], [$($ty)?]);)*
+        }
+
+        $crate::__private::riscv::read_csr_as!($name, $csr);
+
+        #[cfg(test)]
+        mod field_layout_tests {
+            use super::*;
+
+            #[test]
+            fn fields_are_disjoint() {
+                $crate::register::check_layout($name::FIELDS);
+            }
+
+            #[test]
+            fn getters_read_only_their_field() {
+                $($crate::register!(@test_get $name, $get, $mask, [$($range)+], [$($ty)?]);)*
+            }
+        }
+    };
+    (
+        $(#[$attr:meta])*
+        $name:ident: $csr:literal {
+            $(
+                #[doc = $doc:literal]
+                $get:ident, $set:ident, $mask:ident: [$($range:tt)+] $(as $ty:ty)? $(, $access:ident)?;
+            )*
+        }
+    ) => {
+        $crate::register!(@common [$(#[$attr])*] $name, $csr, [$(
+            $crate::register!(@field $get, $doc, [$($range)+])
+                $(.with_access($crate::register::Access::$access))?,
+        )*]);
+
+        impl $name {
+            /// Writes the register value to the CSR.
+            ///
+            /// # Safety
+            ///
+            /// This function is unsafe because writing to CSR registers can have
+            /// system-wide effects and may violate memory safety guarantees.
+            #[inline]
+            pub unsafe fn write(&self) {
+                // SAFETY: Caller ensures this is safe to execute
+                unsafe { _write(self.bits) };
+            }
+            $(
+                $crate::register!(@getter $doc, $get, $mask, [$($range)+], [$($ty)?]);
+                $crate::register!(@setter $doc, $set, [$($range)+], [$($ty)?], [$($access)?]);
+            )*
+        }
+
+        $crate::__private::riscv::read_csr_as!($name, $csr);
+        $crate::__private::riscv::write_csr!($csr);
+        $crate::__private::riscv::set!($csr);
+        $crate::__private::riscv::clear!($csr);
+
+        #[cfg(test)]
+        mod field_layout_tests {
+            use super::*;
+
+            #[test]
+            fn fields_are_disjoint() {
+                $crate::register::check_layout($name::FIELDS);
+            }
+
+            #[test]
+            fn getters_read_only_their_field() {
+                $($crate::register!(@test_get $name, $get, $mask, [$($range)+], [$($ty)?]);)*
+            }
+
+            #[test]
+            fn setters_touch_only_their_field() {
+                $($crate::register!(@test_set $name, $get, $set, $mask, [$($range)+], [$($ty)?], [$($access)?]);)*
+            }
+        }
+    };
+
+    (@common [$($attr:tt)*] $name:ident, $csr:literal, $fields:tt) => {
+        $($attr)*
+        #[derive(Copy, Clone)]
+        pub struct $name {
+            bits: usize,
+        }
+
+        impl $name {
+            /// Field layout of the register.
+            pub const FIELDS: &'static [$crate::register::Field] = &$fields;
+            /// Static description of the register.
+            pub const INFO: $crate::register::RegisterInfo =
+                $crate::register::RegisterInfo::new(stringify!($name), $csr, Self::FIELDS);
+
+            /// Returns the raw bits of the register.
+            #[inline]
+            pub fn bits(&self) -> usize {
+                self.bits
+            }
+            /// Creates a register value from raw bits.
+            #[inline]
+            pub fn from_bits(x: usize) -> Self {
+                $name { bits: x }
+            }
+        }
+
+        impl core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                $crate::register::debug_fields(f, stringify!($name), self.bits, Self::FIELDS)
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                $crate::register::display_fields(f, stringify!($name), self.bits, Self::FIELDS)
+            }
+        }
+    };
+
+    (@field $get:ident, $doc:literal, [$($range:tt)+]) => {
+        $crate::register::Field::new(
+            stringify!($get),
+            $doc,
+            $crate::register!(@lsb [$($range)+]),
+            $crate::register!(@width [$($range)+]),
+        )
+    };
+
+    (@lsb [$bit:literal]) => { $bit };
+    (@lsb [$lo:literal .. $hi:literal]) => { $lo };
+    (@width [$bit:literal]) => { 1 };
+    (@width [$lo:literal .. $hi:literal]) => { $hi - $lo };
+
+    (@access_note Warl) => {
+        "Unsupported values are replaced by a legal value when the register is written."
+    };
+    (@access_note ReadWrite) => {
+        "The field reads back the last value written."
+    };
+
+    (@getter $doc:literal, $get:ident, $mask:ident, [$bit:literal], []) => {
+        #[doc = concat!("Mask of the", $doc, ".")]
+        pub const $mask: usize = $crate::register::field_mask($bit, 1);
+        #[doc = concat!("Returns the", $doc, ".")]
+        #[inline]
+        pub fn $get(&self) -> bool {
+            self.bits & Self::$mask != 0
+        }
+    };
+    (@getter $doc:literal, $get:ident, $mask:ident, [$lo:literal .. $hi:literal], []) => {
+        #[doc = concat!("Mask of the", $doc, ".")]
+        pub const $mask: usize = $crate::register::field_mask($lo, $hi - $lo);
+        #[doc = concat!("Returns the", $doc, ".")]
+        #[inline]
+        pub fn $get(&self) -> usize {
+            $crate::register::field_get(self.bits, $lo, $hi - $lo)
+        }
+    };
+    (@getter $doc:literal, $get:ident, $mask:ident, [$lo:literal .. $hi:literal], [$ty:ty]) => {
+        #[doc = concat!("Mask of the", $doc, ".")]
+        pub const $mask: usize = $crate::register::field_mask($lo, $hi - $lo);
+        #[doc = concat!("Returns the", $doc, ".")]
+        #[inline]
+        pub fn $get(&self) -> $ty {
+            <$ty>::from($crate::register::field_get(self.bits, $lo, $hi - $lo))
+        }
+    };
+
+    (@setter $doc:literal, $set:ident, [$($range:tt)+], [$($ty:ty)?], [ReadOnly]) => {};
+    (@setter $doc:literal, $set:ident, [$bit:literal], [], [$($access:ident)?]) => {
+        #[doc = concat!("Sets the", $doc, ".")]
+        $(
+            #[doc = ""]
+            #[doc = $crate::register!(@access_note $access)]
+        )?
+        #[inline]
+        pub fn $set(&mut self, val: bool) {
+            self.bits = $crate::register::field_set(self.bits, $bit, 1, val as usize);
+        }
+    };
+    (@setter $doc:literal, $set:ident, [$lo:literal .. $hi:literal], [], [$($access:ident)?]) => {
+        #[doc = concat!("Sets the", $doc, ".")]
+        $(
+            #[doc = ""]
+            #[doc = $crate::register!(@access_note $access)]
+        )?
+        #[inline]
+        pub fn $set(&mut self, val: usize) {
+            self.bits = $crate::register::field_set(self.bits, $lo, $hi - $lo, val);
+        }
+    };
+    (@setter $doc:literal, $set:ident, [$lo:literal .. $hi:literal], [$ty:ty], [$($access:ident)?]) => {
+        #[doc = concat!("Sets the", $doc, ".")]
+        $(
+            #[doc = ""]
+            #[doc = $crate::register!(@access_note $access)]
+        )?
+        #[inline]
+        pub fn $set(&mut self, val: $ty) {
+            self.bits = $crate::register::field_set(self.bits, $lo, $hi - $lo, val as usize);
+        }
+    };
+
+    (@test_get $name:ident, $get:ident, $mask:ident, [$bit:literal], []) => {
+        if $name::$mask != 0 {
+            assert!($name::from_bits($name::$mask).$get(), stringify!($get));
+        }
+        assert!(!$name::from_bits(!$name::$mask).$get(), stringify!($get));
+    };
+    (@test_get $name:ident, $get:ident, $mask:ident, [$lo:literal .. $hi:literal], []) => {
+        let max = $crate::register::field_get(usize::MAX, $lo, $hi - $lo);
+        assert_eq!($name::from_bits(usize::MAX).$get(), max, stringify!($get));
+        assert_eq!($name::from_bits(!$name::$mask).$get(), 0, stringify!($get));
+    };
+    (@test_get $name:ident, $get:ident, $mask:ident, [$lo:literal .. $hi:literal], [$ty:ty]) => {
+        $crate::register!(@test_enum $name, $get, $mask, [$lo .. $hi], $ty, |_| {});
+    };
+
+    (@test_set $name:ident, $get:ident, $set:ident, $mask:ident, [$($range:tt)+], [$($ty:ty)?], [ReadOnly]) => {};
+    (@test_set $name:ident, $get:ident, $set:ident, $mask:ident, [$bit:literal], [], [$($access:ident)?]) => {
+        let mut value = $name::from_bits(0);
+        value.$set(true);
+        assert_eq!(value.bits(), $name::$mask, stringify!($set));
+        value = $name::from_bits(usize::MAX);
+        value.$set(false);
+        assert_eq!(value.bits(), !$name::$mask, stringify!($set));
+    };
+    (@test_set $name:ident, $get:ident, $set:ident, $mask:ident, [$lo:literal .. $hi:literal], [], [$($access:ident)?]) => {
+        let mut value = $name::from_bits(0);
+        value.$set($crate::register::field_get(usize::MAX, $lo, $hi - $lo));
+        assert_eq!(value.bits(), $name::$mask, stringify!($set));
+        value = $name::from_bits(usize::MAX);
+        value.$set(0);
+        assert_eq!(value.bits(), !$name::$mask, stringify!($set));
+    };
+    (@test_set $name:ident, $get:ident, $set:ident, $mask:ident, [$lo:literal .. $hi:literal], [$ty:ty], [$($access:ident)?]) => {
+        $crate::register!(@test_enum $name, $get, $mask, [$lo .. $hi], $ty, |raw: usize| {
+            let mut value = $name::from_bits(0);
+            value.$set(<$ty>::from(raw));
+            assert_eq!(value.bits(), $crate::register::field_set(0, $lo, $hi - $lo, raw), stringify!($set));
+            value = $name::from_bits(usize::MAX);
+            value.$set(<$ty>::from(raw));
+            assert_eq!(value.bits(), $crate::register::field_set(usize::MAX, $lo, $hi - $lo, raw), stringify!($set));
+        });
+    };
+
+    // Calls `$check` with every encoding of an enumerated field that its type
+    // accepts, after checking that the getter decodes it unchanged.
+    (@test_enum $name:ident, $get:ident, $mask:ident, [$lo:literal .. $hi:literal], $ty:ty, $check:expr) => {{
+        extern crate std;
+        let check = $check;
+        for raw in 0..=$crate::register::field_get(usize::MAX, $lo, $hi - $lo) {
+            if std::panic::catch_unwind(move || <$ty>::from(raw)).is_err() {
+                continue;
+            }
+            let value = $name::from_bits($crate::register::field_set(!$name::$mask, $lo, $hi - $lo, raw));
+            assert_eq!(value.$get() as usize, raw, stringify!($get));
+            check(raw);
+        }
+    }};
+}
+
+/// Reads a hypervisor or VS-level CSR by number.
+///
+/// The number is validated at compile time; prefix it with `vendor` to access
+/// a CSR outside the hypervisor ranges. See [`register::csr`](crate::register::csr).
+#[macro_export]
+macro_rules! csr_read {
+    (vendor $csr:expr) => {
+        $crate::register::csr::read_vendor::<{ $csr }>()
+    };
+    ($csr:expr) => {
+        $crate::register::csr::read::<{ $csr }>()
+    };
+}
+
+/// Writes a hypervisor or VS-level CSR by number.
+///
+/// The number is validated at compile time, and writes to read-only CSRs are
+/// rejected; prefix it with `vendor` to access a CSR outside the hypervisor
+/// ranges. Expands to an `unsafe` function call. See
+/// [`register::csr`](crate::register::csr).
+#[macro_export]
+macro_rules! csr_write {
+    (vendor $csr:expr, $value:expr) => {
+        $crate::register::csr::write_vendor::<{ $csr }>($value)
+    };
+    ($csr:expr, $value:expr) => {
+        $crate::register::csr::write::<{ $csr }>($value)
+    };
+}
+
+/// Swaps a hypervisor or VS-level CSR by number, returning its previous value.
+///
+/// The exchange is a single `csrrw`. The number is validated like for
+/// [`csr_write!`]; prefix it with `vendor` to access a CSR outside the
+/// hypervisor ranges. Expands to an `unsafe` function call. See
+/// [`register::csr`](crate::register::csr).
+#[macro_export]
+macro_rules! csr_swap {
+    (vendor $csr:expr, $value:expr) => {
+        $crate::register::csr::swap_vendor::<{ $csr }>($value)
+    };
+    ($csr:expr, $value:expr) => {
+        $crate::register::csr::swap::<{ $csr }>($value)
+    };
+}
+
+/// Brackets an assembly template with `.option arch` so the assembler accepts
+/// instructions of `$arch` (e.g. `"+h"`) even when the target does not
+/// enable the extension. Whether the hart implements it is up to the caller.
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+macro_rules! with_arch {
+    ($arch:literal, $insn:expr) => {
+        concat!(
+            ".option push\n.option arch, ",
+            $arch,
+            "\n",
+            $insn,
+            "\n.option pop"
+        )
+    };
+}
@@ -0,0 +1,245 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Page-Spanning Guest Access Emulation.
+//!
+//! A misaligned guest load or store may cross a page boundary. The two halves
+//! then live at unrelated guest physical addresses, and the guest-page fault
+//! only reports the address of the half that faulted. Emulating the access as
+//! a single operation at that address silently reads or corrupts the wrong
+//! bytes.
+//!
+//! [`chunks`](crate::mmio::chunks) splits an access at page boundaries, and
+//! [`emulate_load`](crate::mmio::emulate_load) and
+//! [`emulate_store`](crate::mmio::emulate_store) translate each chunk through a
+//! [`GuestMemory`](crate::mmio::GuestMemory) and issue one callback per chunk,
+//! assembling the value in little-endian byte order.
+
+use crate::trap::FaultAccess;
+
+/// Size of the pages an access is split at.
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// The part of a guest access that falls into a single page.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Chunk {
+    /// Guest virtual address of the first byte.
+    pub gva: usize,
+    /// Offset of the first byte within the whole access.
+    pub offset: usize,
+    /// Length in bytes.
+    pub len: usize,
+}
+
+/// Returns whether an access of `len` bytes at `gva` crosses a page boundary.
+#[inline]
+pub fn spans_pages(gva: usize, len: usize) -> bool {
+    len > 1 && (gva % PAGE_SIZE) + len > PAGE_SIZE
+}
+
+/// Splits an access of `len` bytes at `gva` into per-page chunks.
+pub fn chunks(gva: usize, len: usize) -> impl Iterator<Item = Chunk> {
+    let mut offset = 0;
+    core::iter::from_fn(move || {
+        if offset == len {
+            return None;
+        }
+        let gva = gva.wrapping_add(offset);
+        let chunk_len = (PAGE_SIZE - gva % PAGE_SIZE).min(len - offset);
+        let chunk = Chunk {
+            gva,
+            offset,
+            len: chunk_len,
+        };
+        offset += chunk_len;
+        Some(chunk)
+    })
+}
+
+/// Guest memory operations needed to emulate a split access.
+pub trait GuestMemory {
+    /// Error reported by the callbacks, such as a nested guest-page fault.
+    type Error;
+
+    /// Translates a guest virtual address through the guest's page tables.
+    fn translate(&mut self, gva: usize, access: FaultAccess) -> Result<usize, Self::Error>;
+    /// Emulates a load of `buf.len()` bytes at `gpa`.
+    fn read(&mut self, gpa: usize, buf: &mut [u8]) -> Result<(), Self::Error>;
+    /// Emulates a store of `data` at `gpa`.
+    fn write(&mut self, gpa: usize, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Returns the guest physical address of `chunk`.
+///
+/// The faulting page is already known from the trap; later pages are
+/// re-walked.
+fn chunk_gpa<M: GuestMemory>(
+    mem: &mut M,
+    chunk: &Chunk,
+    fault_gva: usize,
+    fault_gpa: usize,
+    access: FaultAccess,
+) -> Result<usize, M::Error> {
+    if chunk.gva / PAGE_SIZE == fault_gva / PAGE_SIZE {
+        Ok((fault_gpa & !(PAGE_SIZE - 1)) | (chunk.gva % PAGE_SIZE))
+    } else {
+        mem.translate(chunk.gva, access)
+    }
+}
+
+/// Emulates a load of `len` bytes (at most 8) at `gva`.
+///
+/// `fault_gva` and `fault_gpa` are the addresses reported by the guest-page
+/// fault, e.g. from
+/// [`GuestPageFault::full_gpa`](crate::trap::GuestPageFault::full_gpa).
+pub fn emulate_load<M: GuestMemory>(
+    mem: &mut M,
+    gva: usize,
+    len: usize,
+    fault_gva: usize,
+    fault_gpa: usize,
+) -> Result<u64, M::Error> {
+    assert!(len <= 8, "access wider than 8 bytes");
+    let mut bytes = [0; 8];
+    for chunk in chunks(gva, len) {
+        let gpa = chunk_gpa(mem, &chunk, fault_gva, fault_gpa, FaultAccess::Load)?;
+        mem.read(gpa, &mut bytes[chunk.offset..chunk.offset + chunk.len])?;
+    }
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Emulates a store of the `len` low bytes (at most 8) of `value` at `gva`.
+///
+/// All chunks are translated before the first one is written, so a fault on
+/// the second page leaves guest memory unchanged.
+pub fn emulate_store<M: GuestMemory>(
+    mem: &mut M,
+    gva: usize,
+    len: usize,
+    value: u64,
+    fault_gva: usize,
+    fault_gpa: usize,
+) -> Result<(), M::Error> {
+    assert!(len <= 8, "access wider than 8 bytes");
+    let bytes = value.to_le_bytes();
+    let mut gpas = [0; 2];
+    for (i, chunk) in chunks(gva, len).enumerate() {
+        gpas[i] = chunk_gpa(mem, &chunk, fault_gva, fault_gpa, FaultAccess::Store)?;
+    }
+    for (i, chunk) in chunks(gva, len).enumerate() {
+        mem.write(gpas[i], &bytes[chunk.offset..chunk.offset + chunk.len])?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::vec::Vec;
+
+    /// Maps guest page 0x10 to 0x80 and page 0x11 to 0x42; backs 0x80 and 0x42.
+    struct Memory {
+        ram: [[u8; PAGE_SIZE]; 2],
+        walks: Vec<usize>,
+    }
+
+    impl Memory {
+        fn new() -> Self {
+            Memory {
+                ram: [[0; PAGE_SIZE]; 2],
+                walks: Vec::new(),
+            }
+        }
+        fn page(&mut self, gpa: usize) -> &mut [u8; PAGE_SIZE] {
+            match gpa / PAGE_SIZE {
+                0x80 => &mut self.ram[0],
+                0x42 => &mut self.ram[1],
+                _ => panic!("unmapped gpa {:#x}", gpa),
+            }
+        }
+    }
+
+    impl GuestMemory for Memory {
+        type Error = usize;
+
+        fn translate(&mut self, gva: usize, _: FaultAccess) -> Result<usize, usize> {
+            self.walks.push(gva);
+            match gva / PAGE_SIZE {
+                0x10 => Ok(0x80_000 | gva % PAGE_SIZE),
+                0x11 => Ok(0x42_000 | gva % PAGE_SIZE),
+                _ => Err(gva),
+            }
+        }
+        fn read(&mut self, gpa: usize, buf: &mut [u8]) -> Result<(), usize> {
+            let off = gpa % PAGE_SIZE;
+            buf.copy_from_slice(&self.page(gpa)[off..off + buf.len()]);
+            Ok(())
+        }
+        fn write(&mut self, gpa: usize, data: &[u8]) -> Result<(), usize> {
+            let off = gpa % PAGE_SIZE;
+            self.page(gpa)[off..off + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_chunks() {
+        assert!(!spans_pages(0x10ff8, 8));
+        assert!(spans_pages(0x10ffd, 8));
+        let split: Vec<_> = chunks(0x10ffd, 8).collect();
+        assert_eq!(
+            split,
+            [
+                Chunk {
+                    gva: 0x10ffd,
+                    offset: 0,
+                    len: 3
+                },
+                Chunk {
+                    gva: 0x11000,
+                    offset: 3,
+                    len: 5
+                },
+            ]
+        );
+        assert_eq!(chunks(0x10000, 4).count(), 1);
+    }
+
+    #[test]
+    fn test_split_store_and_load() {
+        let mut mem = Memory::new();
+        let value = 0x0807_0605_0403_0201;
+        emulate_store(&mut mem, 0x10ffd, 8, value, 0x10ffd, 0x80ffd).unwrap();
+        assert_eq!(&mem.ram[0][0xffd..], &[1, 2, 3]);
+        assert_eq!(&mem.ram[1][..5], &[4, 5, 6, 7, 8]);
+        assert_eq!(mem.walks, [0x11000]);
+
+        // Fault reported on the second page
+        let loaded = emulate_load(&mut mem, 0x10ffd, 8, 0x11000, 0x42000).unwrap();
+        assert_eq!(loaded, value);
+        assert_eq!(mem.walks, [0x11000, 0x10ffd]);
+    }
+
+    #[test]
+    fn test_store_fault_leaves_memory_unchanged() {
+        let mut mem = Memory::new();
+        assert_eq!(
+            emulate_store(&mut mem, 0x11ffe, 4, u64::MAX, 0x11ffe, 0x42ffe),
+            Err(0x12000)
+        );
+        assert_eq!(mem.ram[1][0xffe..], [0, 0]);
+    }
+}
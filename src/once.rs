@@ -0,0 +1,198 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Race-Free Once-Initialized Storage.
+//!
+//! Probed limits such as GEILEN, VMIDLEN or a whole
+//! [`HExtInfo`](crate::hext::HExtInfo) are typically computed during boot, when
+//! every hart may reach the same initialization code at once.
+//! [`Once`](crate::once::Once) stores such a value in a `static` without locks
+//! or `static mut`: the first hart to finish publishes its value, and harts
+//! that lose the race keep using their own result.
+//! [`PerHart`](crate::once::PerHart) holds one [`Once`](crate::once::Once) per
+//! hart for values that may differ between harts.
+//!
+//! ```no_run
+//! use riscv_h::hext::HExtInfo;
+//! use riscv_h::once::PerHart;
+//!
+//! static HEXT: PerHart<HExtInfo, 8> = PerHart::new();
+//!
+//! # let hartid = 0;
+//! let info = HEXT.get_or_init(hartid, || unsafe { HExtInfo::probe() });
+//! ```
+
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const EMPTY: u8 = 0;
+const WRITING: u8 = 1;
+const READY: u8 = 2;
+
+/// A value that is written at most once and read lock-free afterwards.
+///
+/// Concurrent initializers may all run; every caller must therefore compute
+/// the same value, which holds for the hardware limits this is meant for.
+pub struct Once<T> {
+    state: AtomicU8,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// SAFETY: The value is written by exactly one thread before `READY` is
+// published with release ordering, and only read after observing `READY`.
+unsafe impl<T: Copy + Send> Sync for Once<T> {}
+
+impl<T: Copy> Once<T> {
+    /// Creates an empty cell.
+    #[inline]
+    pub const fn new() -> Self {
+        Once {
+            state: AtomicU8::new(EMPTY),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+    /// Returns the stored value, if any.
+    #[inline]
+    pub fn get(&self) -> Option<T> {
+        if self.state.load(Ordering::Acquire) == READY {
+            // SAFETY: `READY` is only published after the value is written
+            Some(unsafe { (*self.value.get()).assume_init() })
+        } else {
+            None
+        }
+    }
+    /// Stores `value` unless the cell is already set or being set.
+    ///
+    /// Returns `value` back if another caller got there first.
+    pub fn set(&self, value: T) -> Result<(), T> {
+        if self
+            .state
+            .compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            return Err(value);
+        }
+        // SAFETY: Winning the exchange grants exclusive write access
+        unsafe { (*self.value.get()).write(value) };
+        self.state.store(READY, Ordering::Release);
+        Ok(())
+    }
+    /// Returns the stored value, computing and publishing it on first use.
+    ///
+    /// Never blocks: if another caller is publishing concurrently, the value
+    /// computed by this caller is returned instead.
+    #[inline]
+    pub fn get_or_init(&self, f: impl FnOnce() -> T) -> T {
+        if let Some(value) = self.get() {
+            return value;
+        }
+        let value = f();
+        let _ = self.set(value);
+        value
+    }
+}
+
+impl<T: Copy> Default for Once<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + core::fmt::Debug> core::fmt::Debug for Once<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Once").field(&self.get()).finish()
+    }
+}
+
+/// One [`Once`] per hart, indexed by hart ID.
+pub struct PerHart<T, const N: usize> {
+    slots: [Once<T>; N],
+}
+
+impl<T: Copy, const N: usize> PerHart<T, N> {
+    /// Creates storage for harts `0..N`.
+    #[inline]
+    pub const fn new() -> Self {
+        PerHart {
+            slots: [const { Once::new() }; N],
+        }
+    }
+    /// Returns the slot of `hartid`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hartid` is not below `N`.
+    #[inline]
+    pub fn slot(&self, hartid: usize) -> &Once<T> {
+        &self.slots[hartid]
+    }
+    /// Returns the value stored for `hartid`, if any.
+    #[inline]
+    pub fn get(&self, hartid: usize) -> Option<T> {
+        self.slot(hartid).get()
+    }
+    /// Returns the value of `hartid`, computing it on first use.
+    #[inline]
+    pub fn get_or_init(&self, hartid: usize, f: impl FnOnce() -> T) -> T {
+        self.slot(hartid).get_or_init(f)
+    }
+}
+
+impl<T: Copy, const N: usize> Default for PerHart<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + core::fmt::Debug, const N: usize> core::fmt::Debug for PerHart<T, N> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(&self.slots).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::thread;
+
+    #[test]
+    fn test_once_set_get() {
+        let once = Once::new();
+        assert_eq!(once.get(), None);
+        assert_eq!(once.get_or_init(|| 14), 14);
+        assert_eq!(once.set(7), Err(7));
+        assert_eq!(once.get_or_init(|| unreachable!()), 14);
+    }
+
+    #[test]
+    fn test_concurrent_init() {
+        static GEILEN: Once<usize> = Once::new();
+        let handles: [_; 8] = core::array::from_fn(|_| thread::spawn(|| GEILEN.get_or_init(|| 31)));
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 31);
+        }
+        assert_eq!(GEILEN.get(), Some(31));
+    }
+
+    #[test]
+    fn test_per_hart() {
+        let harts: PerHart<usize, 4> = PerHart::new();
+        assert_eq!(harts.get_or_init(1, || 10), 10);
+        assert_eq!(harts.get(1), Some(10));
+        assert_eq!(harts.get(2), None);
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Memory-Ordering Companions of CSR Writes.
+
+/// Orders all preceding memory and I/O accesses before all following ones.
+#[inline]
+pub(crate) unsafe fn fence() {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        core::arch::asm!("fence iorw, iorw")
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
@@ -0,0 +1,190 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compile-Time-Checked CSR Access.
+//!
+//! [`csr_read!`](crate::csr_read), [`csr_write!`](crate::csr_write) and
+//! [`csr_swap!`](crate::csr_swap) access a CSR by number, for registers that
+//! have no typed module yet. The number is checked at compile time to lie in
+//! the VS-level or hypervisor ranges handled by this crate, and writes are
+//! additionally rejected for read-only CSRs, so a typo fails the build instead
+//! of raising an illegal-instruction exception at run time. Vendor CSRs outside
+//! these ranges must be tagged explicitly:
+//!
+//! ```no_run
+//! let hvictl = riscv_h::csr_read!(0x609);
+//! unsafe { riscv_h::csr_write!(0x609, hvictl) };
+//! let scratch = unsafe { riscv_h::csr_swap!(0x240, 0) };
+//! let custom = riscv_h::csr_read!(vendor 0x7c0);
+//! ```
+//!
+//! ```compile_fail
+//! // 0x060 is not a hypervisor CSR
+//! let hstatus = riscv_h::csr_read!(0x060);
+//! ```
+//!
+//! ```compile_fail
+//! // 0x6c0 is in the custom range of the hypervisor CSRs
+//! let custom = riscv_h::csr_read!(0x6c0);
+//! ```
+//!
+//! ```compile_fail
+//! // hgeip is read-only
+//! unsafe { riscv_h::csr_write!(0xe12, 0) };
+//! ```
+
+/// Returns whether `csr` is a standard VS-level or hypervisor CSR number.
+///
+/// These are the `0x2xx` VS-level and the `0x6xx`, `0xAxx` and `0xExx`
+/// hypervisor ranges of the privileged specification, minus the `0xC0..=0xFF`
+/// sub-range of each, which is reserved for custom CSRs and needs the
+/// `vendor` tag.
+#[inline]
+pub const fn is_hypervisor_csr(csr: u16) -> bool {
+    matches!(csr, 0x200..=0x2bf | 0x600..=0x6bf | 0xa00..=0xabf | 0xe00..=0xebf)
+}
+
+/// Returns whether `csr` is read-only, as encoded in its top two bits.
+#[inline]
+pub const fn is_read_only(csr: u16) -> bool {
+    csr >> 10 == 0b11
+}
+
+/// Reads the hypervisor or VS-level CSR `CSR`.
+#[inline]
+pub fn read<const CSR: u16>() -> usize {
+    const { assert!(is_hypervisor_csr(CSR), "not a hypervisor or VS-level CSR") };
+    read_vendor::<CSR>()
+}
+
+/// Writes the hypervisor or VS-level CSR `CSR`.
+///
+/// # Safety
+///
+/// Writing a CSR can change address translation, interrupt routing or trap
+/// delegation; the caller must ensure `value` is valid for `CSR`.
+#[inline]
+pub unsafe fn write<const CSR: u16>(value: usize) {
+    const { assert!(is_hypervisor_csr(CSR), "not a hypervisor or VS-level CSR") };
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { write_vendor::<CSR>(value) }
+}
+
+/// Writes `value` to the hypervisor or VS-level CSR `CSR` and returns the
+/// previous value, with a single `csrrw`.
+///
+/// # Safety
+///
+/// See [`write()`].
+#[inline]
+pub unsafe fn swap<const CSR: u16>(value: usize) -> usize {
+    const { assert!(is_hypervisor_csr(CSR), "not a hypervisor or VS-level CSR") };
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { swap_vendor::<CSR>(value) }
+}
+
+/// Reads the CSR `CSR` without checking its range.
+#[inline]
+pub fn read_vendor<const CSR: u16>() -> usize {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let bits: usize;
+        // SAFETY: Reading a CSR has no side effects on the CSRs handled here
+        unsafe { core::arch::asm!("csrr {0}, {csr}", out(reg) bits, csr = const CSR) };
+        bits
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+/// Writes the CSR `CSR` without checking its range.
+///
+/// # Safety
+///
+/// See [`write()`].
+#[cfg_attr(
+    not(any(target_arch = "riscv32", target_arch = "riscv64")),
+    allow(unused_variables)
+)]
+#[inline]
+pub unsafe fn write_vendor<const CSR: u16>(value: usize) {
+    const { assert!(!is_read_only(CSR), "CSR is read-only") };
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        core::arch::asm!("csrw {csr}, {0}", in(reg) value, csr = const CSR)
+    };
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+/// Swaps the CSR `CSR` without checking its range.
+///
+/// # Safety
+///
+/// See [`write()`].
+#[cfg_attr(
+    not(any(target_arch = "riscv32", target_arch = "riscv64")),
+    allow(unused_variables)
+)]
+#[inline]
+pub unsafe fn swap_vendor<const CSR: u16>(value: usize) -> usize {
+    const { assert!(!is_read_only(CSR), "CSR is read-only") };
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let bits: usize;
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe {
+            core::arch::asm!("csrrw {0}, {csr}, {1}", out(reg) bits, in(reg) value, csr = const CSR)
+        };
+        bits
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        assert!(is_hypervisor_csr(0x200));
+        assert!(is_hypervisor_csr(0x280));
+        assert!(is_hypervisor_csr(0x680));
+        assert!(is_hypervisor_csr(0xe12));
+        assert!(!is_hypervisor_csr(0x100));
+        assert!(!is_hypervisor_csr(0x300));
+        assert!(!is_hypervisor_csr(0x7c0));
+    }
+
+    #[test]
+    fn test_custom_ranges() {
+        for base in [0x200, 0x600, 0xa00, 0xe00] {
+            assert!(is_hypervisor_csr(base + 0xbf));
+            assert!(!is_hypervisor_csr(base + 0xc0));
+            assert!(!is_hypervisor_csr(base + 0xff));
+            assert!(!is_hypervisor_csr(base + 0x100));
+        }
+        assert!(is_hypervisor_csr(0x6a8));
+        assert!(is_hypervisor_csr(0xeb0));
+    }
+
+    #[test]
+    fn test_read_only() {
+        assert!(is_read_only(0xe12));
+        assert!(!is_read_only(0x600));
+        assert!(!is_read_only(0xa00));
+    }
+}
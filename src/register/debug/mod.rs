@@ -0,0 +1,131 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Debug/Trace Trigger Registers (Sdtrig).
+//!
+//! A hypervisor debugging a guest wants hardware breakpoints and watchpoints
+//! that fire only while the guest runs. `mcontrol6` triggers carry separate
+//! `vs`/`vu` mode bits for that purpose:
+//! [`Mcontrol6::guest`](crate::register::debug::tdata1::Mcontrol6::guest)
+//! builds a trigger matching in VS-mode and VU-mode but never in M-, HS- or
+//! U-mode, and [`set_guest_trigger`](crate::register::debug::set_guest_trigger)
+//! programs it. Combined with [`hcontext`](crate::register::hcontext), a
+//! trigger can further be limited to a single guest.
+//!
+//! The trigger CSRs are machine-level registers. HS-mode software either
+//! needs M-mode to grant access or programs the same `tdata` values through
+//! the SBI Debug Triggers extension.
+//!
+//! A match raises a breakpoint exception; see the
+//! [`breakpoint`](crate::breakpoint) module for routing it to the hypervisor.
+
+/// Trigger data register 1
+pub mod tdata1;
+/// Trigger data register 2
+pub mod tdata2;
+/// Trigger select register
+pub mod tselect;
+
+use self::tdata1::Mcontrol6;
+
+/// Trigger type of `mcontrol6` triggers.
+pub const TYPE_MCONTROL6: usize = 6;
+
+/// Guest accesses a trigger matches.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TriggerKind {
+    /// Instruction fetch from the address (hardware breakpoint).
+    Execute,
+    /// Load from the address.
+    Load,
+    /// Store to the address.
+    Store,
+    /// Load from or store to the address.
+    Access,
+}
+
+impl Mcontrol6 {
+    /// Returns a trigger raising a breakpoint exception when the guest
+    /// performs `kind` at exactly the address in `tdata2`.
+    ///
+    /// It matches in VS-mode and VU-mode only.
+    pub fn guest(kind: TriggerKind) -> Self {
+        let mut trigger = Mcontrol6::from_bits(0);
+        trigger.set_trigger_type(TYPE_MCONTROL6);
+        trigger.set_vs(true);
+        trigger.set_vu(true);
+        trigger.set_execute(kind == TriggerKind::Execute);
+        trigger.set_load(matches!(kind, TriggerKind::Load | TriggerKind::Access));
+        trigger.set_store(matches!(kind, TriggerKind::Store | TriggerKind::Access));
+        trigger
+    }
+    /// Returns whether the trigger can fire outside of a guest.
+    #[inline]
+    pub fn matches_host(&self) -> bool {
+        self.m() || self.s() || self.u()
+    }
+}
+
+/// Programs trigger `index` to fire when a guest performs `kind` at `addr`.
+///
+/// The trigger is disabled while `tdata2` is updated, so it never fires on a
+/// mix of the old and new configuration.
+///
+/// # Safety
+///
+/// Requires access to the trigger CSRs, and trigger `index` must not be in
+/// use by anyone else.
+pub unsafe fn set_guest_trigger(index: usize, kind: TriggerKind, addr: usize) {
+    // SAFETY: Caller ensures the trigger is ours to program
+    unsafe {
+        tselect::write(index);
+        Mcontrol6::from_bits(0).write();
+        tdata2::write(addr);
+        Mcontrol6::guest(kind).write();
+    }
+}
+
+/// Disables trigger `index`.
+///
+/// # Safety
+///
+/// Requires access to the trigger CSRs, and trigger `index` must not be in
+/// use by anyone else.
+pub unsafe fn clear_trigger(index: usize) {
+    // SAFETY: Caller ensures the trigger is ours to program
+    unsafe {
+        tselect::write(index);
+        Mcontrol6::from_bits(0).write();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_trigger_modes() {
+        let trigger = Mcontrol6::guest(TriggerKind::Execute);
+        assert_eq!(trigger.trigger_type(), TYPE_MCONTROL6);
+        assert!(trigger.vs() && trigger.vu());
+        assert!(!trigger.matches_host());
+        assert!(trigger.execute());
+        assert!(!trigger.load() && !trigger.store());
+        assert_eq!(trigger.action(), 0);
+        assert_eq!(trigger.bits(), (6 << 60) | (1 << 24) | (1 << 23) | (1 << 2));
+
+        let watch = Mcontrol6::guest(TriggerKind::Access);
+        assert!(watch.load() && watch.store() && !watch.execute());
+    }
+}
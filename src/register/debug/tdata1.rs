@@ -0,0 +1,64 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Trigger Data Register 1.
+//!
+//! Configures the trigger selected by `tselect`. Its layout depends on the
+//! trigger type in the top four bits; this module describes the type 6
+//! (`mcontrol6`) address/data match trigger layout used for breakpoints and
+//! watchpoints.
+
+crate::register! {
+    /// Trigger Data Register 1 in `mcontrol6` format.
+    Mcontrol6: 0x7A1 {
+        /// trigger type
+        trigger_type, set_trigger_type, TYPE: [60..64];
+        /// debug-mode-only access to the trigger
+        dmode, set_dmode, DMODE: [59];
+        /// uncertain match status
+        uncertain, set_uncertain, UNCERTAIN: [26];
+        /// high bit of the hit status
+        hit1, set_hit1, HIT1: [25];
+        /// match in VS-mode
+        vs, set_vs, VS: [24];
+        /// match in VU-mode
+        vu, set_vu, VU: [23];
+        /// low bit of the hit status
+        hit0, set_hit0, HIT0: [22];
+        /// compare data instead of address
+        select, set_select, SELECT: [21];
+        /// access size to match
+        size, set_size, SIZE: [16..19];
+        /// action taken on a match
+        action, set_action, ACTION: [12..16];
+        /// chain with the next trigger
+        chain, set_chain, CHAIN: [11];
+        /// match condition
+        match_mode, set_match_mode, MATCH: [7..11];
+        /// match in M-mode
+        m, set_m, M: [6];
+        /// fire on uncertain matches
+        uncertainen, set_uncertainen, UNCERTAINEN: [5];
+        /// match in S-mode (HS-mode)
+        s, set_s, S: [4];
+        /// match in U-mode
+        u, set_u, U: [3];
+        /// match instruction fetches
+        execute, set_execute, EXECUTE: [2];
+        /// match stores
+        store, set_store, STORE: [1];
+        /// match loads
+        load, set_load, LOAD: [0];
+    }
+}
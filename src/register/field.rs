@@ -0,0 +1,328 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Register Field Metadata.
+//!
+//! Every register defined with [`register!`](crate::register!) describes its
+//! fields in a `FIELDS` table and itself in an `INFO`
+//! [`RegisterInfo`](crate::register::RegisterInfo), which drive its `Debug` and
+//! `Display` output and let tools decode register values generically.
+
+use core::fmt;
+
+/// How software may access a field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Reads return the last value written.
+    ReadWrite,
+    /// Writes are ignored.
+    ReadOnly,
+    /// Writes of unsupported values are replaced by a legal value.
+    Warl,
+}
+
+/// A field of a CSR.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    name: &'static str,
+    description: &'static str,
+    lsb: usize,
+    width: usize,
+    access: Access,
+}
+
+impl Field {
+    /// Creates a field description.
+    ///
+    /// A leading space, as left by a `///` doc comment, is removed from
+    /// `description`.
+    pub const fn new(
+        name: &'static str,
+        description: &'static str,
+        lsb: usize,
+        width: usize,
+    ) -> Self {
+        let description = match description.as_bytes() {
+            [b' ', ..] => description.split_at(1).1,
+            _ => description,
+        };
+        Field {
+            name,
+            description,
+            lsb,
+            width,
+            access: Access::ReadWrite,
+        }
+    }
+    /// Returns the field with the given access type.
+    pub const fn with_access(self, access: Access) -> Self {
+        Field { access, ..self }
+    }
+    /// Returns the name of the field, which is also its getter name.
+    #[inline]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+    /// Returns the human-readable description of the field.
+    #[inline]
+    pub const fn description(&self) -> &'static str {
+        self.description
+    }
+    /// Returns the index of the lowest bit of the field.
+    #[inline]
+    pub const fn lsb(&self) -> usize {
+        self.lsb
+    }
+    /// Returns the width of the field in bits.
+    #[inline]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+    /// Returns how software may access the field.
+    #[inline]
+    pub const fn access(&self) -> Access {
+        self.access
+    }
+    /// Returns the mask of the field within the register.
+    ///
+    /// The mask only covers the bits that exist at the XLEN of the target, so
+    /// it is empty for a field above bit 31 on RV32.
+    #[inline]
+    pub const fn mask(&self) -> usize {
+        field_mask(self.lsb, self.width)
+    }
+    /// Extracts the value of the field from raw register bits.
+    #[inline]
+    pub const fn get(&self, bits: usize) -> usize {
+        field_get(bits, self.lsb, self.width)
+    }
+}
+
+/// Returns the mask of the `width` bits starting at `lsb`, clipped to XLEN.
+#[doc(hidden)]
+#[inline]
+pub const fn field_mask(lsb: usize, width: usize) -> usize {
+    let xlen = usize::BITS as usize;
+    if width == 0 || lsb >= xlen {
+        return 0;
+    }
+    let width = if width > xlen - lsb {
+        xlen - lsb
+    } else {
+        width
+    };
+    (usize::MAX >> (xlen - width)) << lsb
+}
+
+/// Extracts the `width` bits starting at `lsb` from `bits`.
+#[doc(hidden)]
+#[inline]
+pub const fn field_get(bits: usize, lsb: usize, width: usize) -> usize {
+    match field_mask(lsb, width) {
+        0 => 0,
+        mask => (bits & mask) >> lsb,
+    }
+}
+
+/// Replaces the `width` bits starting at `lsb` in `bits` with `value`.
+///
+/// Fields that do not exist at the XLEN of the target are left out.
+///
+/// # Panics
+///
+/// Panics if `value` does not fit in the field.
+#[doc(hidden)]
+#[inline]
+#[track_caller]
+pub const fn field_set(bits: usize, lsb: usize, width: usize, value: usize) -> usize {
+    let mask = field_mask(lsb, width);
+    if mask == 0 {
+        return bits;
+    }
+    assert!(value <= mask >> lsb, "value does not fit in the field");
+    (bits & !mask) | (value << lsb)
+}
+
+/// A CSR and the layout of its fields.
+#[derive(Copy, Clone, Debug)]
+pub struct RegisterInfo {
+    name: &'static str,
+    csr: u16,
+    fields: &'static [Field],
+}
+
+impl RegisterInfo {
+    /// Creates a register description.
+    pub const fn new(name: &'static str, csr: u16, fields: &'static [Field]) -> Self {
+        RegisterInfo { name, csr, fields }
+    }
+    /// Returns the name of the register type.
+    #[inline]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+    /// Returns the CSR number.
+    #[inline]
+    pub const fn csr(&self) -> u16 {
+        self.csr
+    }
+    /// Returns the fields of the register.
+    #[inline]
+    pub const fn fields(&self) -> &'static [Field] {
+        self.fields
+    }
+}
+
+/// Formats register bits as a struct listing the raw bits and every field.
+#[doc(hidden)]
+pub fn debug_fields(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    bits: usize,
+    fields: &[Field],
+) -> fmt::Result {
+    let mut s = f.debug_struct(name);
+    s.field("bits", &bits);
+    for field in fields {
+        if field.width == 1 {
+            s.field(field.name, &(field.get(bits) != 0));
+        } else {
+            s.field(field.name, &field.get(bits));
+        }
+    }
+    s.finish()
+}
+
+/// Formats register bits as `Name(0xbits) field=0xvalue flag ...`, listing
+/// only the flags that are set.
+#[doc(hidden)]
+pub fn display_fields(
+    f: &mut fmt::Formatter<'_>,
+    name: &str,
+    bits: usize,
+    fields: &[Field],
+) -> fmt::Result {
+    write!(f, "{}({:#x})", name, bits)?;
+    for field in fields {
+        match (field.width, field.get(bits)) {
+            (1, 0) => {}
+            (1, _) => write!(f, " {}", field.name)?,
+            (_, value) => write!(f, " {}={:#x}", field.name, value)?,
+        }
+    }
+    Ok(())
+}
+
+/// Panics if any two fields overlap or a field exceeds the widest XLEN.
+#[doc(hidden)]
+pub fn check_layout(fields: &[Field]) {
+    let mut seen = 0;
+    for field in fields {
+        assert!(
+            field.width > 0 && field.lsb + field.width <= 64,
+            "field {} out of range",
+            field.name
+        );
+        assert_eq!(seen & field.mask(), 0, "field {} overlaps", field.name);
+        seen |= field.mask();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+
+    const FIELDS: &[Field] = &[
+        Field::new("mode", " translation mode", 60, 4),
+        Field::new("sie", " interrupt enable", 1, 1),
+        Field::new("spp", " previous privilege", 8, 1),
+    ];
+
+    struct Value(usize);
+
+    impl fmt::Debug for Value {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            debug_fields(f, "Value", self.0, FIELDS)
+        }
+    }
+
+    impl fmt::Display for Value {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            display_fields(f, "Value", self.0, FIELDS)
+        }
+    }
+
+    #[test]
+    fn test_field() {
+        let field = FIELDS[0];
+        assert_eq!(field.name(), "mode");
+        assert_eq!(field.description(), "translation mode");
+        assert_eq!(field.mask(), 0xf << 60);
+        assert_eq!(field.get(9 << 60 | 0xff), 9);
+        assert_eq!(field.access(), Access::ReadWrite);
+        assert_eq!(field.with_access(Access::Warl).access(), Access::Warl);
+        assert_eq!(Field::new("all", "", 0, 64).mask(), usize::MAX);
+    }
+
+    #[test]
+    fn test_field_bits() {
+        assert_eq!(field_mask(4, 4), 0xf0);
+        assert_eq!(field_mask(0, 0), 0);
+        assert_eq!(field_mask(usize::BITS as usize, 4), 0);
+        assert_eq!(
+            field_mask(usize::BITS as usize - 2, 4),
+            0b11 << (usize::BITS - 2)
+        );
+        assert_eq!(field_get(0xabcd, 4, 8), 0xbc);
+        assert_eq!(field_get(usize::MAX, usize::BITS as usize, 4), 0);
+        assert_eq!(field_set(0xabcd, 4, 8, 0x12), 0xa12d);
+        assert_eq!(field_set(0xabcd, usize::BITS as usize, 4, 0), 0xabcd);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_field_set_overflow() {
+        field_set(0, 4, 4, 0x10);
+    }
+
+    #[test]
+    fn test_formatting() {
+        let value = Value(8 << 60 | 1 << 1);
+        assert_eq!(
+            format!("{:?}", value),
+            format!(
+                "Value {{ bits: {}, mode: 8, sie: true, spp: false }}",
+                value.0
+            )
+        );
+        assert_eq!(
+            format!("{}", value),
+            "Value(0x8000000000000002) mode=0x8 sie"
+        );
+    }
+
+    #[test]
+    fn test_check_layout() {
+        check_layout(FIELDS);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_check_layout_overlap() {
+        check_layout(&[Field::new("a", "", 0, 4), Field::new("b", "", 3, 1)]);
+    }
+}
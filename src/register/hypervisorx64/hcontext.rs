@@ -0,0 +1,43 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Context Register.
+//!
+//! With Sdtrig, `hcontext` holds an identifier of the currently running guest
+//! that triggers can match on through the `mhselect`/`mhvalue` fields of
+//! their `textra` register, so a trigger armed for one guest does not fire in
+//! another. A
+//! hypervisor typically writes the guest's VMID here on every world switch.
+//! The field is WARL; on RV64 at most 14 bits are implemented.
+
+crate::register! {
+    /// Hypervisor Context Register.
+    Hcontext: 0x6A8 {
+        /// hypervisor context ID
+        hcontext, set_hcontext, HCONTEXT: [0..14], Warl;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hcontext() {
+        let mut hcontext = Hcontext::from_bits(0);
+        hcontext.set_hcontext(0x2a);
+        assert_eq!(hcontext.hcontext(), 0x2a);
+        assert_eq!(Hcontext::HCONTEXT, 0x3fff);
+    }
+}
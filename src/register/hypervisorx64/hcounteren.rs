@@ -12,365 +12,172 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Hypervisor Guest External Interrupt Pending Register.
+//! Hypervisor Counter Enable Register.
+//!
+//! `hcounteren` controls which of the `cycle`, `time`, `instret` and
+//! `hpmcounter3`..`hpmcounter31` counters VS-mode and VU-mode may read. A
+//! guest read of a disabled counter raises a virtual-instruction exception,
+//! which lets the hypervisor emulate it.
+//!
+//! Most hypervisors either expose every counter
+//! ([`enable_all`](crate::register::hcounteren::enable_all)) or only `cycle`,
+//! `time` and `instret`;
+//! [`HcounterenBuilder`](crate::register::hcounteren::HcounterenBuilder)
+//! assembles other combinations.
+//!
+//! ```
+//! use riscv_h::register::hcounteren::HcounterenBuilder;
+//!
+//! let value = HcounterenBuilder::new().basic().hpm_range(3..=6).build();
+//! assert_eq!(value.bits(), 0b111_1111);
+//! ```
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use core::ops::RangeInclusive;
+use riscv::set_clear_csr;
 
-/// Hypervisor Counter Enable Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Hcounteren {
-    bits: usize,
+/// Bits of `cycle`, `time` and `instret`.
+pub const BASIC: usize = 0b111;
+/// Bits of every counter.
+pub const ALL: usize = 0xffff_ffff;
+
+crate::register! {
+    /// Hypervisor Counter Enable Register.
+    Hcounteren: 0x606 {
+        /// status of the cycle counter
+        cy, set_cy, CY: [0];
+        /// status of the timer counter
+        tm, set_tm, TM: [1];
+        /// status of the interrupt counter
+        ir, set_ir, IR: [2];
+        /// status of the hypervisor performance monitor 3
+        hpm3, set_hpm3, HPM3: [3];
+        /// status of the hypervisor performance monitor 4
+        hpm4, set_hpm4, HPM4: [4];
+        /// status of the hypervisor performance monitor 5
+        hpm5, set_hpm5, HPM5: [5];
+        /// status of the hypervisor performance monitor 6
+        hpm6, set_hpm6, HPM6: [6];
+        /// status of the hypervisor performance monitor 7
+        hpm7, set_hpm7, HPM7: [7];
+        /// status of the hypervisor performance monitor 8
+        hpm8, set_hpm8, HPM8: [8];
+        /// status of the hypervisor performance monitor 9
+        hpm9, set_hpm9, HPM9: [9];
+        /// status of the hypervisor performance monitor 10
+        hpm10, set_hpm10, HPM10: [10];
+        /// status of the hypervisor performance monitor 11
+        hpm11, set_hpm11, HPM11: [11];
+        /// status of the hypervisor performance monitor 12
+        hpm12, set_hpm12, HPM12: [12];
+        /// status of the hypervisor performance monitor 13
+        hpm13, set_hpm13, HPM13: [13];
+        /// status of the hypervisor performance monitor 14
+        hpm14, set_hpm14, HPM14: [14];
+        /// status of the hypervisor performance monitor 15
+        hpm15, set_hpm15, HPM15: [15];
+        /// status of the hypervisor performance monitor 16
+        hpm16, set_hpm16, HPM16: [16];
+        /// status of the hypervisor performance monitor 17
+        hpm17, set_hpm17, HPM17: [17];
+        /// status of the hypervisor performance monitor 18
+        hpm18, set_hpm18, HPM18: [18];
+        /// status of the hypervisor performance monitor 19
+        hpm19, set_hpm19, HPM19: [19];
+        /// status of the hypervisor performance monitor 20
+        hpm20, set_hpm20, HPM20: [20];
+        /// status of the hypervisor performance monitor 21
+        hpm21, set_hpm21, HPM21: [21];
+        /// status of the hypervisor performance monitor 22
+        hpm22, set_hpm22, HPM22: [22];
+        /// status of the hypervisor performance monitor 23
+        hpm23, set_hpm23, HPM23: [23];
+        /// status of the hypervisor performance monitor 24
+        hpm24, set_hpm24, HPM24: [24];
+        /// status of the hypervisor performance monitor 25
+        hpm25, set_hpm25, HPM25: [25];
+        /// status of the hypervisor performance monitor 26
+        hpm26, set_hpm26, HPM26: [26];
+        /// status of the hypervisor performance monitor 27
+        hpm27, set_hpm27, HPM27: [27];
+        /// status of the hypervisor performance monitor 28
+        hpm28, set_hpm28, HPM28: [28];
+        /// status of the hypervisor performance monitor 29
+        hpm29, set_hpm29, HPM29: [29];
+        /// status of the hypervisor performance monitor 30
+        hpm30, set_hpm30, HPM30: [30];
+        /// status of the hypervisor performance monitor 31
+        hpm31, set_hpm31, HPM31: [31];
+    }
 }
 
 impl Hcounteren {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hcounteren { bits: x }
-    }
-    /// Writes the register value to the CSR.
+    /// Returns whether guest access to `hpmcounter<n>` is enabled.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the status of the cycle counter.
-    #[inline]
-    pub fn cy(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-    /// Sets the status of the cycle counter.
-    #[inline]
-    pub fn set_cy(&mut self, val: bool) {
-        self.bits.set_bit(0, val);
-    }
-    /// Returns the status of the timer counter.
-    #[inline]
-    pub fn tm(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-    /// Sets the status of the timer counter.
-    #[inline]
-    pub fn set_tm(&mut self, val: bool) {
-        self.bits.set_bit(1, val);
-    }
-    /// Returns the status of the interrupt counter.
-    #[inline]
-    pub fn ir(&self) -> bool {
-        self.bits.get_bit(2)
-    }
-    /// Sets the status of the interrupt counter.
-    #[inline]
-    pub fn set_ir(&mut self, val: bool) {
-        self.bits.set_bit(2, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 3.
-    #[inline]
-    pub fn hpm3(&self) -> bool {
-        self.bits.get_bit(3)
-    }
-    /// Sets the status of the hypervisor performance monitor 3.
-    #[inline]
-    pub fn set_hpm3(&mut self, val: bool) {
-        self.bits.set_bit(3, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 4.
-    #[inline]
-    pub fn hpm4(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-    /// Sets the status of the hypervisor performance monitor 4.
-    #[inline]
-    pub fn set_hpm4(&mut self, val: bool) {
-        self.bits.set_bit(4, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 5.
-    #[inline]
-    pub fn hpm5(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-    /// Sets the status of the hypervisor performance monitor 5.
-    #[inline]
-    pub fn set_hpm5(&mut self, val: bool) {
-        self.bits.set_bit(5, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 6.
-    #[inline]
-    pub fn hpm6(&self) -> bool {
-        self.bits.get_bit(6)
-    }
-    /// Sets the status of the hypervisor performance monitor 6.
-    #[inline]
-    pub fn set_hpm6(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 7.
-    #[inline]
-    pub fn hpm7(&self) -> bool {
-        self.bits.get_bit(7)
-    }
-    /// Sets the status of the hypervisor performance monitor 7.
-    #[inline]
-    pub fn set_hpm7(&mut self, val: bool) {
-        self.bits.set_bit(7, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 8.
-    #[inline]
-    pub fn hpm8(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-    /// Sets the status of the hypervisor performance monitor 8.
-    #[inline]
-    pub fn set_hpm8(&mut self, val: bool) {
-        self.bits.set_bit(8, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 9.
-    #[inline]
-    pub fn hpm9(&self) -> bool {
-        self.bits.get_bit(9)
-    }
-    /// Sets the status of the hypervisor performance monitor 9.
-    #[inline]
-    pub fn set_hpm9(&mut self, val: bool) {
-        self.bits.set_bit(9, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 10.
-    #[inline]
-    pub fn hpm10(&self) -> bool {
-        self.bits.get_bit(10)
-    }
-    /// Sets the status of the hypervisor performance monitor 10.
-    #[inline]
-    pub fn set_hpm10(&mut self, val: bool) {
-        self.bits.set_bit(10, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 11.
-    #[inline]
-    pub fn hpm11(&self) -> bool {
-        self.bits.get_bit(11)
-    }
-    /// Sets the status of the hypervisor performance monitor 11.
-    #[inline]
-    pub fn set_hpm11(&mut self, val: bool) {
-        self.bits.set_bit(11, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 12.
+    /// Panics if `n` is not in `3..=31`.
     #[inline]
-    pub fn hpm12(&self) -> bool {
-        self.bits.get_bit(12)
+    pub fn hpm(&self, n: usize) -> bool {
+        self.bits & hpm_mask(n) != 0
     }
-    /// Sets the status of the hypervisor performance monitor 12.
-    #[inline]
-    pub fn set_hpm12(&mut self, val: bool) {
-        self.bits.set_bit(12, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 13.
-    #[inline]
-    pub fn hpm13(&self) -> bool {
-        self.bits.get_bit(13)
-    }
-    /// Sets the status of the hypervisor performance monitor 13.
-    #[inline]
-    pub fn set_hpm13(&mut self, val: bool) {
-        self.bits.set_bit(13, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 14.
-    #[inline]
-    pub fn hpm14(&self) -> bool {
-        self.bits.get_bit(14)
-    }
-    /// Sets the status of the hypervisor performance monitor 14.
-    #[inline]
-    pub fn set_hpm14(&mut self, val: bool) {
-        self.bits.set_bit(14, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 15.
-    #[inline]
-    pub fn hpm15(&self) -> bool {
-        self.bits.get_bit(15)
-    }
-    /// Sets the status of the hypervisor performance monitor 15.
-    #[inline]
-    pub fn set_hpm15(&mut self, val: bool) {
-        self.bits.set_bit(15, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 16.
-    #[inline]
-    pub fn hpm16(&self) -> bool {
-        self.bits.get_bit(16)
-    }
-    /// Sets the status of the hypervisor performance monitor 16.
-    #[inline]
-    pub fn set_hpm16(&mut self, val: bool) {
-        self.bits.set_bit(16, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 17.
-    #[inline]
-    pub fn hpm17(&self) -> bool {
-        self.bits.get_bit(17)
-    }
-    /// Sets the status of the hypervisor performance monitor 17.
-    #[inline]
-    pub fn set_hpm17(&mut self, val: bool) {
-        self.bits.set_bit(17, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 18.
-    #[inline]
-    pub fn hpm18(&self) -> bool {
-        self.bits.get_bit(18)
-    }
-    /// Sets the status of the hypervisor performance monitor 18.
-    #[inline]
-    pub fn set_hpm18(&mut self, val: bool) {
-        self.bits.set_bit(18, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 19.
-    #[inline]
-    pub fn hpm19(&self) -> bool {
-        self.bits.get_bit(19)
-    }
-    /// Sets the status of the hypervisor performance monitor 19.
-    #[inline]
-    pub fn set_hpm19(&mut self, val: bool) {
-        self.bits.set_bit(19, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 20.
-    #[inline]
-    pub fn hpm20(&self) -> bool {
-        self.bits.get_bit(20)
-    }
-    /// Sets the status of the hypervisor performance monitor 20.
-    #[inline]
-    pub fn set_hpm20(&mut self, val: bool) {
-        self.bits.set_bit(20, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 21.
-    #[inline]
-    pub fn hpm21(&self) -> bool {
-        self.bits.get_bit(21)
-    }
-    /// Sets the status of the hypervisor performance monitor 21.
-    #[inline]
-    pub fn set_hpm21(&mut self, val: bool) {
-        self.bits.set_bit(21, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 22.
-    #[inline]
-    pub fn hpm22(&self) -> bool {
-        self.bits.get_bit(22)
-    }
-    /// Sets the status of the hypervisor performance monitor 22.
-    #[inline]
-    pub fn set_hpm22(&mut self, val: bool) {
-        self.bits.set_bit(22, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 23.
-    #[inline]
-    pub fn hpm23(&self) -> bool {
-        self.bits.get_bit(23)
-    }
-    /// Sets the status of the hypervisor performance monitor 23.
-    #[inline]
-    pub fn set_hpm23(&mut self, val: bool) {
-        self.bits.set_bit(23, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 24.
-    #[inline]
-    pub fn hpm24(&self) -> bool {
-        self.bits.get_bit(24)
-    }
-    /// Sets the status of the hypervisor performance monitor 24.
-    #[inline]
-    pub fn set_hpm24(&mut self, val: bool) {
-        self.bits.set_bit(24, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 25.
-    #[inline]
-    pub fn hpm25(&self) -> bool {
-        self.bits.get_bit(25)
-    }
-    /// Sets the status of the hypervisor performance monitor 25.
-    #[inline]
-    pub fn set_hpm25(&mut self, val: bool) {
-        self.bits.set_bit(25, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 26.
-    #[inline]
-    pub fn hpm26(&self) -> bool {
-        self.bits.get_bit(26)
-    }
-    /// Sets the status of the hypervisor performance monitor 26.
-    #[inline]
-    pub fn set_hpm26(&mut self, val: bool) {
-        self.bits.set_bit(26, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 27.
-    #[inline]
-    pub fn hpm27(&self) -> bool {
-        self.bits.get_bit(27)
-    }
-    /// Sets the status of the hypervisor performance monitor 27.
-    #[inline]
-    pub fn set_hpm27(&mut self, val: bool) {
-        self.bits.set_bit(27, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 28.
-    #[inline]
-    pub fn hpm28(&self) -> bool {
-        self.bits.get_bit(28)
-    }
-    /// Sets the status of the hypervisor performance monitor 28.
-    #[inline]
-    pub fn set_hpm28(&mut self, val: bool) {
-        self.bits.set_bit(28, val);
-    }
-    /// Returns the status of the hypervisor performance monitor 29.
-    #[inline]
-    pub fn hpm29(&self) -> bool {
-        self.bits.get_bit(29)
-    }
-    /// Sets the status of the hypervisor performance monitor 29.
+    /// Enables or disables guest access to `hpmcounter<n>`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `n` is not in `3..=31`.
     #[inline]
-    pub fn set_hpm29(&mut self, val: bool) {
-        self.bits.set_bit(29, val);
+    pub fn set_hpm(&mut self, n: usize, value: bool) {
+        let mask = hpm_mask(n);
+        if value {
+            self.bits |= mask;
+        } else {
+            self.bits &= !mask;
+        }
     }
-    /// Returns the status of the hypervisor performance monitor 30.
+}
+
+/// Builder of `hcounteren` values.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HcounterenBuilder {
+    bits: usize,
+}
+
+impl HcounterenBuilder {
+    /// Starts with every counter disabled.
     #[inline]
-    pub fn hpm30(&self) -> bool {
-        self.bits.get_bit(30)
+    pub const fn new() -> Self {
+        HcounterenBuilder { bits: 0 }
     }
-    /// Sets the status of the hypervisor performance monitor 30.
+    /// Enables `cycle`, `time` and `instret`.
     #[inline]
-    pub fn set_hpm30(&mut self, val: bool) {
-        self.bits.set_bit(30, val);
+    pub const fn basic(mut self) -> Self {
+        self.bits |= BASIC;
+        self
     }
-    /// Returns the status of the hypervisor performance monitor 31.
-    #[inline]
-    pub fn hpm31(&self) -> bool {
-        self.bits.get_bit(31)
+    /// Enables `hpmcounter<n>` for every `n` in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is not within `3..=31`.
+    pub fn hpm_range(mut self, range: RangeInclusive<usize>) -> Self {
+        for n in range {
+            self.bits |= hpm_mask(n);
+        }
+        self
     }
-    /// Sets the status of the hypervisor performance monitor 31.
+    /// Returns the assembled register value.
     #[inline]
-    pub fn set_hpm31(&mut self, val: bool) {
-        self.bits.set_bit(31, val);
+    pub fn build(self) -> Hcounteren {
+        Hcounteren::from_bits(self.bits)
     }
 }
 
-read_csr_as!(Hcounteren, 0x606);
-write_csr!(0x606);
-set!(0x606);
-clear!(0x606);
+/// Returns the `hcounteren` bit of `hpmcounter<n>`.
+#[inline]
+fn hpm_mask(n: usize) -> usize {
+    assert!((3..=31).contains(&n), "hpmcounter{n} does not exist");
+    1 << n
+}
 
 // bit ops
 set_clear_csr!(
@@ -470,4 +277,83 @@ set_clear_csr!(
     /// Hypervisor performance monitor 31 enable.
     , set_hpm31, clear_hpm31, 1 << 31);
 
-// enums
+/// Enables guest access to every counter.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which counters the guest can
+/// read.
+#[inline]
+pub unsafe fn enable_all() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(ALL) };
+}
+
+/// Disables guest access to every counter.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which counters the guest can
+/// read.
+#[inline]
+pub unsafe fn disable_all() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(ALL) };
+}
+
+/// Enables guest access to `hpmcounter<n>`.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which counters the guest can
+/// read. Panics if `n` is not in `3..=31`.
+#[inline]
+pub unsafe fn set_hpm(n: usize) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(hpm_mask(n)) };
+}
+
+/// Disables guest access to `hpmcounter<n>`.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which counters the guest can
+/// read. Panics if `n` is not in `3..=31`.
+#[inline]
+pub unsafe fn clear_hpm(n: usize) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(hpm_mask(n)) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hpm_index() {
+        let mut hcounteren = Hcounteren::from_bits(0);
+        hcounteren.set_hpm(3, true);
+        hcounteren.set_hpm(31, true);
+        assert!(hcounteren.hpm3());
+        assert!(hcounteren.hpm(31));
+        assert!(!hcounteren.hpm(4));
+        hcounteren.set_hpm(3, false);
+        assert_eq!(hcounteren.bits(), 1 << 31);
+    }
+
+    #[test]
+    fn test_builder() {
+        assert_eq!(HcounterenBuilder::new().build().bits(), 0);
+        assert_eq!(HcounterenBuilder::new().basic().build().bits(), BASIC);
+        let all = HcounterenBuilder::new().basic().hpm_range(3..=31).build();
+        assert_eq!(all.bits(), ALL);
+        let some = HcounterenBuilder::new().hpm_range(4..=5).build();
+        assert!(some.hpm4() && some.hpm5() && !some.cy());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hpm_rejects_fixed_counters() {
+        Hcounteren::from_bits(0).hpm(2);
+    }
+}
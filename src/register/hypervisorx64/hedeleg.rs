@@ -21,164 +21,166 @@
 //! This register enables efficient virtualization by allowing guests to handle
 //! common exceptions (like page faults) directly without hypervisor intervention.
 //! Exception codes correspond to standard RISC-V exception cause values.
+//!
+//! Only the exceptions in
+//! [`Hedeleg::DELEGATABLE_MASK`](crate::register::hedeleg::Hedeleg::DELEGATABLE_MASK)
+//! can be delegated: the hypervisor-only causes (environment call from VS-mode,
+//! guest-page faults and virtual instruction) always trap to HS-mode, and their
+//! bits read as zero.
+//! [`Hedeleg::write_checked`](crate::register::hedeleg::Hedeleg::write_checked)
+//! reports requested delegations the hart did not honor.
+//!
+//! Besides the `exN` accessors named after cause numbers, exceptions can be
+//! delegated by [`Exception`](crate::register::vscause::Exception) or through
+//! accessors named after the exception.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::vscause::Exception;
+use riscv::set_clear_csr;
 
-/// Hypervisor Trap Delegation Registers.
-#[derive(Copy, Clone, Debug)]
-pub struct Hedeleg {
-    bits: usize,
+crate::register! {
+    /// Hypervisor Trap Delegation Registers.
+    Hedeleg: 0x602 {
+        /// instruction address misaligned exception delegation
+        ex0, set_ex0, EX0: [0];
+        /// instruction access fault exception delegation
+        ex1, set_ex1, EX1: [1];
+        /// illegal instruction exception delegation
+        ex2, set_ex2, EX2: [2];
+        /// breakpoint exception delegation
+        ex3, set_ex3, EX3: [3];
+        /// load address misaligned exception delegation
+        ex4, set_ex4, EX4: [4];
+        /// load access fault exception delegation
+        ex5, set_ex5, EX5: [5];
+        /// store/AMO address misaligned exception delegation
+        ex6, set_ex6, EX6: [6];
+        /// store/AMO access fault exception delegation
+        ex7, set_ex7, EX7: [7];
+        /// environment call exception delegation
+        ex8, set_ex8, EX8: [8];
+        /// instruction page fault exception delegation
+        ex12, set_ex12, EX12: [12];
+        /// load page fault exception delegation
+        ex13, set_ex13, EX13: [13];
+        /// store/AMO page fault exception delegation
+        ex15, set_ex15, EX15: [15];
+    }
 }
 
 impl Hedeleg {
-    /// Returns the raw bits of the register.
+    /// Bits of the exceptions that can be delegated to VS-mode.
+    pub const DELEGATABLE_MASK: usize = Self::EX0
+        | Self::EX1
+        | Self::EX2
+        | Self::EX3
+        | Self::EX4
+        | Self::EX5
+        | Self::EX6
+        | Self::EX7
+        | Self::EX8
+        | Self::EX12
+        | Self::EX13
+        | Self::EX15;
+    /// Delegations of [`Self::standard_guest`].
+    pub const STANDARD_GUEST: usize = Self::EX0
+        | Self::EX1
+        | Self::EX3
+        | Self::EX4
+        | Self::EX5
+        | Self::EX6
+        | Self::EX7
+        | Self::EX8
+        | Self::EX12
+        | Self::EX13
+        | Self::EX15;
+
+    /// Returns the delegations a guest OS normally expects.
+    ///
+    /// Misaligned accesses, access faults, breakpoints, environment calls
+    /// from VU-mode and page faults are handled by the guest itself. Illegal
+    /// instructions stay with the hypervisor, which may need to emulate them.
     #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
+    pub fn standard_guest() -> Self {
+        Hedeleg::from_bits(Self::STANDARD_GUEST)
     }
-    /// Creates a register value from raw bits.
+    /// Returns the requested delegations outside [`Self::DELEGATABLE_MASK`].
     #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hedeleg { bits: x }
+    pub fn undelegatable(&self) -> Hedeleg {
+        Hedeleg::from_bits(self.bits & !Self::DELEGATABLE_MASK)
     }
-    /// Writes the register value to the CSR.
+    /// Writes the value and reads it back.
+    ///
+    /// Fails with the requested delegations that did not take effect, either
+    /// because they can never be delegated or because the hart does not
+    /// implement them. The honored delegations are in effect either way.
     ///
     /// # Safety
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
+    /// Same as [`Hedeleg::write`].
+    pub unsafe fn write_checked(&self) -> Result<(), Hedeleg> {
         // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the instruction address misaligned exception delegation.
-    #[inline]
-    pub fn ex0(&self) -> bool {
-        self.bits.get_bit(0)
-    }
-    /// Sets the instruction address misaligned exception delegation.
-    #[inline]
-    pub fn set_ex0(&mut self, val: bool) {
-        self.bits.set_bit(0, val);
-    }
-    /// Returns the instruction access fault exception delegation.
-    #[inline]
-    pub fn ex1(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-    /// Sets the instruction access fault exception delegation.
-    #[inline]
-    pub fn set_ex1(&mut self, val: bool) {
-        self.bits.set_bit(1, val);
-    }
-    /// Returns the illegal instruction exception delegation.
-    #[inline]
-    pub fn ex2(&self) -> bool {
-        self.bits.get_bit(2)
-    }
-    /// Sets the illegal instruction exception delegation.
-    #[inline]
-    pub fn set_ex2(&mut self, val: bool) {
-        self.bits.set_bit(2, val);
-    }
-    /// Returns the breakpoint exception delegation.
-    #[inline]
-    pub fn ex3(&self) -> bool {
-        self.bits.get_bit(3)
-    }
-    /// Sets the breakpoint exception delegation.
-    #[inline]
-    pub fn set_ex3(&mut self, val: bool) {
-        self.bits.set_bit(3, val);
-    }
-    /// Returns the load address misaligned exception delegation.
-    #[inline]
-    pub fn ex4(&self) -> bool {
-        self.bits.get_bit(4)
-    }
-    /// Sets the load address misaligned exception delegation.
-    #[inline]
-    pub fn set_ex4(&mut self, val: bool) {
-        self.bits.set_bit(4, val);
-    }
-    /// Returns the load access fault exception delegation.
-    #[inline]
-    pub fn ex5(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-    /// Sets the load access fault exception delegation.
-    #[inline]
-    pub fn set_ex5(&mut self, val: bool) {
-        self.bits.set_bit(5, val);
-    }
-    /// Returns the store/AMO address misaligned exception delegation.
-    #[inline]
-    pub fn ex6(&self) -> bool {
-        self.bits.get_bit(6)
-    }
-    /// Sets the store/AMO address misaligned exception delegation.
-    #[inline]
-    pub fn set_ex6(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
-    }
-    /// Returns the store/AMO access fault exception delegation.
-    #[inline]
-    pub fn ex7(&self) -> bool {
-        self.bits.get_bit(7)
-    }
-    /// Sets the store/AMO access fault exception delegation.
-    #[inline]
-    pub fn set_ex7(&mut self, val: bool) {
-        self.bits.set_bit(7, val);
-    }
-    /// Returns the environment call exception delegation.
-    #[inline]
-    pub fn ex8(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-    /// Sets the environment call exception delegation.
-    #[inline]
-    pub fn set_ex8(&mut self, val: bool) {
-        self.bits.set_bit(8, val);
+        unsafe { self.write() };
+        match self.bits & !read().bits {
+            0 => Ok(()),
+            bits => Err(Hedeleg::from_bits(bits)),
+        }
     }
-    /// Returns the instruction page fault exception delegation.
-    #[inline]
-    pub fn ex12(&self) -> bool {
-        self.bits.get_bit(12)
-    }
-    /// Sets the instruction page fault exception delegation.
-    #[inline]
-    pub fn set_ex12(&mut self, val: bool) {
-        self.bits.set_bit(12, val);
-    }
-    /// Returns the load page fault exception delegation.
-    #[inline]
-    pub fn ex13(&self) -> bool {
-        self.bits.get_bit(13)
-    }
-    /// Sets the load page fault exception delegation.
+}
+
+impl Hedeleg {
+    /// Delegates exception `e` to VS-mode.
+    ///
+    /// Exceptions outside [`Self::DELEGATABLE_MASK`] are recorded but have no
+    /// effect once written.
     #[inline]
-    pub fn set_ex13(&mut self, val: bool) {
-        self.bits.set_bit(13, val);
+    pub fn delegate(&mut self, e: Exception) {
+        self.bits |= 1 << e.number();
     }
-    /// Returns the store/AMO page fault exception delegation.
+    /// Stops delegating exception `e` to VS-mode.
     #[inline]
-    pub fn ex15(&self) -> bool {
-        self.bits.get_bit(15)
+    pub fn undelegate(&mut self, e: Exception) {
+        self.bits &= !(1 << e.number());
     }
-    /// Sets the store/AMO page fault exception delegation.
+    /// Returns whether exception `e` is delegated to VS-mode.
     #[inline]
-    pub fn set_ex15(&mut self, val: bool) {
-        self.bits.set_bit(15, val);
+    pub fn is_delegated(&self, e: Exception) -> bool {
+        self.bits & (1 << e.number()) != 0
     }
 }
 
-read_csr_as!(Hedeleg, 0x602);
-write_csr!(0x602);
-set!(0x602);
-clear!(0x602);
+macro_rules! named_accessors {
+    ($($get:ident, $set:ident => $ex:ident, $set_ex:ident, $desc:literal;)*) => {
+        impl Hedeleg {
+            $(
+                #[doc = concat!("Returns whether ", $desc, " exceptions are delegated.")]
+                #[inline]
+                pub fn $get(&self) -> bool {
+                    self.$ex()
+                }
+                #[doc = concat!("Sets whether ", $desc, " exceptions are delegated.")]
+                #[inline]
+                pub fn $set(&mut self, delegate: bool) {
+                    self.$set_ex(delegate)
+                }
+            )*
+        }
+    };
+}
+
+named_accessors! {
+    instruction_misaligned, set_instruction_misaligned => ex0, set_ex0, "instruction address misaligned";
+    instruction_fault, set_instruction_fault => ex1, set_ex1, "instruction access fault";
+    illegal_instruction, set_illegal_instruction => ex2, set_ex2, "illegal instruction";
+    breakpoint, set_breakpoint => ex3, set_ex3, "breakpoint";
+    load_misaligned, set_load_misaligned => ex4, set_ex4, "load address misaligned";
+    load_fault, set_load_fault => ex5, set_ex5, "load access fault";
+    store_misaligned, set_store_misaligned => ex6, set_ex6, "store/AMO address misaligned";
+    store_fault, set_store_fault => ex7, set_ex7, "store/AMO access fault";
+    user_env_call, set_user_env_call => ex8, set_ex8, "environment call from VU-mode";
+    instruction_page_fault, set_instruction_page_fault => ex12, set_ex12, "instruction page fault";
+    load_page_fault, set_load_page_fault => ex13, set_ex13, "load page fault";
+    store_page_fault, set_store_page_fault => ex15, set_ex15, "store/AMO page fault";
+}
 
 // bit ops
 set_clear_csr!(
@@ -219,3 +221,54 @@ set_clear_csr!(
     , set_ex15, clear_ex15, 1 << 15);
 
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delegate_by_exception() {
+        let mut hedeleg = Hedeleg::from_bits(0);
+        hedeleg.delegate(Exception::LoadPageFault);
+        hedeleg.delegate(Exception::Breakpoint);
+        assert_eq!(hedeleg.bits(), (1 << 13) | (1 << 3));
+        assert!(hedeleg.is_delegated(Exception::LoadPageFault));
+        assert!(hedeleg.load_page_fault() && hedeleg.ex13());
+        assert!(!hedeleg.is_delegated(Exception::StorePageFault));
+
+        hedeleg.undelegate(Exception::Breakpoint);
+        assert!(!hedeleg.breakpoint());
+        assert_eq!(hedeleg.bits(), 1 << 13);
+    }
+
+    #[test]
+    fn test_named_accessors() {
+        let mut hedeleg = Hedeleg::from_bits(0);
+        hedeleg.set_instruction_page_fault(true);
+        hedeleg.set_user_env_call(true);
+        assert!(hedeleg.ex12() && hedeleg.ex8());
+        hedeleg.set_ex12(false);
+        assert!(!hedeleg.instruction_page_fault());
+    }
+
+    #[test]
+    fn test_standard_guest() {
+        let hedeleg = Hedeleg::standard_guest();
+        assert_eq!(hedeleg.undelegatable().bits(), 0);
+        assert!(hedeleg.is_delegated(Exception::UserEnvCall));
+        assert!(hedeleg.is_delegated(Exception::StorePageFault));
+        assert!(hedeleg.is_delegated(Exception::InstructionMisaligned));
+        assert!(!hedeleg.is_delegated(Exception::IllegalInstruction));
+        assert!(!hedeleg.is_delegated(Exception::SupervisorEnvCall));
+    }
+
+    #[test]
+    fn test_delegatable_mask() {
+        assert_eq!(Hedeleg::DELEGATABLE_MASK, 0xb1ff);
+
+        let request = Hedeleg::from_bits((1 << 3) | (1 << 10) | (1 << 21));
+        assert_eq!(request.undelegatable().bits(), (1 << 10) | (1 << 21));
+        let request = Hedeleg::from_bits(Hedeleg::DELEGATABLE_MASK);
+        assert_eq!(request.undelegatable().bits(), 0);
+    }
+}
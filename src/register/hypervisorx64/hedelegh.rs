@@ -0,0 +1,89 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Exception Delegation Register (high half).
+//!
+//! On RV32, exceptions with codes 32–63 are delegated through `hedelegh`: bit
+//! `n` of this register is bit `n + 32` of [`hedeleg`](super::hedeleg). No
+//! standard exception uses these codes yet, and codes 48–63 are designated for
+//! custom use, so the bits are accessed by exception code.
+
+use bit_field::BitField;
+
+crate::register! {
+    /// Hypervisor Exception Delegation Register, high half.
+    Hedelegh: 0x612 {}
+}
+
+/// Lowest exception code delegated through `hedelegh`.
+pub const FIRST_CODE: usize = 32;
+
+/// Returns the `hedelegh` bit of exception `code`.
+///
+/// # Panics
+///
+/// Panics unless `code` is within 32..64.
+#[inline]
+fn code_bit(code: usize) -> usize {
+    assert!(
+        (FIRST_CODE..FIRST_CODE + 32).contains(&code),
+        "exception {code} is not delegated by hedelegh"
+    );
+    code - FIRST_CODE
+}
+
+impl Hedelegh {
+    /// Returns whether exception `code` is delegated to VS-mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `code` is within 32..64.
+    #[inline]
+    pub fn is_delegated(&self, code: usize) -> bool {
+        self.bits.get_bit(code_bit(code))
+    }
+    /// Sets whether exception `code` is delegated to VS-mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `code` is within 32..64.
+    #[inline]
+    pub fn set_delegated(&mut self, code: usize, delegate: bool) {
+        self.bits.set_bit(code_bit(code), delegate);
+    }
+}
+
+/// Delegates exception `code` to VS-mode in the `hedelegh` CSR.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which mode handles guest
+/// exceptions. Panics unless `code` is within 32..64.
+#[inline]
+pub unsafe fn delegate(code: usize) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(1 << code_bit(code)) };
+}
+
+/// Stops delegating exception `code` to VS-mode in the `hedelegh` CSR.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which mode handles guest
+/// exceptions. Panics unless `code` is within 32..64.
+#[inline]
+pub unsafe fn undelegate(code: usize) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(1 << code_bit(code)) };
+}
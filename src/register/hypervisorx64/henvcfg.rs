@@ -0,0 +1,105 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Environment Configuration Register.
+//!
+//! The `henvcfg` register controls extension features visible to VS-mode and
+//! VU-mode, such as cache-block management instructions (Zicbom/Zicboz),
+//! page-based memory types (Svpbmt), hardware A/D-bit updates (Svadu) and the
+//! `vstimecmp` register (Sstc).
+//!
+//! Several of these bits change how guest memory accesses or guest address
+//! translation behave.
+//! [`Henvcfg::write_ordered`](crate::register::henvcfg::Henvcfg::write_ordered)
+//! performs the fences the privileged specification requires around such
+//! changes.
+
+use crate::asm::hfence_vvma_all;
+use crate::register::barrier::fence;
+
+crate::register! {
+    /// Hypervisor Environment Configuration Register.
+    Henvcfg: 0x60A {
+        /// fence of I/O implies memory
+        fiom, set_fiom, FIOM: [0];
+        /// landing pad enable
+        lpe, set_lpe, LPE: [2];
+        /// shadow stack enable
+        sse, set_sse, SSE: [3];
+        /// cache block invalidate instruction enable
+        cbie, set_cbie, CBIE: [4..6], Warl;
+        /// cache block clean and flush instruction enable
+        cbcfe, set_cbcfe, CBCFE: [6];
+        /// cache block zero instruction enable
+        cbze, set_cbze, CBZE: [7];
+        /// pointer masking mode
+        pmm, set_pmm, PMM: [32..34], Warl;
+        /// hardware A/D-bit update enable
+        adue, set_adue, ADUE: [61];
+        /// page-based memory types enable
+        pbmte, set_pbmte, PBMTE: [62];
+        /// VS-mode timer compare enable
+        stce, set_stce, STCE: [63];
+    }
+}
+
+impl Henvcfg {
+    /// Writes this value to `henvcfg` with the required ordering.
+    ///
+    /// A `fence` before the write orders preceding memory accesses before a
+    /// change of `FIOM` or the cache-block controls; an `hfence.vvma` after it
+    /// makes changes of `PBMTE` and `ADUE` visible to VS-stage translation.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Henvcfg::write`]; `hgatp` must hold the guest's VMID.
+    #[inline]
+    pub unsafe fn write_ordered(&self) {
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe {
+            fence();
+            self.write();
+            hfence_vvma_all();
+        }
+    }
+}
+
+/// Reads `henvcfg` after all preceding memory accesses have been performed.
+#[inline]
+pub fn read_ordered() -> Henvcfg {
+    // SAFETY: A fence has no side effects besides ordering
+    unsafe { fence() };
+    read()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_henvcfg_fields() {
+        let mut henvcfg = Henvcfg::from_bits(0);
+        henvcfg.set_stce(true);
+        henvcfg.set_pbmte(true);
+        henvcfg.set_cbie(0b11);
+        henvcfg.set_cbze(true);
+        assert_eq!(
+            henvcfg.bits(),
+            (1 << 63) | (1 << 62) | (0b11 << 4) | (1 << 7)
+        );
+        assert!(henvcfg.stce());
+        assert!(!henvcfg.adue());
+        assert_eq!(henvcfg.cbie(), 0b11);
+    }
+}
@@ -0,0 +1,32 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Environment Configuration Register (high half).
+//!
+//! On RV32, bits 63:32 of `henvcfg` are accessed through `henvcfgh`. Bit `n`
+//! of this register is bit `n + 32` of `henvcfg`.
+
+crate::register! {
+    /// Hypervisor Environment Configuration Register, high half.
+    Henvcfgh: 0x61A {
+        /// pointer masking mode
+        pmm, set_pmm, PMM: [0..2], Warl;
+        /// hardware A/D-bit update enable
+        adue, set_adue, ADUE: [29];
+        /// page-based memory types enable
+        pbmte, set_pbmte, PBMTE: [30];
+        /// VS-mode timer compare enable
+        stce, set_stce, STCE: [31];
+    }
+}
@@ -16,7 +16,7 @@
 //!
 //! The `hgatp` register controls guest address translation for two-stage memory management
 //! in RISC-V hypervisor implementations. This register configures:
-//! - Guest physical address translation mode (Bare, Sv39x4, Sv48x4)
+//! - Guest physical address translation mode (Bare, Sv39x4, Sv48x4, Sv57x4)
 //! - Virtual Machine ID (VMID) for TLB management
 //! - Root page table physical page number (PPN)
 //!
@@ -24,73 +24,205 @@
 //! 1. Guest virtual → Guest physical (controlled by VS-mode satp)  
 //! 2. Guest physical → Host physical (controlled by this hgatp register)
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, write_csr};
+use crate::asm::hfence_gvma_vmid;
+use crate::register::barrier::fence;
 
-/// Hypervisor Guest Address Translation and Protection Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Hgatp {
-    bits: usize,
+/// Largest VMIDLEN allowed by the architecture on RV64.
+pub const VMIDLEN_MAX: usize = 14;
+/// Required alignment of the G-stage root page table (16 KiB).
+pub const ROOT_ALIGN: usize = 16 * 1024;
+
+crate::register! {
+    /// Hypervisor Guest Address Translation and Protection Register.
+    Hgatp: 0x680 {
+        /// guest address translation mode
+        mode, set_mode, MODE: [60..64] as HgatpValues, Warl;
+        /// Virtual machine ID
+        vmid, set_vmid, VMID: [44..58], Warl;
+        /// Physical Page Number for root page table
+        ppn, set_ppn, PPN: [0..44];
+    }
 }
 
 impl Hgatp {
-    /// Returns the raw bits of the register.
+    /// Returns the value disabling G-stage translation.
+    ///
+    /// In Bare mode the VMID and PPN fields must be zero, otherwise the
+    /// write to `hgatp` may be ignored.
     #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
+    pub fn bare() -> Self {
+        Hgatp::from_bits(0)
     }
-    /// Creates a register value from raw bits.
+    /// Creates a value translating through the root page table at physical
+    /// address `root_paddr`.
+    ///
+    /// Fails instead of truncating if the root table is not 16 KiB aligned,
+    /// its page number does not fit in the PPN field, or `vmid` does not fit
+    /// in [`VMIDLEN_MAX`] bits. The hart may implement fewer VMID bits; use
+    /// [`Hgatp::with_vmidlen`] to check against those.
+    ///
+    /// With [`HgatpValues::Bare`], `vmid` and `root_paddr` must be zero, as
+    /// the write may otherwise be ignored; see [`Hgatp::bare`].
     #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hgatp { bits: x }
+    pub fn new(mode: HgatpValues, vmid: usize, root_paddr: usize) -> Result<Self, HgatpError> {
+        Self::with_vmidlen(mode, vmid, root_paddr, VMIDLEN_MAX)
+    }
+    /// Like [`Hgatp::new`], but checks `vmid` against the `vmidlen` bits the
+    /// hart implements, as returned by [`vmid_len`] or
+    /// [`HExtInfo::vmidlen`](crate::hext::HExtInfo::vmidlen).
+    ///
+    /// The hardware would silently drop the upper VMID bits, making two
+    /// guests share a VMID.
+    pub fn with_vmidlen(
+        mode: HgatpValues,
+        vmid: usize,
+        root_paddr: usize,
+        vmidlen: usize,
+    ) -> Result<Self, HgatpError> {
+        if matches!(mode, HgatpValues::Bare) && (vmid != 0 || root_paddr != 0) {
+            return Err(HgatpError::NonZeroBare);
+        }
+        if root_paddr & (ROOT_ALIGN - 1) != 0 {
+            return Err(HgatpError::MisalignedRoot);
+        }
+        let ppn = root_paddr >> 12;
+        if ppn > Self::PPN {
+            return Err(HgatpError::PpnTooLarge);
+        }
+        if vmid >> vmidlen.min(VMIDLEN_MAX) != 0 {
+            return Err(HgatpError::VmidTooLarge);
+        }
+        let mut hgatp = Hgatp::from_bits(0);
+        hgatp.set_mode(mode);
+        hgatp.set_vmid(vmid);
+        hgatp.set_ppn(ppn);
+        Ok(hgatp)
     }
-    /// Writes the register value to the CSR.
+    /// Writes this value to `hgatp` and fences its VMID.
+    ///
+    /// The `hfence.gvma` orders preceding stores to the G-stage page tables
+    /// before the implicit reads of the new translation, and discards stale
+    /// translations cached for the VMID.
     ///
     /// # Safety
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
+    /// Same as [`Hgatp::write`].
     #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
+    pub unsafe fn write_ordered(&self) {
+        // SAFETY: Caller ensures the translation is valid
+        unsafe {
+            self.write();
+            hfence_gvma_vmid(self.vmid());
+        }
     }
-    /// Returns the guest address translation mode.
+    /// Writes this value to `hgatp` and returns a guard that keeps the G-stage
+    /// TLB coherent for its VMID.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Hgatp::write`]; the root page table must stay valid while the
+    /// guard is alive.
     #[inline]
-    pub fn mode(&self) -> HgatpValues {
-        HgatpValues::from(self.bits.get_bits(60..64))
+    pub unsafe fn install(self) -> HgatpGuard {
+        // SAFETY: Caller ensures the translation is valid
+        unsafe { self.write_ordered() };
+        HgatpGuard { hgatp: self }
     }
-    /// Sets the guest address translation mode.
+}
+
+/// Guard for an installed `hgatp` value.
+///
+/// Issues `hfence.gvma` for the installed VMID whenever the translation is
+/// replaced and when the guard is dropped, so that early returns cannot leave
+/// stale G-stage translations behind.
+#[derive(Debug)]
+#[must_use = "dropping the guard immediately fences the installed VMID"]
+pub struct HgatpGuard {
+    hgatp: Hgatp,
+}
+
+impl HgatpGuard {
+    /// Returns the installed `hgatp` value.
     #[inline]
-    pub fn set_mode(&mut self, val: HgatpValues) {
-        self.bits.set_bits(60..64, val as usize);
+    pub fn hgatp(&self) -> Hgatp {
+        self.hgatp
     }
-    /// Returns the Virtual machine ID.
+    /// Returns the installed VMID.
     #[inline]
     pub fn vmid(&self) -> usize {
-        self.bits.get_bits(44..58)
-    }
-    /// Sets the Virtual machine ID.
-    #[inline]
-    pub fn set_vmid(&mut self, val: usize) {
-        self.bits.set_bits(44..58, val);
+        self.hgatp.vmid()
     }
-    /// Returns the Physical Page Number for root page table.
-    #[inline]
-    pub fn ppn(&self) -> usize {
-        self.bits.get_bits(0..44)
+    /// Installs `hgatp` in place of the current value.
+    ///
+    /// Fences the previous VMID, and the new one if it differs.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Hgatp::install`].
+    pub unsafe fn replace(&mut self, hgatp: Hgatp) {
+        let old = self.vmid();
+        // SAFETY: Caller ensures the translation is valid
+        unsafe {
+            hgatp.write();
+            hfence_gvma_vmid(old);
+            if hgatp.vmid() != old {
+                hfence_gvma_vmid(hgatp.vmid());
+            }
+        }
+        self.hgatp = hgatp;
     }
-    /// Sets the Physical Page Number for root page table.
-    #[inline]
-    pub fn set_ppn(&mut self, val: usize) {
-        self.bits.set_bits(0..44, val);
+}
+
+impl Drop for HgatpGuard {
+    fn drop(&mut self) {
+        // SAFETY: Fencing only discards cached translations
+        unsafe { hfence_gvma_vmid(self.vmid()) };
     }
 }
 
-read_csr_as!(Hgatp, 0x680);
-write_csr!(0x680);
-set!(0x680);
-clear!(0x680);
+/// Reads `hgatp` after all preceding memory accesses have been performed.
+#[inline]
+pub fn read_ordered() -> Hgatp {
+    // SAFETY: A fence has no side effects besides ordering
+    unsafe { fence() };
+    read()
+}
+
+/// Returns the number of VMID bits implemented by this hart (VMIDLEN).
+///
+/// Writes all-ones to the VMID field and counts the bits that read back,
+/// then restores the original `hgatp`. A VMID allocator can size itself to
+/// `1 << vmid_len()` VMIDs; zero means VMIDs are not implemented.
+///
+/// # Safety
+///
+/// No guest may be running on this hart, since its translation briefly
+/// uses a different VMID.
+pub unsafe fn vmid_len() -> usize {
+    let saved = read();
+    let mut probe = saved;
+    probe.set_vmid((1 << VMIDLEN_MAX) - 1);
+    // SAFETY: Caller ensures no guest is using hgatp
+    unsafe { probe.write() };
+    let vmid = read().vmid();
+    // SAFETY: Restores the original value
+    unsafe { saved.write() };
+    vmid.count_ones() as usize
+}
+
+/// Errors building an [`Hgatp`] value with [`Hgatp::new`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HgatpError {
+    /// The root page table is not 16 KiB aligned.
+    MisalignedRoot,
+    /// The root page table lies beyond the addresses the PPN field can hold.
+    PpnTooLarge,
+    /// The VMID is wider than VMIDLEN.
+    VmidTooLarge,
+    /// A VMID or root page table was given for Bare mode.
+    NonZeroBare,
+}
+
 // bit ops
 
 /// Hypervisor Guest Address Translation and Protection Register values.
@@ -103,6 +235,8 @@ pub enum HgatpValues {
     Sv39x4 = 8,
     /// Supervisor Virtual Address Translation (SV48)
     Sv48x4 = 9,
+    /// Supervisor Virtual Address Translation (SV57)
+    Sv57x4 = 10,
 }
 
 impl HgatpValues {
@@ -111,6 +245,7 @@ impl HgatpValues {
             0 => Self::Bare,
             8 => Self::Sv39x4,
             9 => Self::Sv48x4,
+            10 => Self::Sv57x4,
             _ => unreachable!(),
         }
     }
@@ -144,6 +279,11 @@ mod tests {
         hgatp.set_mode(HgatpValues::Sv48x4);
         assert!(matches!(hgatp.mode(), HgatpValues::Sv48x4));
         assert_eq!(hgatp.bits() & (0xF << 60), 9_usize << 60);
+
+        // Test setting mode to Sv57x4
+        hgatp.set_mode(HgatpValues::Sv57x4);
+        assert!(matches!(hgatp.mode(), HgatpValues::Sv57x4));
+        assert_eq!(hgatp.bits() & (0xF << 60), 10_usize << 60);
     }
 
     #[test]
@@ -183,11 +323,68 @@ mod tests {
         assert_eq!(hgatp.ppn(), max_ppn);
     }
 
+    #[test]
+    fn test_hgatp_new() {
+        let hgatp = Hgatp::new(HgatpValues::Sv39x4, 5, 0x8020_4000).unwrap();
+        assert!(matches!(hgatp.mode(), HgatpValues::Sv39x4));
+        assert_eq!(hgatp.vmid(), 5);
+        assert_eq!(hgatp.ppn(), 0x80204);
+
+        let max_root = (Hgatp::PPN << 12) & !(ROOT_ALIGN - 1);
+        assert!(Hgatp::new(HgatpValues::Sv48x4, 0x3FFF, max_root).is_ok());
+
+        let bare = Hgatp::new(HgatpValues::Bare, 0, 0).unwrap();
+        assert_eq!(bare.bits(), Hgatp::bare().bits());
+        assert_eq!(bare.bits(), 0);
+    }
+
+    #[test]
+    fn test_hgatp_new_errors() {
+        assert_eq!(
+            Hgatp::new(HgatpValues::Sv39x4, 0, 0x8020_1000).unwrap_err(),
+            HgatpError::MisalignedRoot
+        );
+        assert_eq!(
+            Hgatp::new(HgatpValues::Sv39x4, 0, 1 << 56).unwrap_err(),
+            HgatpError::PpnTooLarge
+        );
+        assert_eq!(
+            Hgatp::new(HgatpValues::Sv39x4, 0x4000, 0x8020_0000).unwrap_err(),
+            HgatpError::VmidTooLarge
+        );
+        assert_eq!(
+            Hgatp::new(HgatpValues::Bare, 1, 0).unwrap_err(),
+            HgatpError::NonZeroBare
+        );
+        assert_eq!(
+            Hgatp::new(HgatpValues::Bare, 0, 0x8020_0000).unwrap_err(),
+            HgatpError::NonZeroBare
+        );
+    }
+
+    #[test]
+    fn test_hgatp_with_vmidlen() {
+        let root = 0x8020_0000;
+        let hgatp = Hgatp::with_vmidlen(HgatpValues::Sv39x4, 0x7F, root, 7).unwrap();
+        assert_eq!(hgatp.vmid(), 0x7F);
+        assert_eq!(
+            Hgatp::with_vmidlen(HgatpValues::Sv39x4, 0x80, root, 7).unwrap_err(),
+            HgatpError::VmidTooLarge
+        );
+        // Without VMIDs, only VMID 0 is usable
+        assert!(Hgatp::with_vmidlen(HgatpValues::Sv39x4, 0, root, 0).is_ok());
+        assert_eq!(
+            Hgatp::with_vmidlen(HgatpValues::Sv39x4, 1, root, 0).unwrap_err(),
+            HgatpError::VmidTooLarge
+        );
+    }
+
     #[test]
     fn test_hgatp_values_from() {
         assert!(matches!(HgatpValues::from(0), HgatpValues::Bare));
         assert!(matches!(HgatpValues::from(8), HgatpValues::Sv39x4));
         assert!(matches!(HgatpValues::from(9), HgatpValues::Sv48x4));
+        assert!(matches!(HgatpValues::from(10), HgatpValues::Sv57x4));
     }
 
     #[test]
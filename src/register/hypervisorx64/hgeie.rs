@@ -13,7 +13,140 @@
 // limitations under the License.
 
 //! Hypervisor Guest External Interrupt Enable Register.
-use riscv::{read_csr_as_usize, write_csr_as_usize};
+//!
+//! Bit `n` enables guest external interrupts from guest interrupt file `n`
+//! (1..=GEILEN). Bit 0 is read-only zero.
+//! [`Hgeie::enabled`](crate::register::hgeie::Hgeie::enabled) iterates over the
+//! enabled guest external interrupt numbers.
 
-read_csr_as_usize!(0x607);
-write_csr_as_usize!(0x607);
+crate::register! {
+    /// Hypervisor Guest External Interrupt Enable Register.
+    Hgeie: 0x607 {}
+}
+
+impl Hgeie {
+    /// Returns whether guest external interrupt `gei` is enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gei` is 0 or not smaller than XLEN.
+    #[inline]
+    pub fn is_enabled(&self, gei: usize) -> bool {
+        self.bits & gei_mask(gei) != 0
+    }
+    /// Enables guest external interrupt `gei`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gei` is 0 or not smaller than XLEN.
+    #[inline]
+    pub fn enable(&mut self, gei: usize) {
+        self.bits |= gei_mask(gei);
+    }
+    /// Disables guest external interrupt `gei`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gei` is 0 or not smaller than XLEN.
+    #[inline]
+    pub fn disable(&mut self, gei: usize) {
+        self.bits &= !gei_mask(gei);
+    }
+    /// Returns an iterator over the enabled guest external interrupt numbers,
+    /// in increasing order.
+    #[inline]
+    pub fn enabled(&self) -> GuestInterrupts {
+        GuestInterrupts::new(self.bits)
+    }
+}
+
+/// Returns the bit of guest external interrupt `gei`.
+fn gei_mask(gei: usize) -> usize {
+    assert!(
+        (1..usize::BITS as usize).contains(&gei),
+        "guest external interrupt {gei} out of range"
+    );
+    1 << gei
+}
+
+/// Iterator over the guest external interrupt numbers set in `hgeie` or
+/// `hgeip`.
+#[derive(Clone, Debug)]
+pub struct GuestInterrupts {
+    bits: usize,
+}
+
+impl GuestInterrupts {
+    /// Iterates over the bits of `bits`, ignoring the reserved bit 0.
+    #[inline]
+    pub(crate) fn new(bits: usize) -> Self {
+        GuestInterrupts { bits: bits & !1 }
+    }
+}
+
+impl Iterator for GuestInterrupts {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.bits == 0 {
+            return None;
+        }
+        let gei = self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1;
+        Some(gei)
+    }
+}
+
+/// Atomically clears the bits in `mask` and returns the previous value.
+///
+/// # Safety
+///
+/// This function is unsafe because it disables guest external interrupts.
+#[inline]
+#[cfg_attr(
+    not(any(target_arch = "riscv32", target_arch = "riscv64")),
+    allow(unused_variables)
+)]
+pub unsafe fn fetch_clear(mask: usize) -> Hgeie {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let bits: usize;
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe { core::arch::asm!("csrrc {0}, 0x607, {1}", out(reg) bits, in(reg) mask) };
+        Hgeie { bits }
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enable_disable() {
+        let mut hgeie = Hgeie::from_bits(0);
+        hgeie.enable(1);
+        hgeie.enable(5);
+        assert_eq!(hgeie.bits(), 0b10_0010);
+        assert!(hgeie.is_enabled(5));
+        assert!(!hgeie.is_enabled(4));
+        hgeie.disable(1);
+        assert_eq!(hgeie.bits(), 0b10_0000);
+    }
+
+    #[test]
+    fn test_enabled_iterator() {
+        let hgeie = Hgeie::from_bits(0b1010_0111);
+        assert!(hgeie.enabled().eq([1, 2, 5, 7]));
+        assert_eq!(Hgeie::from_bits(0).enabled().next(), None);
+        assert_eq!(Hgeie::from_bits(1 << 63).enabled().next(), Some(63));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_reserved_bit_is_rejected() {
+        Hgeie::from_bits(0).enable(0);
+    }
+}
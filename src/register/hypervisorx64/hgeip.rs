@@ -13,7 +13,125 @@
 // limitations under the License.
 
 //! Hypervisor Guest External Interrupt Pending Register.
-use riscv::{read_csr_as_usize, write_csr_as_usize};
+//!
+//! Bit `n` is set while guest interrupt file `n` (1..=GEILEN) has an
+//! interrupt pending. The register is read-only: pending bits are cleared
+//! by servicing the interrupt in the guest interrupt file itself.
+use super::hgeie::{self, GuestInterrupts, Hgeie};
+use core::num::NonZeroUsize;
 
-read_csr_as_usize!(0xE12);
-write_csr_as_usize!(0xE12);
+crate::register! {
+    /// Hypervisor Guest External Interrupt Pending Register.
+    #[derive(PartialEq, Eq)]
+    Hgeip: 0xE12, ReadOnly {
+        /// pending guest external interrupts, bit `n` for file `n`
+        gei, GEI: [1..64];
+    }
+}
+
+impl Hgeip {
+    /// Returns an iterator over the guest interrupt files with a pending
+    /// interrupt, in increasing order.
+    #[inline]
+    pub fn pending(&self) -> GuestInterrupts {
+        GuestInterrupts::new(self.bits)
+    }
+}
+
+/// A guest interrupt file number (1..=GEILEN), as selected by `hstatus.VGEIN`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GuestFile(NonZeroUsize);
+
+impl GuestFile {
+    /// Creates a guest file number, or `None` for 0 (no guest file).
+    #[inline]
+    pub fn new(n: usize) -> Option<Self> {
+        NonZeroUsize::new(n).map(GuestFile)
+    }
+    /// Returns the guest file number.
+    #[inline]
+    pub fn get(&self) -> usize {
+        self.0.get()
+    }
+    /// Returns the bit of this guest file in `hgeie`/`hgeip`.
+    #[inline]
+    pub fn mask(&self) -> usize {
+        1 << self.0.get()
+    }
+}
+
+/// Returns the lowest guest file that is both pending and enabled.
+#[inline]
+pub fn lowest(pending: Hgeip, enabled: Hgeie) -> Option<GuestFile> {
+    GuestInterrupts::new(pending.bits & enabled.bits())
+        .next()
+        .and_then(GuestFile::new)
+}
+
+/// Claims the lowest pending guest external interrupt among `enabled`.
+///
+/// The claimed file is masked in `hgeie` with a single `csrrc`, so a nested
+/// handler on the same hart cannot claim it twice; a file that was masked
+/// concurrently is skipped. The caller must re-enable the file in `hgeie`
+/// once the guest has been notified.
+///
+/// # Safety
+///
+/// This function is unsafe because it modifies `hgeie`.
+pub unsafe fn claim_lowest(enabled: Hgeie) -> Option<GuestFile> {
+    let mut enabled = enabled;
+    loop {
+        let file = lowest(read(), enabled)?;
+        // SAFETY: Caller ensures this is safe to execute
+        let previous = unsafe { hgeie::fetch_clear(file.mask()) };
+        if previous.bits() & file.mask() != 0 {
+            return Some(file);
+        }
+        enabled = Hgeie::from_bits(enabled.bits() & !file.mask());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_file() {
+        assert!(GuestFile::new(0).is_none());
+        let file = GuestFile::new(5).unwrap();
+        assert_eq!(file.get(), 5);
+        assert_eq!(file.mask(), 1 << 5);
+    }
+
+    #[test]
+    fn test_pending() {
+        let hgeip = Hgeip::from_bits(0b1001_0011);
+        assert!(hgeip.pending().eq([1, 4, 7]));
+        assert_eq!(Hgeip::from_bits(0b1).pending().next(), None);
+    }
+
+    #[test]
+    fn test_fields() {
+        let hgeip = Hgeip::from_bits(0b1001_0010);
+        assert_eq!(hgeip.gei(), 0b100_1001);
+        assert_eq!(Hgeip::GEI & 1, 0);
+        assert!(
+            Hgeip::FIELDS
+                .iter()
+                .all(|field| field.access() == crate::register::Access::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn test_lowest() {
+        let enabled = Hgeie::from_bits(0b1111_0110);
+        let pending = Hgeip::from_bits;
+        assert_eq!(lowest(pending(0b1010_0000), enabled), GuestFile::new(5));
+        assert_eq!(lowest(pending(0b0000_0110), enabled), GuestFile::new(1));
+        // Pending but disabled
+        assert_eq!(lowest(pending(0b0000_1000), enabled), None);
+        // Reserved bit 0 is ignored
+        assert_eq!(lowest(pending(0b1), Hgeie::from_bits(0b1)), None);
+        assert_eq!(lowest(pending(0), enabled), None);
+    }
+}
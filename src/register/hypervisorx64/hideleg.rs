@@ -13,84 +13,133 @@
 // limitations under the License.
 
 //! Hypervisor Interrupt Delegation Register.
+//!
+//! Bits 2, 6 and 10 delegate the VS-level software, timer and external
+//! interrupts to VS-mode, where the guest sees them as the corresponding
+//! supervisor interrupts.
+//! [`Hideleg::all_vs_interrupts`](crate::register::hideleg::Hideleg::all_vs_interrupts)
+//! delegates all three, as nearly every hypervisor does. The former
+//! `sip`/`tip`/`eip` names of these bits are deprecated.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::interrupts::hypervisor_interrupt_bits;
+use riscv::set_clear_csr;
 
-/// Hypervisor Interrupt Delegation Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Hideleg {
-    bits: usize,
+crate::register! {
+    /// Hypervisor Interrupt Delegation Register.
+    Hideleg: 0x603 {
+        /// VS-level software interrupt delegation
+        vssip, set_vssip, VSSIP: [2];
+        /// VS-level timer interrupt delegation
+        vstip, set_vstip, VSTIP: [6];
+        /// VS-level external interrupt delegation
+        vseip, set_vseip, VSEIP: [10];
+    }
 }
 
 impl Hideleg {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hideleg { bits: x }
-    }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
-    ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
+    /// Delegations of [`Self::all_vs_interrupts`].
+    pub const ALL_VS_INTERRUPTS: usize = Self::VSSIP | Self::VSTIP | Self::VSEIP;
+    /// Former name of [`Self::VSSIP`].
+    #[deprecated(note = "use `VSSIP`")]
+    pub const SIP: usize = Self::VSSIP;
+    /// Former name of [`Self::VSTIP`].
+    #[deprecated(note = "use `VSTIP`")]
+    pub const TIP: usize = Self::VSTIP;
+    /// Former name of [`Self::VSEIP`].
+    #[deprecated(note = "use `VSEIP`")]
+    pub const EIP: usize = Self::VSEIP;
+
+    /// Returns the value delegating every VS-level interrupt to VS-mode.
     #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
+    pub fn all_vs_interrupts() -> Self {
+        Hideleg::from_bits(Self::ALL_VS_INTERRUPTS)
     }
-    /// Returns the status of the supervisor software interrupt delegation.
+    /// Returns the VS-level software interrupt delegation.
+    #[deprecated(note = "use `vssip`")]
     #[inline]
     pub fn sip(&self) -> bool {
-        self.bits.get_bit(2)
+        self.vssip()
     }
-    /// Sets the status of the supervisor software interrupt delegation.
+    /// Sets the VS-level software interrupt delegation.
+    #[deprecated(note = "use `set_vssip`")]
     #[inline]
-    pub fn set_sip(&mut self, val: bool) {
-        self.bits.set_bit(2, val);
+    pub fn set_sip(&mut self, sip: bool) {
+        self.set_vssip(sip)
     }
-    /// Returns the status of the supervisor timer interrupt delegation.
+    /// Returns the VS-level timer interrupt delegation.
+    #[deprecated(note = "use `vstip`")]
     #[inline]
     pub fn tip(&self) -> bool {
-        self.bits.get_bit(6)
+        self.vstip()
     }
-    /// Sets the status of the supervisor timer interrupt delegation.
+    /// Sets the VS-level timer interrupt delegation.
+    #[deprecated(note = "use `set_vstip`")]
     #[inline]
-    pub fn set_tip(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
+    pub fn set_tip(&mut self, tip: bool) {
+        self.set_vstip(tip)
     }
-    /// Returns the status of the supervisor external interrupt delegation.
+    /// Returns the VS-level external interrupt delegation.
+    #[deprecated(note = "use `vseip`")]
     #[inline]
     pub fn eip(&self) -> bool {
-        self.bits.get_bit(10)
+        self.vseip()
     }
-    /// Sets the status of the supervisor external interrupt delegation.
+    /// Sets the VS-level external interrupt delegation.
+    #[deprecated(note = "use `set_vseip`")]
     #[inline]
-    pub fn set_eip(&mut self, val: bool) {
-        self.bits.set_bit(10, val);
+    pub fn set_eip(&mut self, eip: bool) {
+        self.set_vseip(eip)
     }
 }
 
-read_csr_as!(Hideleg, 0x603);
-write_csr!(0x603);
-set!(0x603);
-clear!(0x603);
+hypervisor_interrupt_bits! {
+    ///
+    /// SGEI cannot be delegated and is ignored.
+    Hideleg: Self::ALL_VS_INTERRUPTS, "delegated"
+}
 
 // bit ops
 set_clear_csr!(
-    /// Supervisor software interrupt delegation.
+    /// VS-level software interrupt delegation.
+    , set_vssip, clear_vssip, 1 << 2);
+set_clear_csr!(
+    /// VS-level timer interrupt delegation.
+    , set_vstip, clear_vstip, 1 << 6);
+set_clear_csr!(
+    /// VS-level external interrupt delegation.
+    , set_vseip, clear_vseip, 1 << 10);
+set_clear_csr!(
+    /// VS-level software interrupt delegation.
+    #[deprecated(note = "use `set_vssip`/`clear_vssip`")]
     , set_sip, clear_sip, 1 << 2);
 set_clear_csr!(
-    /// Supervisor timer interrupt delegation.
+    /// VS-level timer interrupt delegation.
+    #[deprecated(note = "use `set_vstip`/`clear_vstip`")]
     , set_tip, clear_tip, 1 << 6);
 set_clear_csr!(
-    /// Supervisor external interrupt delegation.
+    /// VS-level external interrupt delegation.
+    #[deprecated(note = "use `set_vseip`/`clear_vseip`")]
     , set_eip, clear_eip, 1 << 10);
 
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_vs_interrupts() {
+        let hideleg = Hideleg::all_vs_interrupts();
+        assert_eq!(hideleg.bits(), (1 << 2) | (1 << 6) | (1 << 10));
+        assert!(hideleg.vssip() && hideleg.vstip() && hideleg.vseip());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_names() {
+        let mut hideleg = Hideleg::from_bits(0);
+        hideleg.set_tip(true);
+        assert!(hideleg.vstip() && hideleg.tip());
+        assert!(!hideleg.sip() && !hideleg.eip());
+    }
+}
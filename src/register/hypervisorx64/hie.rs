@@ -22,84 +22,39 @@
 //!
 //! This register works in conjunction with the `hvip` register (interrupt pending)
 //! and guest interrupt delegation to manage virtualized interrupt delivery.
+//!
+//! [`enable_for_guest_run`](crate::register::hie::enable_for_guest_run) sets
+//! every enable bit a running guest needs with a single CSR instruction, and is
+//! meant to be called right before guest entry.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::interrupts::hypervisor_interrupt_bits;
+use riscv::set_clear_csr;
 
-/// Hypervisor Interrupt Enable Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Hie {
-    bits: usize,
+crate::register! {
+    /// Hypervisor Interrupt Enable Register.
+    Hie: 0x604 {
+        /// status of the virtual supervisor software interrupt enable
+        vssie, set_vssie, VSSIE: [2];
+        /// status of the virtual supervisor timer interrupt enable
+        vstie, set_vstie, VSTIE: [6];
+        /// status of the virtual supervisor external interrupt enable
+        vseie, set_vseie, VSEIE: [10];
+        /// status of the supervisor guest external interrupt enable
+        sgeie, set_sgeie, SGEIE: [12];
+    }
 }
 
 impl Hie {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hie { bits: x }
-    }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
-    ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the status of the virtual supervisor software interrupt enable.
-    #[inline]
-    pub fn vssie(&self) -> bool {
-        self.bits.get_bit(2)
-    }
-    /// Sets the status of the virtual supervisor software interrupt enable.
-    #[inline]
-    pub fn set_vssie(&mut self, val: bool) {
-        self.bits.set_bit(2, val);
-    }
-    /// Returns the status of the virtual supervisor timer interrupt enable.
-    #[inline]
-    pub fn vstie(&self) -> bool {
-        self.bits.get_bit(6)
-    }
-    /// Sets the status of the virtual supervisor timer interrupt enable.
-    #[inline]
-    pub fn set_vstie(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
-    }
-    /// Returns the status of the virtual supervisor external interrupt enable.
-    #[inline]
-    pub fn vseie(&self) -> bool {
-        self.bits.get_bit(10)
-    }
-    /// Sets the status of the virtual supervisor external interrupt enable.
-    #[inline]
-    pub fn set_vseie(&mut self, val: bool) {
-        self.bits.set_bit(10, val);
-    }
-    /// Returns the status of the supervisor guest external interrupt enable.
-    #[inline]
-    pub fn sgeie(&self) -> bool {
-        self.bits.get_bit(12)
-    }
-    /// Sets the status of the supervisor guest external interrupt enable.
-    #[inline]
-    pub fn set_sgeie(&mut self, val: bool) {
-        self.bits.set_bit(12, val);
-    }
+    /// Enable bits of the VS-level software, timer and external interrupts.
+    pub const ALL_VS_INTERRUPTS: usize = Self::VSSIE | Self::VSTIE | Self::VSEIE;
+    /// Enable bits set by
+    /// [`enable_for_guest_run`](crate::register::hie::enable_for_guest_run).
+    pub const GUEST_RUN: usize = Self::ALL_VS_INTERRUPTS | Self::SGEIE;
 }
 
-read_csr_as!(Hie, 0x604);
-write_csr!(0x604);
-set!(0x604);
-clear!(0x604);
+hypervisor_interrupt_bits! {
+    Hie: Self::ALL_VS_INTERRUPTS | Self::SGEIE, "enabled"
+}
 
 // bit ops
 set_clear_csr!(
@@ -115,4 +70,54 @@ set_clear_csr!(
     /// Supervisor guest external interrupt enable.
     , set_sgeie, clear_sgeie, 1 << 12);
 
+/// Enables the VS-level software, timer and external interrupts.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which interrupts can be taken.
+#[inline]
+pub unsafe fn enable_all_vs() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(Hie::ALL_VS_INTERRUPTS) };
+}
+
+/// Enables the supervisor guest external interrupt.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which interrupts can be taken.
+#[inline]
+pub unsafe fn enable_sgeie() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(Hie::SGEIE) };
+}
+
+/// Enables every interrupt in [`Hie::GUEST_RUN`] with a single `csrrs`.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which interrupts can be taken.
+#[inline]
+pub unsafe fn enable_for_guest_run() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(Hie::GUEST_RUN) };
+}
+
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::interrupts::HypervisorInterrupt;
+
+    #[test]
+    fn test_presets() {
+        assert_eq!(Hie::ALL_VS_INTERRUPTS, (1 << 2) | (1 << 6) | (1 << 10));
+        let hie = Hie::from_bits(Hie::GUEST_RUN);
+        assert!(
+            HypervisorInterrupt::ALL
+                .into_iter()
+                .all(|irq| hie.is_set(irq))
+        );
+    }
+}
@@ -13,84 +13,40 @@
 // limitations under the License.
 
 //! Hypervisor Interrupt Pending Register.
+//!
+//! Only VSSIP, an alias of the same bit in `hvip`, is writable in `hip`, see
+//! [`Hip::WRITABLE_MASK`](crate::register::hip::Hip::WRITABLE_MASK). VSTIP and
+//! VSEIP are read-only views of `hvip` and of other interrupt sources, and
+//! SGEIP reflects `hgeip & hgeie`, so VS-level interrupts are asserted through
+//! [`hvip`](crate::register::hvip) instead.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::interrupts::hypervisor_interrupt_bits;
+use riscv::set_clear_csr;
 
-/// Hypervisor Interrupt Registers.
-#[derive(Copy, Clone, Debug)]
-pub struct Hip {
-    bits: usize,
+crate::register! {
+    /// Hypervisor Interrupt Registers.
+    Hip: 0x644 {
+        /// virtual supervisor software interrupt pending
+        vssip, set_vssip, VSSIP: [2];
+        /// virtual supervisor timer interrupt pending
+        vstip, set_vstip, VSTIP: [6], ReadOnly;
+        /// virtual supervisor external interrupt pending
+        vseip, set_vseip, VSEIP: [10], ReadOnly;
+        /// supervisor guest external interrupt pending
+        sgeip, set_sgeip, SGEIP: [12], ReadOnly;
+    }
 }
 
 impl Hip {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hip { bits: x }
-    }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
-    ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the virtual supervisor software interrupt pending.
-    #[inline]
-    pub fn vssip(&self) -> bool {
-        self.bits.get_bit(2)
-    }
-    /// Sets the virtual supervisor software interrupt pending.
-    #[inline]
-    pub fn set_vssip(&mut self, val: bool) {
-        self.bits.set_bit(2, val);
-    }
-    /// Returns the virtual supervisor timer interrupt pending.
-    #[inline]
-    pub fn vstip(&self) -> bool {
-        self.bits.get_bit(6)
-    }
-    /// Sets the virtual supervisor timer interrupt pending.
-    #[inline]
-    pub fn set_vstip(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
-    }
-    /// Returns the virtual supervisor external interrupt pending.
-    #[inline]
-    pub fn vseip(&self) -> bool {
-        self.bits.get_bit(10)
-    }
-    /// Sets the virtual supervisor external interrupt pending.
-    #[inline]
-    pub fn set_vseip(&mut self, val: bool) {
-        self.bits.set_bit(10, val);
-    }
-    /// Returns the supervisor guest external interrupt pending.
-    #[inline]
-    pub fn sgeip(&self) -> bool {
-        self.bits.get_bit(12)
-    }
-    /// Sets the supervisor guest external interrupt pending.
-    #[inline]
-    pub fn set_sgeip(&mut self, val: bool) {
-        self.bits.set_bit(12, val);
-    }
+    /// Bits that can be written in the CSR.
+    pub const WRITABLE_MASK: usize = Self::VSSIP;
 }
 
-read_csr_as!(Hip, 0x644);
-write_csr!(0x644);
-set!(0x644);
-clear!(0x644);
+hypervisor_interrupt_bits! {
+    ///
+    /// Only the bits in [`Self::WRITABLE_MASK`] take effect when written.
+    Hip: Self::VSSIP | Self::VSTIP | Self::VSEIP | Self::SGEIP, "pending"
+}
 
 // bit ops
 set_clear_csr!(
@@ -98,12 +54,32 @@ set_clear_csr!(
     , set_vssip, clear_vssip, 1 << 2);
 set_clear_csr!(
     /// Virtual supervisor timer interrupt pending enable.
+    #[deprecated(note = "VSTIP is read-only in `hip`; use `hvip::set_vstip`/`hvip::clear_vstip`")]
     , set_vstip, clear_vstip, 1 << 6);
 set_clear_csr!(
     /// Virtual supervisor external interrupt pending enable.
+    #[deprecated(note = "VSEIP is read-only in `hip`; use `hvip::set_vseip`/`hvip::clear_vseip`")]
     , set_vseip, clear_vseip, 1 << 10);
 set_clear_csr!(
     /// Supervisor guest external interrupt pending enable.
+    #[deprecated(note = "SGEIP is read-only; it reflects `hgeip & hgeie`")]
     , set_sgeip, clear_sgeip, 1 << 12);
 
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writable_mask() {
+        assert_eq!(Hip::WRITABLE_MASK, 1 << 2);
+        for field in Hip::FIELDS {
+            let writable = field.mask() & Hip::WRITABLE_MASK != 0;
+            assert_eq!(
+                writable,
+                field.access() != crate::register::Access::ReadOnly
+            );
+        }
+    }
+}
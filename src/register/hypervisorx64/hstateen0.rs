@@ -0,0 +1,92 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor State Enable Register 0.
+//!
+//! With Smstateen, `hstateen0` controls which optional state VS-mode and
+//! VU-mode may access. A cleared bit makes guest accesses to the
+//! corresponding state raise a virtual-instruction exception, so a hypervisor
+//! can hide state it does not context-switch. Bits are only writable when the
+//! matching `mstateen0` bit is set.
+
+use riscv::set_clear_csr;
+
+crate::register! {
+    /// Hypervisor State Enable Register 0.
+    Hstateen0: 0x60C {
+        /// custom state enable
+        c, set_c, C: [0];
+        /// fcsr access enable (Zfinx)
+        fcsr, set_fcsr, FCSR: [1];
+        /// jvt access enable (Zcmt)
+        jvt, set_jvt, JVT: [2];
+        /// scontext access enable (Sdtrig)
+        context, set_context, CONTEXT: [57];
+        /// IMSIC state enable (Ssaia)
+        imsic, set_imsic, IMSIC: [58];
+        /// AIA state enable (Ssaia)
+        aia, set_aia, AIA: [59];
+        /// siselect/sireg access enable (Sscsrind)
+        csrind, set_csrind, CSRIND: [60];
+        /// senvcfg access enable
+        envcfg, set_envcfg, ENVCFG: [62];
+        /// sstateen0 access enable
+        se0, set_se0, SE0: [63];
+    }
+}
+
+// bit ops
+set_clear_csr!(
+    /// Custom state enable.
+    , set_c, clear_c, 1 << 0);
+set_clear_csr!(
+    /// fcsr access enable.
+    , set_fcsr, clear_fcsr, 1 << 1);
+set_clear_csr!(
+    /// jvt access enable.
+    , set_jvt, clear_jvt, 1 << 2);
+set_clear_csr!(
+    /// scontext access enable.
+    , set_context, clear_context, 1 << 57);
+set_clear_csr!(
+    /// IMSIC state enable.
+    , set_imsic, clear_imsic, 1 << 58);
+set_clear_csr!(
+    /// AIA state enable.
+    , set_aia, clear_aia, 1 << 59);
+set_clear_csr!(
+    /// siselect/sireg access enable.
+    , set_csrind, clear_csrind, 1 << 60);
+set_clear_csr!(
+    /// senvcfg access enable.
+    , set_envcfg, clear_envcfg, 1 << 62);
+set_clear_csr!(
+    /// sstateen0 access enable.
+    , set_se0, clear_se0, 1 << 63);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hstateen0_fields() {
+        let mut hstateen0 = Hstateen0::from_bits(0);
+        hstateen0.set_se0(true);
+        hstateen0.set_aia(true);
+        hstateen0.set_imsic(true);
+        assert_eq!(hstateen0.bits(), (1 << 63) | (1 << 59) | (1 << 58));
+        assert!(!hstateen0.envcfg());
+        assert!(!hstateen0.csrind());
+    }
+}
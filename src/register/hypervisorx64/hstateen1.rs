@@ -0,0 +1,28 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor State Enable Register 1.
+//!
+//! Reserved by Smstateen for state of future extensions; only the `SE1` bit,
+//! which controls VS-mode access to `sstateen1`, is defined so far. Other bits
+//! are available through
+//! [`Hstateen1::bits`](crate::register::hstateen1::Hstateen1::bits).
+
+crate::register! {
+    /// Hypervisor State Enable Register 1.
+    Hstateen1: 0x60D {
+        /// sstateen1 access enable
+        se1, set_se1, SE1: [63];
+    }
+}
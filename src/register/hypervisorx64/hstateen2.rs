@@ -0,0 +1,28 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor State Enable Register 2.
+//!
+//! Reserved by Smstateen for state of future extensions; only the `SE2` bit,
+//! which controls VS-mode access to `sstateen2`, is defined so far. Other bits
+//! are available through
+//! [`Hstateen2::bits`](crate::register::hstateen2::Hstateen2::bits).
+
+crate::register! {
+    /// Hypervisor State Enable Register 2.
+    Hstateen2: 0x60E {
+        /// sstateen2 access enable
+        se2, set_se2, SE2: [63];
+    }
+}
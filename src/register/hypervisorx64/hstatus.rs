@@ -22,146 +22,122 @@
 //! - Virtual machine privilege and execution state
 //! - Guest virtual address translation controls  
 //! - Virtual interrupt management
-//! - Hypervisor user mode support
-
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
-
-/// Hypervisor Status Register
-#[derive(Copy, Clone, Debug)]
-pub struct Hstatus {
-    bits: usize,
+//! - Hypervisor user mode support, including pointer masking
+//!
+//! [`trap_came_from_guest`](crate::register::hstatus::trap_came_from_guest) is
+//! the check every trap handler starts with.
+
+use core::num::NonZeroUsize;
+use riscv::set_clear_csr;
+
+crate::register! {
+    /// Hypervisor Status Register
+    Hstatus: 0x600 {
+        /// pointer masking for HU-mode (Ssnpm)
+        hupmm, set_hupmm, HUPMM: [48..50] as PointerMasking, Warl;
+        /// effective XLEN for VS-mode
+        vsxl, set_vsxl, VSXL: [32..34] as VsxlValues, Warl;
+        /// TSR for VS-mode
+        vtsr, set_vtsr, VTSR: [22];
+        /// TW for VS-mode
+        vtw, set_vtw, VTW: [21];
+        /// TVM for VS-mode
+        vtvm, set_vtvm, VTVM: [20];
+        /// virtual guest external interrupt number
+        vgein_raw, set_vgein, VGEIN: [12..18];
+        /// hypervisor user mode status
+        hu, set_hu, HU: [9];
+        /// supervisor previous virtual privilege
+        spvp, set_spvp, SPVP: [8];
+        /// supervisor previous virtualization mode
+        spv, set_spv, SPV: [7];
+        /// guest virtual address status
+        gva, set_gva, GVA: [6];
+        /// VS-mode memory access endianness
+        vsbe, set_vsbe, VSBE: [5];
+    }
 }
 
 impl Hstatus {
-    /// Returns the raw bits of the register.
+    /// Returns the guest interrupt file selected for VS-level external
+    /// interrupts, or `None` if VGEIN is zero.
     #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
+    pub fn vgein(&self) -> Option<NonZeroUsize> {
+        NonZeroUsize::new(self.vgein_raw())
     }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hstatus { bits: x }
-    }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
+    /// Selects guest interrupt file `n`, or none with 0, on a hart with
+    /// `geilen` guest interrupt files.
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the effective XLEN for VS-mode.
-    #[inline]
-    pub fn vsxl(&self) -> VsxlValues {
-        VsxlValues::from(self.bits.get_bits(32..34))
-    }
-    /// Sets the effective XLEN for VS-mode.
-    #[inline]
-    pub fn set_vsxl(&mut self, val: VsxlValues) {
-        self.bits.set_bits(32..34, val as usize);
-    }
-    /// Returns the TSR for VS-mode.
-    #[inline]
-    pub fn vtsr(&self) -> bool {
-        self.bits.get_bit(22)
-    }
-    /// Sets the TSR for VS-mode.
-    #[inline]
-    pub fn set_vtsr(&mut self, val: bool) {
-        self.bits.set_bit(22, val);
-    }
-    /// Returns the TW for VS-mode.
-    #[inline]
-    pub fn vtw(&self) -> bool {
-        self.bits.get_bit(21)
-    }
-    /// Sets the TW for VS-mode.
-    #[inline]
-    pub fn set_vtw(&mut self, val: bool) {
-        self.bits.set_bit(21, val);
-    }
-    /// Returns the TVM for VS-mode.
-    #[inline]
-    pub fn vtvm(&self) -> bool {
-        self.bits.get_bit(20)
-    }
-    /// Sets the TVM for VS-mode.
-    #[inline]
-    pub fn set_vtvm(&mut self, val: bool) {
-        self.bits.set_bit(20, val);
-    }
-    /// Returns the virtual guest external interrupt number.
-    #[inline]
-    pub fn vgein(&self) -> usize {
-        self.bits.get_bits(12..18)
-    }
-    /// Sets the virtual guest external interrupt number.
-    #[inline]
-    pub fn set_vgein(&mut self, val: usize) {
-        self.bits.set_bits(12..18, val);
-    }
-    /// Returns the hypervisor user mode status.
-    #[inline]
-    pub fn hu(&self) -> bool {
-        self.bits.get_bit(9)
-    }
-    /// Sets the hypervisor user mode status.
-    #[inline]
-    pub fn set_hu(&mut self, val: bool) {
-        self.bits.set_bit(9, val);
-    }
-    /// Returns the supervisor previous virtual privilege.
-    #[inline]
-    pub fn spvp(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-    /// Sets the supervisor previous virtual privilege.
-    #[inline]
-    pub fn set_spvp(&mut self, val: bool) {
-        self.bits.set_bit(8, val);
-    }
-    /// Returns the supervisor previous virtualization mode.
-    #[inline]
-    pub fn spv(&self) -> bool {
-        self.bits.get_bit(7)
-    }
-    /// Sets the supervisor previous virtualization mode.
-    #[inline]
-    pub fn set_spv(&mut self, val: bool) {
-        self.bits.set_bit(7, val);
-    }
-    /// Returns the guest virtual address status.
+    /// Fails and leaves VGEIN unchanged if `n` exceeds `geilen` or the
+    /// 6-bit field.
     #[inline]
-    pub fn gva(&self) -> bool {
-        self.bits.get_bit(6)
+    pub fn set_vgein_checked(&mut self, n: usize, geilen: usize) -> Result<(), VgeinError> {
+        if n > geilen {
+            return Err(VgeinError::BeyondGeilen);
+        }
+        if n >> 6 != 0 {
+            return Err(VgeinError::TooLarge);
+        }
+        self.set_vgein(n);
+        Ok(())
     }
-    /// Sets the guest virtual address status.
+    /// Returns the mode the last trap into HS-mode was taken from.
     #[inline]
-    pub fn set_gva(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
+    pub fn previous_mode(&self) -> PreviousMode {
+        match (self.spv(), self.spvp()) {
+            (false, _) => PreviousMode::Host,
+            (true, false) => PreviousMode::VirtualUser,
+            (true, true) => PreviousMode::VirtualSupervisor,
+        }
     }
-    /// Returns the VS-mode memory access endianness.
+    /// Sets SPV and SPVP so that `sret` returns to `mode`.
+    ///
+    /// SPVP is left unchanged for [`PreviousMode::Host`], where it is not used.
     #[inline]
-    pub fn vsbe(&self) -> bool {
-        self.bits.get_bit(5)
+    pub fn set_previous_mode(&mut self, mode: PreviousMode) {
+        self.set_spv(mode.is_guest());
+        if mode.is_guest() {
+            self.set_spvp(mode == PreviousMode::VirtualSupervisor);
+        }
     }
-    /// Sets the VS-mode memory access endianness.
+}
+
+/// Reads `hstatus` once and returns whether the last trap into HS-mode was
+/// taken from VS-mode or VU-mode.
+#[inline]
+pub fn trap_came_from_guest() -> bool {
+    read().previous_mode().is_guest()
+}
+
+/// Errors selecting a guest interrupt file with [`Hstatus::set_vgein_checked`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VgeinError {
+    /// The hart implements fewer guest interrupt files (GEILEN).
+    BeyondGeilen,
+    /// The index does not fit in the 6-bit VGEIN field.
+    TooLarge,
+}
+
+/// Virtualization mode and privilege a trap was taken from, as recorded in
+/// SPV and SPVP.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PreviousMode {
+    /// HS-mode or U-mode, with virtualization off (SPV=0).
+    Host,
+    /// VU-mode (SPV=1, SPVP=0).
+    VirtualUser,
+    /// VS-mode (SPV=1, SPVP=1).
+    VirtualSupervisor,
+}
+
+impl PreviousMode {
+    /// Returns whether the mode is a virtualized guest mode.
     #[inline]
-    pub fn set_vsbe(&mut self, val: bool) {
-        self.bits.set_bit(5, val);
+    pub fn is_guest(self) -> bool {
+        self != PreviousMode::Host
     }
 }
 
-read_csr_as!(Hstatus, 0x600);
-write_csr!(0x600);
-set!(0x600);
-clear!(0x600);
-
 // bit ops
 set_clear_csr!(
     /// TSR for VS-mode enable.
@@ -211,6 +187,29 @@ impl VsxlValues {
     }
 }
 
+/// Pointer masking modes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum PointerMasking {
+    /// Pointer masking is disabled
+    Disabled = 0,
+    /// The upper 7 bits are masked (PMLEN=7), for Sv57 address spaces
+    Pm57 = 2,
+    /// The upper 16 bits are masked (PMLEN=16), for Sv48 address spaces
+    Pm48 = 3,
+}
+
+impl PointerMasking {
+    fn from(x: usize) -> Self {
+        match x {
+            0 => Self::Disabled,
+            2 => Self::Pm57,
+            3 => Self::Pm48,
+            _ => unreachable!(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +240,20 @@ mod tests {
         assert_eq!(hstatus.bits() & (0b11 << 32), 3 << 32);
     }
 
+    #[test]
+    fn test_hstatus_hupmm() {
+        let mut hstatus = Hstatus::from_bits(0);
+        assert_eq!(hstatus.hupmm(), PointerMasking::Disabled);
+
+        hstatus.set_hupmm(PointerMasking::Pm57);
+        assert_eq!(hstatus.hupmm(), PointerMasking::Pm57);
+        assert_eq!(hstatus.bits(), 2 << 48);
+
+        hstatus.set_hupmm(PointerMasking::Pm48);
+        assert_eq!(hstatus.hupmm(), PointerMasking::Pm48);
+        assert_eq!(hstatus.bits(), 3 << 48);
+    }
+
     #[test]
     fn test_hstatus_boolean_fields() {
         let mut hstatus = Hstatus::from_bits(0);
@@ -297,21 +310,58 @@ mod tests {
         assert_eq!(hstatus.bits() & (1 << 5), 1 << 5);
     }
 
+    #[test]
+    fn test_previous_mode() {
+        let mut hstatus = Hstatus::from_bits(0);
+        assert_eq!(hstatus.previous_mode(), PreviousMode::Host);
+        assert!(!hstatus.previous_mode().is_guest());
+
+        hstatus.set_previous_mode(PreviousMode::VirtualSupervisor);
+        assert!(hstatus.spv() && hstatus.spvp());
+        assert_eq!(hstatus.previous_mode(), PreviousMode::VirtualSupervisor);
+
+        hstatus.set_previous_mode(PreviousMode::VirtualUser);
+        assert!(hstatus.spv() && !hstatus.spvp());
+        assert!(hstatus.previous_mode().is_guest());
+
+        // SPVP is kept when returning to the host
+        hstatus.set_spvp(true);
+        hstatus.set_previous_mode(PreviousMode::Host);
+        assert!(!hstatus.spv() && hstatus.spvp());
+        assert_eq!(hstatus.previous_mode(), PreviousMode::Host);
+    }
+
+    #[test]
+    fn test_hstatus_vgein_checked() {
+        let mut hstatus = Hstatus::from_bits(0);
+        assert_eq!(hstatus.set_vgein_checked(5, 7), Ok(()));
+        assert_eq!(hstatus.vgein(), NonZeroUsize::new(5));
+        assert_eq!(
+            hstatus.set_vgein_checked(8, 7),
+            Err(VgeinError::BeyondGeilen)
+        );
+        assert_eq!(hstatus.vgein_raw(), 5);
+        assert_eq!(hstatus.set_vgein_checked(64, 64), Err(VgeinError::TooLarge));
+        assert_eq!(hstatus.set_vgein_checked(0, 7), Ok(()));
+        assert_eq!(hstatus.vgein(), None);
+    }
+
     #[test]
     fn test_hstatus_vgein() {
         let mut hstatus = Hstatus::from_bits(0);
 
         // Test setting VGEIN to various values (6-bit field, bits 12-17)
         hstatus.set_vgein(0x15); // 21 in decimal
-        assert_eq!(hstatus.vgein(), 0x15);
+        assert_eq!(hstatus.vgein_raw(), 0x15);
         assert_eq!(hstatus.bits() & (0x3F << 12), 0x15 << 12);
 
         // Test boundary values
         hstatus.set_vgein(0);
-        assert_eq!(hstatus.vgein(), 0);
+        assert_eq!(hstatus.vgein_raw(), 0);
+        assert_eq!(hstatus.vgein(), None);
 
         hstatus.set_vgein(0x3F); // Maximum 6-bit value
-        assert_eq!(hstatus.vgein(), 0x3F);
+        assert_eq!(hstatus.vgein_raw(), 0x3F);
         assert_eq!(hstatus.bits() & (0x3F << 12), 0x3F << 12);
     }
 
@@ -339,7 +389,7 @@ mod tests {
         hstatus.set_vsxl(VsxlValues::Vsxl64);
 
         assert!(hstatus.vtsr());
-        assert_eq!(hstatus.vgein(), 0x2A);
+        assert_eq!(hstatus.vgein_raw(), 0x2A);
         assert!(hstatus.hu());
         assert!(matches!(hstatus.vsxl(), VsxlValues::Vsxl64));
 
@@ -13,8 +13,227 @@
 // limitations under the License.
 
 //! Hypervisor Time Delta Register.
-use riscv::{read_composite_csr, read_csr_as_usize, write_csr_as_usize};
+//!
+//! On RV32 the 64-bit delta is split across `htimedelta` (low half) and
+//! `htimedeltah` (high half). Use
+//! [`read64`](crate::register::htimedelta::read64) to obtain
+//! the full value without observing a torn mix of old and new halves, and
+//! [`write64`](crate::register::htimedelta::write64) to update it without
+//! exposing a delta larger than both the old and the new one.
+//!
+//! The delta is a two's-complement value added to `time`, so a guest clock
+//! behind the host has a negative delta.
+//! [`read_signed`](crate::register::htimedelta::read_signed),
+//! [`write_signed`](crate::register::htimedelta::write_signed) and the
+//! [`Htimedelta`](crate::register::htimedelta::Htimedelta) wrapper work with it
+//! as an `i64`.
+use riscv::{read_csr_as_usize, write_csr_as_usize};
 
-read_composite_csr!(super::htimedeltah::read(), read());
 read_csr_as_usize!(0x605);
 write_csr_as_usize!(0x605);
+
+/// Reads the full 64-bit time delta without tearing.
+///
+/// On RV32 the high half is read again after the low half and the whole
+/// sequence is retried if it changed, so an update of the register pair by
+/// another context can never yield the high half of one value combined with
+/// the low half of another. On RV64 this is a single CSR read.
+#[inline]
+pub fn read64() -> u64 {
+    match () {
+        #[cfg(target_arch = "riscv32")]
+        () => composite(super::htimedeltah::read, read),
+        #[cfg(not(target_arch = "riscv32"))]
+        () => read() as u64,
+    }
+}
+
+/// Writes the full 64-bit time delta.
+///
+/// On RV32 the low half is cleared before the high half is written and the
+/// low half last. Every intermediate delta is then at most the larger of the
+/// old and new values, so a running guest timer cannot see its `time` jump
+/// past `vstimecmp` and raise a spurious interrupt, which a naive high-then-low
+/// or low-then-high sequence can cause. On RV64 this is a single CSR write.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes the time observed by the guest.
+#[inline]
+pub unsafe fn write64(delta: u64) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        match () {
+            #[cfg(target_arch = "riscv32")]
+            () => super::split_write(delta, 0, |lo| write(lo), |hi| super::htimedeltah::write(hi)),
+            #[cfg(not(target_arch = "riscv32"))]
+            () => write(delta as usize),
+        }
+    }
+}
+
+/// Reads the full time delta as a signed value.
+#[inline]
+pub fn read_signed() -> i64 {
+    read64() as i64
+}
+
+/// Writes the full time delta as a signed value.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes the time observed by the guest.
+#[inline]
+pub unsafe fn write_signed(delta: i64) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { write64(delta as u64) };
+}
+
+/// Offset between guest and host time.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Htimedelta {
+    bits: u64,
+}
+
+impl Htimedelta {
+    /// Reads the current delta.
+    #[inline]
+    pub fn current() -> Self {
+        Htimedelta { bits: read64() }
+    }
+    /// Creates a delta from its raw 64-bit value.
+    #[inline]
+    pub const fn from_bits(bits: u64) -> Self {
+        Htimedelta { bits }
+    }
+    /// Creates a delta from a signed offset.
+    #[inline]
+    pub const fn from_signed(delta: i64) -> Self {
+        Htimedelta { bits: delta as u64 }
+    }
+    /// Creates the delta that makes the guest observe `guest` at host time `host`.
+    #[inline]
+    pub const fn between(guest: u64, host: u64) -> Self {
+        Htimedelta {
+            bits: guest.wrapping_sub(host),
+        }
+    }
+    /// Returns the raw 64-bit value.
+    #[inline]
+    pub const fn bits(&self) -> u64 {
+        self.bits
+    }
+    /// Returns the delta as a signed offset.
+    #[inline]
+    pub const fn signed(&self) -> i64 {
+        self.bits as i64
+    }
+    /// Returns the guest time at host time `host`.
+    #[inline]
+    pub const fn guest_time(&self, host: u64) -> u64 {
+        host.wrapping_add(self.bits)
+    }
+    /// Returns the host time at guest time `guest`.
+    #[inline]
+    pub const fn host_time(&self, guest: u64) -> u64 {
+        guest.wrapping_sub(self.bits)
+    }
+    /// Writes the delta to the CSR.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it changes the time observed by the guest.
+    #[inline]
+    pub unsafe fn write(&self) {
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe { write_signed(self.signed()) };
+    }
+}
+
+/// Combines two 32-bit halves, re-reading `hi` until it is stable around `lo`.
+#[cfg(any(target_arch = "riscv32", test))]
+fn composite(mut hi: impl FnMut() -> usize, mut lo: impl FnMut() -> usize) -> u64 {
+    loop {
+        let high = hi();
+        let low = lo();
+        if hi() == high {
+            return ((high as u32 as u64) << 32) | low as u32 as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_delta() {
+        let delta = Htimedelta::from_signed(-1000);
+        assert_eq!(delta.bits(), 0xFFFF_FFFF_FFFF_FC18);
+        assert_eq!(delta.signed(), -1000);
+        assert_eq!(delta.guest_time(5000), 4000);
+        assert_eq!(delta.host_time(4000), 5000);
+        // Wraps like the hardware addition.
+        assert_eq!(delta.guest_time(10), u64::MAX - 989);
+
+        let delta = Htimedelta::between(100, 1100);
+        assert_eq!(delta, Htimedelta::from_signed(-1000));
+        assert_eq!(Htimedelta::between(1100, 100).signed(), 1000);
+    }
+
+    #[test]
+    fn test_split_write_order() {
+        use core::cell::Cell;
+
+        // Every delta observable between the writes stays at or below the
+        // larger of the old and new values.
+        for (old, new) in [
+            (0x1_FFFF_FFFF, 0x2_0000_0000),
+            (0x2_0000_0000, 0x1_FFFF_FFFF),
+        ] {
+            let value = Cell::new(old);
+            let peak = Cell::new(0);
+            let update = |keep: u64, bits: u64| {
+                value.set((value.get() & keep) | bits);
+                peak.set(peak.get().max(value.get()));
+            };
+            super::super::split_write(
+                new,
+                0,
+                |lo| update(!0xFFFF_FFFF, lo as u64),
+                |hi| update(0xFFFF_FFFF, (hi as u64) << 32),
+            );
+            assert_eq!(value.get(), new);
+            assert_eq!(peak.get(), old.max(new));
+        }
+    }
+
+    #[test]
+    fn test_composite_stable() {
+        assert_eq!(
+            composite(|| 0x1234_5678, || 0x9ABC_DEF0),
+            0x1234_5678_9ABC_DEF0
+        );
+    }
+
+    #[test]
+    fn test_composite_retries_on_carry() {
+        // The low half wraps between the first and second read of the high
+        // half, so the first (torn) combination must be discarded.
+        let highs = [0x1, 0x2, 0x2, 0x2];
+        let lows = [0xFFFF_FFFF, 0x0000_0003];
+        let (mut h, mut l) = (0, 0);
+        let value = composite(
+            || {
+                h += 1;
+                highs[h - 1]
+            },
+            || {
+                l += 1;
+                lows[l - 1]
+            },
+        );
+        assert_eq!(value, 0x2_0000_0003);
+        assert_eq!(l, 2);
+    }
+}
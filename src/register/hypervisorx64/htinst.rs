@@ -11,9 +11,367 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-
 //! Hypervisor Trap Instruction Register.
-use riscv::{read_csr_as_usize, write_csr_as_usize};
+//!
+//! On a trap into HS-mode, `htinst` holds zero, a transformed version of the
+//! trapping instruction, or a pseudoinstruction for an implicit access of the
+//! VS-stage page-table walk. For loads and stores, the transformed instruction
+//! keeps the opcode, width and data register of the original instruction,
+//! replaces its address operands with the offset of the faulting address, and
+//! clears bit 1 if the original instruction was compressed.
+//! [`Htinst::decode`](crate::register::htinst::Htinst::decode) turns such a
+//! value into an [`Access`](crate::register::htinst::Access), which is all an
+//! MMIO emulator needs to complete the access.
+//!
+//! [`Htinst::classification`](crate::register::htinst::Htinst::classification)
+//! tells the three kinds of values apart, so that a trap handler can handle
+//! implicit accesses before attempting a decode.
+//!
+//! When forwarding a trap to a nested hypervisor or from firmware, the value to
+//! report in `htinst` or `mtinst` is assembled with
+//! [`TransformedBuilder`](crate::register::htinst::TransformedBuilder):
+//!
+//! ```
+//! use riscv_h::register::htinst::{AccessKind, TransformedBuilder};
+//!
+//! // lhu a0, misaligned by one byte
+//! let value = TransformedBuilder::new(AccessKind::Load, 2, 10)
+//!     .offset(1)
+//!     .build();
+//! assert_eq!(value.bits(), 0x0000_d503);
+//! ```
+
+crate::register! {
+    /// Hypervisor Trap Instruction Register.
+    Htinst: 0x64A {
+        /// source register of a transformed store
+        rs2, set_rs2, RS2: [20..25];
+        /// offset of the faulting address from the original effective address
+        offset, set_offset, OFFSET: [15..20];
+        /// width and signedness of a transformed load or store
+        funct3, set_funct3, FUNCT3: [12..15];
+        /// destination register of a transformed load
+        rd, set_rd, RD: [7..12];
+        /// opcode, with bit 1 cleared if the original instruction was compressed
+        opcode, set_opcode, OPCODE: [0..7];
+    }
+}
+
+/// Pseudoinstruction for a 32-bit read of a VS-stage page-table entry.
+pub const PSEUDO_READ_32: usize = 0x0000_2000;
+/// Pseudoinstruction for a 32-bit write of a VS-stage page-table entry, to
+/// update its A/D bits.
+pub const PSEUDO_WRITE_32: usize = 0x0000_2020;
+/// Pseudoinstruction for a 64-bit read of a VS-stage page-table entry.
+pub const PSEUDO_READ_64: usize = 0x0000_3000;
+/// Pseudoinstruction for a 64-bit write of a VS-stage page-table entry, to
+/// update its A/D bits.
+pub const PSEUDO_WRITE_64: usize = 0x0000_3020;
+
+/// Major opcode of integer loads.
+const OPCODE_LOAD: usize = 0b000_0011;
+/// Major opcode of integer stores.
+const OPCODE_STORE: usize = 0b010_0011;
+
+/// Kind of value held in `htinst`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Classification {
+    /// No information about the trapping instruction is provided.
+    Zero,
+    /// The trap was caused by an implicit access of the VS-stage page-table
+    /// walk, described by one of the `PSEUDO_*` values.
+    Pseudo,
+    /// A transformed version of the trapping instruction.
+    Transformed,
+}
+
+/// Direction of a decoded guest access.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    /// The guest reads memory into `reg`.
+    Load,
+    /// The guest writes `reg` to memory.
+    Store,
+}
+
+/// A guest load or store decoded from a transformed instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Access {
+    /// Whether the access is a load or a store.
+    pub kind: AccessKind,
+    /// Access width in bytes.
+    pub width: usize,
+    /// Destination register of a load or source register of a store.
+    pub reg: usize,
+    /// Whether a loaded value is sign-extended to XLEN.
+    pub sign_extend: bool,
+    /// Whether the trapping instruction was a 16-bit compressed instruction.
+    pub compressed: bool,
+    /// Offset of the faulting address from the start of the access, non-zero
+    /// only for misaligned accesses split by the implementation.
+    pub offset: usize,
+}
+
+impl Access {
+    /// Returns the length in bytes of the trapping instruction, to advance
+    /// `sepc` by once the access has been emulated.
+    #[inline]
+    pub fn insn_len(&self) -> usize {
+        if self.compressed { 2 } else { 4 }
+    }
+}
+
+impl Htinst {
+    /// Returns what kind of value the register holds.
+    ///
+    /// Transformed instructions have bit 0 set, while pseudoinstructions
+    /// have it cleared. Reserved values with bit 0 cleared are reported as
+    /// [`Classification::Pseudo`] as well.
+    #[inline]
+    pub fn classification(&self) -> Classification {
+        match self.bits {
+            0 => Classification::Zero,
+            bits if bits & 1 == 0 => Classification::Pseudo,
+            _ => Classification::Transformed,
+        }
+    }
+    /// Returns whether the register holds a pseudoinstruction that writes a
+    /// page-table entry, rather than one that reads it.
+    #[inline]
+    pub fn is_pseudo_write(&self) -> bool {
+        matches!(self.bits, PSEUDO_WRITE_32 | PSEUDO_WRITE_64)
+    }
+    /// Decodes a transformed integer load or store.
+    ///
+    /// Returns `None` for zero, pseudoinstructions and every other kind of
+    /// transformed instruction, including floating-point loads and stores and
+    /// atomics. The guest instruction then has to be fetched and decoded by
+    /// the caller.
+    pub fn decode(&self) -> Option<Access> {
+        if self.classification() != Classification::Transformed {
+            return None;
+        }
+        let compressed = self.bits & 0b10 == 0;
+        let (kind, reg) = match self.opcode() | 0b10 {
+            OPCODE_LOAD => (AccessKind::Load, self.rd()),
+            OPCODE_STORE => (AccessKind::Store, self.rs2()),
+            _ => return None,
+        };
+        let funct3 = self.funct3();
+        let (width, sign_extend) = match (kind, funct3) {
+            (AccessKind::Load, 0..=2) => (1 << funct3, true),
+            (AccessKind::Load, 3) => (8, false),
+            (AccessKind::Load, 4..=6) => (1 << (funct3 - 4), false),
+            (AccessKind::Store, 0..=3) => (1 << funct3, false),
+            _ => return None,
+        };
+        Some(Access {
+            kind,
+            width,
+            reg,
+            sign_extend,
+            compressed,
+            offset: self.offset(),
+        })
+    }
+}
+
+/// Builder of transformed load and store instructions.
+///
+/// Loads are zero-extending unless [`sign_extend`](Self::sign_extend) is
+/// requested; 64-bit loads have no such distinction.
+#[derive(Copy, Clone, Debug)]
+pub struct TransformedBuilder {
+    kind: AccessKind,
+    width: usize,
+    reg: usize,
+    sign_extend: bool,
+    compressed: bool,
+    offset: usize,
+}
+
+impl TransformedBuilder {
+    /// Starts a non-compressed, aligned access of `width` bytes to or from
+    /// register `reg`.
+    #[inline]
+    pub const fn new(kind: AccessKind, width: usize, reg: usize) -> Self {
+        TransformedBuilder {
+            kind,
+            width,
+            reg,
+            sign_extend: false,
+            compressed: false,
+            offset: 0,
+        }
+    }
+    /// Sets whether a load sign-extends the value. Ignored for stores.
+    #[inline]
+    pub const fn sign_extend(mut self, sign_extend: bool) -> Self {
+        self.sign_extend = sign_extend;
+        self
+    }
+    /// Sets whether the original instruction was compressed.
+    #[inline]
+    pub const fn compressed(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+    /// Sets the offset of the faulting address from the start of the access.
+    #[inline]
+    pub const fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+    /// Returns the transformed instruction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width is not 1, 2, 4 or 8, if the register is not
+    /// within `0..32`, or if the offset is not smaller than the width.
+    pub fn build(self) -> Htinst {
+        assert!(matches!(self.width, 1 | 2 | 4 | 8), "invalid access width");
+        assert!(self.reg < 32, "invalid register");
+        assert!(self.offset < self.width, "offset outside of the access");
+        let size = self.width.trailing_zeros() as usize;
+        let mut value = Htinst::from_bits(0);
+        match self.kind {
+            AccessKind::Load => {
+                let unsigned = !self.sign_extend && self.width < 8;
+                value.set_opcode(OPCODE_LOAD);
+                value.set_funct3(size | if unsigned { 0b100 } else { 0 });
+                value.set_rd(self.reg);
+            }
+            AccessKind::Store => {
+                value.set_opcode(OPCODE_STORE);
+                value.set_funct3(size);
+                value.set_rs2(self.reg);
+            }
+        }
+        if self.compressed {
+            value.set_opcode(value.opcode() & !0b10);
+        }
+        value.set_offset(self.offset);
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification() {
+        assert_eq!(Htinst::from_bits(0).classification(), Classification::Zero);
+        for pseudo in [
+            PSEUDO_READ_32,
+            PSEUDO_WRITE_32,
+            PSEUDO_READ_64,
+            PSEUDO_WRITE_64,
+        ] {
+            let htinst = Htinst::from_bits(pseudo);
+            assert_eq!(htinst.classification(), Classification::Pseudo);
+            assert_eq!(htinst.is_pseudo_write(), pseudo & 0x20 != 0);
+        }
+        let htinst = Htinst::from_bits(0x0000_2503);
+        assert_eq!(htinst.classification(), Classification::Transformed);
+        assert!(!htinst.is_pseudo_write());
+        let htinst = Htinst::from_bits(0x0000_3501);
+        assert_eq!(htinst.classification(), Classification::Transformed);
+    }
+
+    #[test]
+    fn test_decode_load() {
+        // lw a0, 8(a1) transformed: rs1 and the immediate are cleared
+        let access = Htinst::from_bits(0x0000_2503).decode().unwrap();
+        assert_eq!(access.kind, AccessKind::Load);
+        assert_eq!(access.width, 4);
+        assert_eq!(access.reg, 10);
+        assert!(access.sign_extend);
+        assert!(!access.compressed);
+        assert_eq!(access.insn_len(), 4);
+
+        // lhu t0, with a faulting address two bytes into the access
+        let access = Htinst::from_bits(0x0001_5283).decode().unwrap();
+        assert_eq!(access.width, 2);
+        assert_eq!(access.reg, 5);
+        assert!(!access.sign_extend);
+        assert_eq!(access.offset, 2);
+
+        // c.ld a0, 0(a1) is reported as an ld with bit 1 cleared
+        let access = Htinst::from_bits(0x0000_3501).decode().unwrap();
+        assert_eq!(access.width, 8);
+        assert!(access.compressed);
+        assert_eq!(access.insn_len(), 2);
+    }
+
+    #[test]
+    fn test_decode_store() {
+        // sb a2, 0(a0)
+        let access = Htinst::from_bits(0x00c0_0023).decode().unwrap();
+        assert_eq!(access.kind, AccessKind::Store);
+        assert_eq!(access.width, 1);
+        assert_eq!(access.reg, 12);
+        assert!(!access.sign_extend);
+
+        // c.sw a5, 4(a0)
+        let access = Htinst::from_bits(0x00f0_2021).decode().unwrap();
+        assert_eq!(access.width, 4);
+        assert_eq!(access.reg, 15);
+        assert!(access.compressed);
+    }
+
+    #[test]
+    fn test_builder_round_trip() {
+        let accesses = [
+            (AccessKind::Load, 1, 10, true, false, 0),
+            (AccessKind::Load, 2, 5, false, true, 1),
+            (AccessKind::Load, 4, 31, false, false, 3),
+            (AccessKind::Load, 8, 1, false, true, 0),
+            (AccessKind::Store, 1, 12, false, false, 0),
+            (AccessKind::Store, 8, 15, false, true, 7),
+        ];
+        for (kind, width, reg, sign_extend, compressed, offset) in accesses {
+            let htinst = TransformedBuilder::new(kind, width, reg)
+                .sign_extend(sign_extend)
+                .compressed(compressed)
+                .offset(offset)
+                .build();
+            let expected = Access {
+                kind,
+                width,
+                reg,
+                sign_extend,
+                compressed,
+                offset,
+            };
+            assert_eq!(htinst.decode(), Some(expected));
+        }
+        // Matches the encodings decoded above
+        let lw = TransformedBuilder::new(AccessKind::Load, 4, 10).sign_extend(true);
+        assert_eq!(lw.build().bits(), 0x0000_2503);
+        let c_sw = TransformedBuilder::new(AccessKind::Store, 4, 15).compressed(true);
+        assert_eq!(c_sw.build().bits(), 0x00f0_2021);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_builder_rejects_offset_outside_access() {
+        TransformedBuilder::new(AccessKind::Load, 2, 10)
+            .offset(2)
+            .build();
+    }
 
-read_csr_as_usize!(0x64A);
-write_csr_as_usize!(0x64A);
+    #[test]
+    fn test_decode_rejects_others() {
+        // Nothing reported
+        assert_eq!(Htinst::from_bits(0).decode(), None);
+        // Pseudoinstruction for an implicit page-table read
+        assert_eq!(Htinst::from_bits(0x3000).decode(), None);
+        // flw fa0, 0(a0)
+        assert_eq!(Htinst::from_bits(0x0000_2507).decode(), None);
+        // amoadd.w a0, a1, (a0)
+        assert_eq!(Htinst::from_bits(0x00b0_252f).decode(), None);
+        // Reserved load width
+        assert_eq!(Htinst::from_bits(0x0000_7503).decode(), None);
+    }
+}
@@ -13,7 +13,46 @@
 // limitations under the License.
 
 //! Hypervisor Trap Value Register.
-use riscv::{read_csr_as_usize, write_csr_as_usize};
+//!
+//! On a guest-page fault, `htval` holds the faulting guest physical address
+//! shifted right by two; use
+//! [`Htval::guest_physical_address`](crate::register::htval::Htval::guest_physical_address)
+//! rather than the raw value. It is zero for other traps, and may also be zero
+//! for guest-page faults on implementations that do not report the address.
 
-read_csr_as_usize!(0x643);
-write_csr_as_usize!(0x643);
+crate::register! {
+    /// Hypervisor Trap Value Register.
+    Htval: 0x643 {}
+}
+
+impl Htval {
+    /// Returns the raw register value, i.e. the address shifted right by two.
+    #[inline]
+    pub fn raw(&self) -> usize {
+        self.bits
+    }
+    /// Returns the faulting guest physical address.
+    ///
+    /// `htval` does not record the two low bits of the address, so they are
+    /// always zero here. The result is a `u64` because Sv32x4 guest physical
+    /// addresses are 34 bits wide on RV32.
+    #[inline]
+    pub fn guest_physical_address(&self) -> u64 {
+        (self.bits as u64) << 2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_physical_address() {
+        let htval = Htval::from_bits(0x2000_0400);
+        assert_eq!(htval.raw(), 0x2000_0400);
+        assert_eq!(htval.guest_physical_address(), 0x8000_1000);
+        // Sv32x4 addresses above 4 GiB survive the shift on RV32
+        let htval = Htval::from_bits(0x8000_0000);
+        assert_eq!(htval.guest_physical_address(), 0x2_0000_0000);
+    }
+}
@@ -0,0 +1,58 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Virtual Interrupt Control Register (AIA).
+//!
+//! With VTI set, `hvictl` injects a single interrupt with an explicit
+//! identity and priority into VS-level interrupt selection, in addition to
+//! the interrupts pending in `vsip`. This lets a hypervisor deliver
+//! interrupts that are not backed by `hvip` bits, and control the priority
+//! the guest observes in `vstopi`.
+
+crate::register! {
+    /// Hypervisor Virtual Interrupt Control Register.
+    Hvictl: 0x609 {
+        /// virtual trap interrupt control
+        vti, set_vti, VTI: [30];
+        /// interrupt identity of the injected interrupt
+        iid, set_iid, IID: [16..28];
+        /// default priority rank: just above (false) or just below (true) supervisor external interrupts
+        dpr, set_dpr, DPR: [9];
+        /// report IPRIO (true) or 1 (false) as the priority in `vstopi`
+        ipriom, set_ipriom, IPRIOM: [8];
+        /// priority number of the injected interrupt
+        iprio, set_iprio, IPRIO: [0..8];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hvictl_fields() {
+        let mut hvictl = Hvictl::from_bits(0);
+        hvictl.set_vti(true);
+        hvictl.set_iid(13);
+        hvictl.set_ipriom(true);
+        hvictl.set_iprio(0x20);
+        assert_eq!(hvictl.bits(), (1 << 30) | (13 << 16) | (1 << 8) | 0x20);
+        assert!(!hvictl.dpr());
+
+        let hvictl = Hvictl::from_bits(0x4fff_03ff);
+        assert_eq!(hvictl.iid(), 0xfff);
+        assert_eq!(hvictl.iprio(), 0xff);
+        assert!(hvictl.dpr());
+    }
+}
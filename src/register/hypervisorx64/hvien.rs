@@ -0,0 +1,130 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Virtual Interrupt Enable Register (AIA).
+//!
+//! With AIA, `hvien` selects which of the interrupts 13–63 the hypervisor
+//! injects into VS-mode through `hvip` rather than through the guest's own
+//! `sie`. A set bit makes the matching `hvip` bit visible in the guest's
+//! `vsip`/`sip`, e.g. to hand a local counter-overflow interrupt (LCOFI) to
+//! the guest. Bits 0–12 are read-only zero; on RV32 bits 32–63 live in
+//! `hvienh`.
+
+use bit_field::BitField;
+use riscv::set_clear_csr;
+
+crate::register! {
+    /// Hypervisor Virtual Interrupt Enable Register.
+    Hvien: 0x608 {
+        /// local counter overflow interrupt enable
+        lcofie, set_lcofie, LCOFIE: [13];
+    }
+}
+
+/// Lowest interrupt number controlled by `hvien`.
+pub const FIRST_IRQ: usize = 13;
+/// Interrupt number of the local counter overflow interrupt (Sscofpmf).
+pub const LCOFI: usize = 13;
+
+/// Returns the `hvien` bit mask of interrupt `irq`.
+///
+/// # Panics
+///
+/// Panics if `irq` is below [`FIRST_IRQ`] or not representable in the
+/// register on this XLEN.
+#[inline]
+pub fn irq_mask(irq: usize) -> usize {
+    assert!(
+        (FIRST_IRQ..usize::BITS as usize).contains(&irq),
+        "interrupt {irq} is not controlled by hvien"
+    );
+    1 << irq
+}
+
+impl Hvien {
+    /// Returns whether injection of interrupt `irq` is enabled.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `irq` is out of range, see [`irq_mask`].
+    #[inline]
+    pub fn enabled(&self, irq: usize) -> bool {
+        self.bits & irq_mask(irq) != 0
+    }
+    /// Enables or disables injection of interrupt `irq`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `irq` is out of range, see [`irq_mask`].
+    #[inline]
+    pub fn set_enabled(&mut self, irq: usize, enable: bool) {
+        irq_mask(irq);
+        self.bits.set_bit(irq, enable);
+    }
+}
+
+// bit ops
+set_clear_csr!(
+    /// Local counter overflow interrupt enable.
+    , set_lcofie, clear_lcofie, 1 << 13);
+
+/// Enables injection of interrupt `irq` in the `hvien` CSR.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which interrupts the guest can
+/// observe. Panics if `irq` is out of range, see [`irq_mask`].
+#[inline]
+pub unsafe fn enable(irq: usize) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(irq_mask(irq)) };
+}
+
+/// Disables injection of interrupt `irq` in the `hvien` CSR.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes which interrupts the guest can
+/// observe. Panics if `irq` is out of range, see [`irq_mask`].
+#[inline]
+pub unsafe fn disable(irq: usize) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(irq_mask(irq)) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hvien_per_irq() {
+        let mut hvien = Hvien::from_bits(0);
+        hvien.set_enabled(13, true);
+        hvien.set_enabled(63, true);
+        assert!(hvien.lcofie());
+        assert!(hvien.enabled(13));
+        assert!(hvien.enabled(63));
+        assert!(!hvien.enabled(14));
+        assert_eq!(hvien.bits(), (1 << 63) | Hvien::LCOFIE);
+
+        hvien.set_enabled(13, false);
+        assert!(!hvien.lcofie());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_hvien_rejects_low_irq() {
+        Hvien::from_bits(0).set_enabled(9, true);
+    }
+}
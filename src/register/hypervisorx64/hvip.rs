@@ -13,84 +13,223 @@
 // limitations under the License.
 
 //! Hypervisor Virtual Interrupt Pending Register.
+//!
+//! Bits 2, 6 and 10 assert the VS-level software, timer and external
+//! interrupts. With AIA, bits 13–63 additionally assert local interrupts for
+//! the guest, provided their bit in `hvien` is set;
+//! [`Hvip::set_interrupt`](crate::register::hvip::Hvip::set_interrupt) and
+//! friends access them by interrupt number.
+//! [`inject_lcofi`](crate::register::hvip::inject_lcofi) hands a
+//! counter-overflow interrupt of a virtualized PMU to the guest.
+//!
+//! [`assert_many`](crate::register::hvip::assert_many) and
+//! [`deassert_many`](crate::register::hvip::deassert_many) change several bits
+//! with a single CSR instruction, for a mask composed with
+//! [`HvipMask`](crate::register::hvip::HvipMask):
+//!
+//! ```no_run
+//! use riscv_h::register::hvip::{self, HvipMask};
+//!
+//! let mask = HvipMask::new().vsti().vsei();
+//! unsafe { hvip::assert_many(mask) };
+//! ```
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::hvien::{self, irq_mask};
+use crate::register::interrupts::hypervisor_interrupt_bits;
+use riscv::set_clear_csr;
 
-/// Hypervisor Virtual Interrupt Pending Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Hvip {
-    bits: usize,
+crate::register! {
+    /// Hypervisor Virtual Interrupt Pending Register.
+    Hvip: 0x645 {
+        /// virtual supervisor software interrupt pending
+        vssip, set_vssip, VSSIP: [2];
+        /// virtual supervisor timer interrupt pending
+        vstip, set_vstip, VSTIP: [6];
+        /// virtual supervisor external interrupt pending
+        vseip, set_vseip, VSEIP: [10];
+    }
 }
 
 impl Hvip {
-    /// Returns the raw bits of the register.
+    /// Asserts AIA local interrupt `irq` for the guest.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `irq` is within 13..XLEN, see [`irq_mask`].
     #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
+    pub fn set_interrupt(&mut self, irq: usize) {
+        self.bits |= irq_mask(irq);
     }
-    /// Creates a register value from raw bits.
+    /// Deasserts AIA local interrupt `irq`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `irq` is within 13..XLEN, see [`irq_mask`].
     #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Hvip { bits: x }
+    pub fn clear_interrupt(&mut self, irq: usize) {
+        self.bits &= !irq_mask(irq);
     }
-    /// Writes the register value to the CSR.
+    /// Returns whether AIA local interrupt `irq` is asserted.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
+    /// Panics unless `irq` is within 13..XLEN, see [`irq_mask`].
     #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
+    pub fn is_pending(&self, irq: usize) -> bool {
+        self.bits & irq_mask(irq) != 0
+    }
+}
+
+hypervisor_interrupt_bits! {
+    ///
+    /// SGEI has no bit in `hvip` and is ignored.
+    Hvip: Self::VSSIP | Self::VSTIP | Self::VSEIP, "pending"
+}
+
+// bit ops
+set_clear_csr!(
+    /// Virtual supervisor software interrupt pending enable.
+    , set_vssip, clear_vssip, 1 << 2);
+set_clear_csr!(
+    /// Virtual supervisor timer interrupt pending enable.
+    , set_vstip, clear_vstip, 1 << 6);
+set_clear_csr!(
+    /// Virtual supervisor external interrupt pending enable.
+    , set_vseip, clear_vseip, 1 << 10);
+
+// enums
+
+/// Asserts every interrupt in `mask` with a single `csrrs`.
+///
+/// # Safety
+///
+/// This function is unsafe because it injects interrupts into the guest.
+#[inline]
+pub unsafe fn assert_many(mask: HvipMask) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(mask.bits) };
+}
+
+/// Deasserts every interrupt in `mask` with a single `csrrc`.
+///
+/// # Safety
+///
+/// This function is unsafe because it retracts interrupts from the guest.
+#[inline]
+pub unsafe fn deassert_many(mask: HvipMask) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(mask.bits) };
+}
+
+/// Injects a local counter overflow interrupt into the guest.
+///
+/// Enables LCOFI injection in `hvien` and asserts it in `hvip`. The guest
+/// clears the interrupt through its own `sip` once it has handled it.
+///
+/// # Safety
+///
+/// Requires AIA and Sscofpmf support. This function is unsafe because it
+/// injects an interrupt into the guest.
+#[inline]
+pub unsafe fn inject_lcofi() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        hvien::set_lcofie();
+        _set(lcofi_mask());
     }
-    /// Returns the virtual supervisor software interrupt pending.
+}
+
+/// Returns the `hvip` bit asserted by
+/// [`inject_lcofi`](crate::register::hvip::inject_lcofi).
+#[inline]
+fn lcofi_mask() -> usize {
+    irq_mask(hvien::LCOFI)
+}
+
+/// Builder of `hvip` interrupt masks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HvipMask {
+    bits: usize,
+}
+
+impl HvipMask {
+    /// Starts with an empty mask.
     #[inline]
-    pub fn vssip(&self) -> bool {
-        self.bits.get_bit(2)
+    pub const fn new() -> Self {
+        HvipMask { bits: 0 }
     }
-    /// Sets the virtual supervisor software interrupt pending.
+    /// Adds the VS-level software interrupt.
     #[inline]
-    pub fn set_vssip(&mut self, val: bool) {
-        self.bits.set_bit(2, val);
+    pub const fn vssi(mut self) -> Self {
+        self.bits |= Hvip::VSSIP;
+        self
     }
-    /// Returns the virtual supervisor timer interrupt pending.
+    /// Adds the VS-level timer interrupt.
     #[inline]
-    pub fn vstip(&self) -> bool {
-        self.bits.get_bit(6)
+    pub const fn vsti(mut self) -> Self {
+        self.bits |= Hvip::VSTIP;
+        self
     }
-    /// Sets the virtual supervisor timer interrupt pending.
+    /// Adds the VS-level external interrupt.
     #[inline]
-    pub fn set_vstip(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
+    pub const fn vsei(mut self) -> Self {
+        self.bits |= Hvip::VSEIP;
+        self
     }
-    /// Returns the virtual supervisor external interrupt pending.
+    /// Adds AIA local interrupt `irq`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `irq` is within 13..XLEN, see [`irq_mask`].
     #[inline]
-    pub fn vseip(&self) -> bool {
-        self.bits.get_bit(10)
+    pub fn interrupt(mut self, irq: usize) -> Self {
+        self.bits |= irq_mask(irq);
+        self
     }
-    /// Sets the virtual supervisor external interrupt pending.
+    /// Returns the mask.
     #[inline]
-    pub fn set_vseip(&mut self, val: bool) {
-        self.bits.set_bit(10, val);
+    pub const fn bits(&self) -> usize {
+        self.bits
     }
 }
 
-read_csr_as!(Hvip, 0x645);
-write_csr!(0x645);
-set!(0x645);
-clear!(0x645);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-// bit ops
-set_clear_csr!(
-    /// Virtual supervisor software interrupt pending enable.
-    , set_vssip, clear_vssip, 1 << 2);
-set_clear_csr!(
-    /// Virtual supervisor timer interrupt pending enable.
-    , set_vstip, clear_vstip, 1 << 6);
-set_clear_csr!(
-    /// Virtual supervisor external interrupt pending enable.
-    , set_vseip, clear_vseip, 1 << 10);
+    #[test]
+    fn test_local_interrupts() {
+        let mut hvip = Hvip::from_bits(0);
+        hvip.set_interrupt(13);
+        hvip.set_interrupt(63);
+        assert_eq!(hvip.bits(), (1 << 13) | (1 << 63));
+        assert!(hvip.is_pending(13));
+        assert!(!hvip.is_pending(14));
 
-// enums
+        hvip.clear_interrupt(13);
+        assert_eq!(hvip.bits(), 1 << 63);
+        assert!(!hvip.vseip());
+    }
+
+    #[test]
+    fn test_lcofi_mask() {
+        // The guest sees the injected bit unshifted, as vsip.LCOFIP
+        assert_eq!(lcofi_mask(), hvien::Hvien::LCOFIE);
+        assert_eq!(lcofi_mask(), crate::register::vsip::Vsip::LCOFIP);
+    }
+
+    #[test]
+    fn test_mask_builder() {
+        assert_eq!(HvipMask::new().bits(), 0);
+        let mask = HvipMask::new().vssi().vsti().vsei();
+        assert_eq!(mask.bits(), (1 << 2) | (1 << 6) | (1 << 10));
+        let mask = HvipMask::new().vsei().interrupt(13);
+        assert_eq!(mask.bits(), (1 << 10) | (1 << 13));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_standard_interrupts_are_rejected() {
+        Hvip::from_bits(0).set_interrupt(10);
+    }
+}
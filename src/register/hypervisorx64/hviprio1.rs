@@ -0,0 +1,93 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Virtual Interrupt Priority Register 1 (AIA).
+//!
+//! `hviprio1` holds the priority numbers the guest observes for the supervisor
+//! software, timer and counter-overflow interrupts, one byte per interrupt
+//! identity (see
+//! [`Hviprio1::IIDS`](crate::register::hviprio1::Hviprio1::IIDS)). Lanes of
+//! reserved identities are read-only zero. A priority number of zero means
+//! default priority. On RV32 the upper four lanes live in `hviprio1h`.
+
+use crate::register::interrupts::{Priorities, lane_priority, set_lane_priority};
+
+crate::register! {
+    /// Hypervisor Virtual Interrupt Priority Register 1.
+    Hviprio1: 0x646 {
+        /// priority number of the supervisor software interrupt (1)
+        ssi, set_ssi, SSI: [8..16];
+        /// priority number of the supervisor timer interrupt (5)
+        sti, set_sti, STI: [24..32];
+        /// priority number of the local counter overflow interrupt (13)
+        lcofi, set_lcofi, LCOFI: [40..48];
+    }
+}
+
+impl Hviprio1 {
+    /// Interrupt identities of the priority byte lanes, lowest lane first.
+    pub const IIDS: [usize; 8] = [0, 1, 4, 5, 8, 13, 14, 15];
+
+    /// Returns the priority number of interrupt `iid`, or `None` if `iid`
+    /// has no lane in this register.
+    #[inline]
+    pub fn priority(&self, iid: usize) -> Option<u8> {
+        lane_priority(self.bits, &Self::IIDS, iid)
+    }
+    /// Sets the priority number of interrupt `iid`.
+    ///
+    /// Returns `false` and leaves the value unchanged if `iid` has no lane in
+    /// this register.
+    #[inline]
+    pub fn set_priority(&mut self, iid: usize, prio: u8) -> bool {
+        set_lane_priority(&mut self.bits, &Self::IIDS, iid, prio)
+    }
+    /// Builds the register value from the priority model of
+    /// [`interrupts`](crate::register::interrupts).
+    pub fn from_priorities(priorities: &Priorities) -> Self {
+        let mut reg = Self::from_bits(0);
+        for iid in Self::IIDS {
+            reg.set_priority(iid, priorities[iid]);
+        }
+        reg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hviprio1_lanes() {
+        let mut hviprio1 = Hviprio1::from_bits(0);
+        assert!(hviprio1.set_priority(1, 0x11));
+        assert!(hviprio1.set_priority(5, 0x55));
+        assert!(hviprio1.set_priority(13, 0xdd));
+        assert!(!hviprio1.set_priority(9, 1));
+        assert_eq!(hviprio1.bits(), 0xdd00_5500_1100);
+        assert_eq!(hviprio1.ssi(), 0x11);
+        assert_eq!(hviprio1.sti(), 0x55);
+        assert_eq!(hviprio1.lcofi(), 0xdd);
+        assert_eq!(hviprio1.priority(13), Some(0xdd));
+        assert_eq!(hviprio1.priority(9), None);
+    }
+
+    #[test]
+    fn test_hviprio1_from_priorities() {
+        let mut prio = crate::register::interrupts::DEFAULT_PRIORITIES;
+        prio[5] = 3;
+        prio[9] = 7;
+        assert_eq!(Hviprio1::from_priorities(&prio).bits(), 3 << 24);
+    }
+}
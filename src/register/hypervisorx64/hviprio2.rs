@@ -0,0 +1,73 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Virtual Interrupt Priority Register 2 (AIA).
+//!
+//! `hviprio2` holds the priority numbers the guest observes for the local
+//! interrupts 16–23, one byte per interrupt identity. A priority number of
+//! zero means default priority. On RV32 the upper four lanes live in
+//! `hviprio2h`.
+
+use crate::register::interrupts::{Priorities, lane_priority, set_lane_priority};
+
+crate::register! {
+    /// Hypervisor Virtual Interrupt Priority Register 2.
+    Hviprio2: 0x647 {
+    }
+}
+
+impl Hviprio2 {
+    /// Interrupt identities of the priority byte lanes, lowest lane first.
+    pub const IIDS: [usize; 8] = [16, 17, 18, 19, 20, 21, 22, 23];
+
+    /// Returns the priority number of interrupt `iid`, or `None` if `iid`
+    /// has no lane in this register.
+    #[inline]
+    pub fn priority(&self, iid: usize) -> Option<u8> {
+        lane_priority(self.bits, &Self::IIDS, iid)
+    }
+    /// Sets the priority number of interrupt `iid`.
+    ///
+    /// Returns `false` and leaves the value unchanged if `iid` has no lane in
+    /// this register.
+    #[inline]
+    pub fn set_priority(&mut self, iid: usize, prio: u8) -> bool {
+        set_lane_priority(&mut self.bits, &Self::IIDS, iid, prio)
+    }
+    /// Builds the register value from the priority model of
+    /// [`interrupts`](crate::register::interrupts).
+    pub fn from_priorities(priorities: &Priorities) -> Self {
+        let mut reg = Self::from_bits(0);
+        for iid in Self::IIDS {
+            reg.set_priority(iid, priorities[iid]);
+        }
+        reg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hviprio2_lanes() {
+        let mut hviprio2 = Hviprio2::from_bits(0);
+        assert!(hviprio2.set_priority(16, 1));
+        assert!(hviprio2.set_priority(23, 0x80));
+        assert!(!hviprio2.set_priority(24, 1));
+        assert_eq!(hviprio2.bits(), 0x8000_0000_0000_0001);
+        assert_eq!(hviprio2.priority(23), Some(0x80));
+        assert_eq!(hviprio2.priority(13), None);
+    }
+}
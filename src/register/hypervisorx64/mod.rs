@@ -17,10 +17,20 @@
 //! This module contains implementations of all hypervisor and virtual supervisor
 //! registers for 64-bit RISC-V systems with the hypervisor extension.
 
+/// Hypervisor context register (Sdtrig)
+pub mod hcontext;
 /// Hypervisor counter enable register
 pub mod hcounteren;
 /// Hypervisor exception delegation register  
 pub mod hedeleg;
+/// Hypervisor exception delegation high register (for RV32)
+#[cfg(target_arch = "riscv32")]
+pub mod hedelegh;
+/// Hypervisor environment configuration register
+pub mod henvcfg;
+/// Hypervisor environment configuration high register (for RV32)
+#[cfg(target_arch = "riscv32")]
+pub mod henvcfgh;
 /// Hypervisor guest address translation and protection register
 pub mod hgatp;
 /// Hypervisor guest external interrupt enable register
@@ -29,10 +39,21 @@ pub mod hgeie;
 pub mod hgeip;
 /// Hypervisor interrupt delegation register
 pub mod hideleg;
+/// Hypervisor interrupt delegation high register (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod hidelegh;
 /// Hypervisor interrupt enable register
 pub mod hie;
 /// Hypervisor interrupt pending register
 pub mod hip;
+/// Hypervisor state enable register 0 (Smstateen)
+pub mod hstateen0;
+/// Hypervisor state enable register 1 (Smstateen)
+pub mod hstateen1;
+/// Hypervisor state enable register 2 (Smstateen)
+pub mod hstateen2;
+/// Hypervisor state enable register 3 (Smstateen)
+pub mod hstateen3;
 /// Hypervisor status register
 pub mod hstatus;
 /// Hypervisor time delta register
@@ -43,8 +64,28 @@ pub mod htimedeltah;
 pub mod htinst;
 /// Hypervisor trap value register
 pub mod htval;
+/// Hypervisor virtual interrupt control register (AIA)
+pub mod hvictl;
+/// Hypervisor virtual interrupt enable register (AIA)
+pub mod hvien;
+/// Hypervisor virtual interrupt enable high register (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod hvienh;
 /// Hypervisor virtual interrupt pending register
 pub mod hvip;
+/// Hypervisor virtual interrupt pending high register (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod hviph;
+/// Hypervisor virtual interrupt priority register 1 (AIA)
+pub mod hviprio1;
+/// Hypervisor virtual interrupt priority high register 1 (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod hviprio1h;
+/// Hypervisor virtual interrupt priority register 2 (AIA)
+pub mod hviprio2;
+/// Hypervisor virtual interrupt priority high register 2 (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod hviprio2h;
 /// Virtual supervisor address translation and protection register
 pub mod vsatp;
 /// Virtual supervisor cause register
@@ -53,13 +94,45 @@ pub mod vscause;
 pub mod vsepc;
 /// Virtual supervisor interrupt enable register
 pub mod vsie;
+/// Virtual supervisor interrupt enable high register (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod vsieh;
 /// Virtual supervisor interrupt pending register
 pub mod vsip;
+/// Virtual supervisor interrupt pending high register (AIA, for RV32)
+#[cfg(all(target_arch = "riscv32", feature = "aia"))]
+pub mod vsiph;
+/// Virtual supervisor indirect register alias (Sscsrind)
+pub mod vsireg;
+/// Virtual supervisor indirect register select (Sscsrind)
+pub mod vsiselect;
 /// Virtual supervisor scratch register
 pub mod vsscratch;
 /// Virtual supervisor status register
 pub mod vsstatus;
+/// Virtual supervisor timer compare register (Sstc)
+pub mod vstimecmp;
+/// Virtual supervisor timer compare high register (Sstc, for RV32)
+#[cfg(target_arch = "riscv32")]
+pub mod vstimecmph;
+/// Virtual supervisor top external interrupt register (AIA)
+pub mod vstopei;
+/// Virtual supervisor top interrupt register (AIA)
+pub mod vstopi;
 /// Virtual supervisor trap value register
 pub mod vstval;
 /// Virtual supervisor trap vector register
 pub mod vstvec;
+
+/// Writes `value` to an RV32 CSR pair through its 32-bit halves.
+///
+/// The low half is first parked at `park_lo`, then the high half and finally
+/// the real low half are written. Parking at 0 keeps every intermediate value
+/// at or below the larger of the old and new one, parking at `u32::MAX` keeps
+/// it at or above the smaller one.
+#[cfg(any(target_arch = "riscv32", test))]
+fn split_write(value: u64, park_lo: u32, mut lo: impl FnMut(usize), mut hi: impl FnMut(usize)) {
+    lo(park_lo as usize);
+    hi((value >> 32) as u32 as usize);
+    lo(value as u32 as usize);
+}
@@ -13,77 +13,113 @@
 // limitations under the License.
 
 //! Virtual Supervisor Guest Address Translation and Protection Register.
+//!
+//! `vsatp` selects the VS-stage translation of the guest, with the same modes
+//! as `satp`: [`VsatpMode`](crate::register::vsatp::VsatpMode) lists them. The
+//! G-stage modes of `hgatp` are different, and the former reuse of their names
+//! here is deprecated.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, write_csr};
+use crate::asm::hfence_vvma_all;
+use crate::register::barrier::fence;
 
-/// Virtual Supervisor Address Translation and Protection Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Vsatp {
-    bits: usize,
+/// Largest ASIDLEN allowed by the architecture on RV64.
+pub const ASIDLEN_MAX: usize = 16;
+
+crate::register! {
+    /// Virtual Supervisor Address Translation and Protection Register.
+    Vsatp: 0x280 {
+        /// guest address translation mode
+        mode, set_mode, MODE: [60..64] as VsatpMode, Warl;
+        /// address space identifier
+        asid, set_asid, ASID: [44..60];
+        /// physical page number for root page table
+        ppn, set_ppn, PPN: [0..44];
+    }
 }
 
 impl Vsatp {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Vsatp { bits: x }
-    }
-    /// Writes the register value to the CSR.
+    /// Writes this value to `vsatp` and fences the VS-stage translations.
+    ///
+    /// The `hfence.vvma` orders preceding stores to the guest page tables
+    /// before the implicit reads of the new translation, and discards stale
+    /// translations cached for the current VMID.
     ///
     /// # Safety
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the guest address translation mode.
-    #[inline]
-    pub fn mode(&self) -> HgatpValues {
-        HgatpValues::from(self.bits.get_bits(60..64))
-    }
-    /// Sets the guest address translation mode.
+    /// Same as [`Vsatp::write`]; `hgatp` must hold the guest's VMID.
     #[inline]
-    pub fn set_mode(&mut self, val: HgatpValues) {
-        self.bits.set_bits(60..64, val as usize);
-    }
-    /// Returns the address space identifier.
-    #[inline]
-    pub fn asid(&self) -> usize {
-        self.bits.get_bits(44..60)
-    }
-    /// Sets the address space identifier.
-    #[inline]
-    pub fn set_asid(&mut self, val: usize) {
-        self.bits.set_bits(44..60, val);
-    }
-    /// Returns the physical page number for root page table.
-    #[inline]
-    pub fn ppn(&self) -> usize {
-        self.bits.get_bits(0..44)
-    }
-    /// Sets the physical page number for root page table.
-    #[inline]
-    pub fn set_ppn(&mut self, val: usize) {
-        self.bits.set_bits(0..44, val);
+    pub unsafe fn write_ordered(&self) {
+        // SAFETY: Caller ensures the translation is valid
+        unsafe {
+            self.write();
+            hfence_vvma_all();
+        }
     }
 }
 
-read_csr_as!(Vsatp, 0x280);
-write_csr!(0x280);
-set!(0x280);
-clear!(0x280);
+/// Reads `vsatp` after all preceding memory accesses have been performed.
+#[inline]
+pub fn read_ordered() -> Vsatp {
+    // SAFETY: A fence has no side effects besides ordering
+    unsafe { fence() };
+    read()
+}
+
+/// Returns the number of ASID bits implemented for VS-stage translation
+/// (ASIDLEN).
+///
+/// Writes all-ones to the ASID field and counts the bits that read back,
+/// then restores the original `vsatp`. A hypervisor virtualizing ASIDs can
+/// pass the guest-visible width through or remap wider guest ASIDs. The
+/// probe keeps the current mode, and some implementations only retain the
+/// ASID while a paged mode is selected.
+///
+/// # Safety
+///
+/// No guest may be running on this hart, since its VS-stage translation
+/// briefly uses a different ASID.
+pub unsafe fn asid_len() -> usize {
+    let saved = read();
+    let mut probe = saved;
+    probe.set_asid((1 << ASIDLEN_MAX) - 1);
+    // SAFETY: Caller ensures no guest is using vsatp
+    unsafe { probe.write() };
+    let asid = read().asid();
+    // SAFETY: Restores the original value
+    unsafe { saved.write() };
+    asid.count_ones() as usize
+}
+
 // bit ops
 
+/// VS-stage address translation modes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum VsatpMode {
+    /// No translation
+    Bare = 0,
+    /// Page-based 39-bit virtual addressing
+    Sv39 = 8,
+    /// Page-based 48-bit virtual addressing
+    Sv48 = 9,
+    /// Page-based 57-bit virtual addressing
+    Sv57 = 10,
+}
+
+impl VsatpMode {
+    fn from(x: usize) -> Self {
+        match x {
+            0 => Self::Bare,
+            8 => Self::Sv39,
+            9 => Self::Sv48,
+            10 => Self::Sv57,
+            _ => unreachable!(),
+        }
+    }
+}
+
 /// Hypervisor Guest Address Translation and Protection Register values.
+#[deprecated(note = "vsatp does not use the G-stage modes, use `VsatpMode` instead")]
 #[derive(Copy, Clone, Debug)]
 #[repr(usize)]
 pub enum HgatpValues {
@@ -95,13 +131,35 @@ pub enum HgatpValues {
     Sv48x4 = 9,
 }
 
-impl HgatpValues {
-    fn from(x: usize) -> Self {
-        match x {
-            0 => Self::Bare,
-            8 => Self::Sv39x4,
-            9 => Self::Sv48x4,
-            _ => unreachable!(),
+#[allow(deprecated)]
+impl From<HgatpValues> for VsatpMode {
+    fn from(mode: HgatpValues) -> Self {
+        match mode {
+            HgatpValues::Bare => Self::Bare,
+            HgatpValues::Sv39x4 => Self::Sv39,
+            HgatpValues::Sv48x4 => Self::Sv48,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vsatp_sv57() {
+        let mut vsatp = Vsatp::from_bits(0);
+        vsatp.set_mode(VsatpMode::Sv57);
+        assert_eq!(vsatp.mode(), VsatpMode::Sv57);
+        assert_eq!(vsatp.bits(), 10 << 60);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_from_hgatp_values() {
+        let mode: VsatpMode = HgatpValues::Sv39x4.into();
+        assert_eq!(mode, VsatpMode::Sv39);
+        let mode: VsatpMode = HgatpValues::Sv48x4.into();
+        assert_eq!(mode, VsatpMode::Sv48);
+    }
+}
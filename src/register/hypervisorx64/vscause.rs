@@ -13,68 +13,276 @@
 // limitations under the License.
 
 //! Virtual Supervisor Cause Register.
+//!
+//! [`Vscause::cause`](crate::register::vscause::Vscause::cause) decodes the
+//! register into a [`Trap`](crate::register::vscause::Trap) of the
+//! [`Interrupt`](crate::register::vscause::Interrupt) and
+//! [`Exception`](crate::register::vscause::Exception) causes below, like
+//! `scause::cause` does for `scause`. Besides the standard supervisor causes,
+//! the enums include those added by the hypervisor extension, which a nested
+//! hypervisor running in VS-mode may observe.
+//!
+//! To inject a trap,
+//! [`Vscause::from_exception`](crate::register::vscause::Vscause::from_exception)
+//! and
+//! [`Vscause::from_interrupt`](crate::register::vscause::Vscause::from_interrupt)
+//! build a value and reject the causes a guest can never receive: the
+//! hypervisor-only causes are not delegable to VS-mode, and virtual supervisor
+//! interrupts are reported to the guest as the corresponding supervisor
+//! interrupts.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+pub use riscv::interrupt::{Trap, TrapError};
+use riscv::set_clear_csr;
 
-/// Virtual Supervisor Cause Register
-#[derive(Copy, Clone, Debug)]
-pub struct Vscause {
-    bits: usize,
+crate::register! {
+    /// Virtual Supervisor Cause Register
+    Vscause: 0x242 {
+        /// interrupt cause status
+        interrupt, set_interrupt, INTERRUPT: [63];
+        /// exception code
+        code, set_code, CODE: [0..63];
+    }
 }
 
 impl Vscause {
-    /// Returns the raw bits of the register.
+    /// Creates the value reporting exception `e` to the guest.
+    ///
+    /// Fails for causes that are only ever taken by the hypervisor.
     #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
+    pub fn from_exception(e: Exception) -> Result<Self, TrapError> {
+        match e {
+            Exception::VirtualSupervisorEnvCall
+            | Exception::InstructionGuestPageFault
+            | Exception::LoadGuestPageFault
+            | Exception::VirtualInstruction
+            | Exception::StoreGuestPageFault => Err(TrapError::InvalidException(e.number())),
+            _ => Ok(Vscause { bits: e.number() }),
+        }
     }
-    /// Creates a register value from raw bits.
+    /// Creates the value reporting interrupt `i` to the guest.
+    ///
+    /// Fails for the virtual supervisor and guest external interrupts, which
+    /// the guest sees as the supervisor interrupts instead.
     #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Vscause { bits: x }
+    pub fn from_interrupt(i: Interrupt) -> Result<Self, TrapError> {
+        match i {
+            Interrupt::SupervisorSoft
+            | Interrupt::SupervisorTimer
+            | Interrupt::SupervisorExternal
+            | Interrupt::CounterOverflow => Ok(Vscause {
+                bits: Self::INTERRUPT | i.number(),
+            }),
+            _ => Err(TrapError::InvalidInterrupt(i.number())),
+        }
     }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
+    /// Returns the decoded trap cause.
     ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
+    /// Fails with the raw code if it does not name a known cause.
     #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
+    pub fn cause(&self) -> Result<Trap<Interrupt, Exception>, TrapError> {
+        let code = self.code();
+        if self.interrupt() {
+            Interrupt::from_number(code)
+                .map(Trap::Interrupt)
+                .ok_or(TrapError::InvalidInterrupt(code))
+        } else {
+            Exception::from_number(code)
+                .map(Trap::Exception)
+                .ok_or(TrapError::InvalidException(code))
+        }
     }
-    /// Returns the interrupt cause status.
+}
+
+/// Interrupt causes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum Interrupt {
+    /// Supervisor software interrupt.
+    SupervisorSoft = 1,
+    /// Virtual supervisor software interrupt.
+    VirtualSupervisorSoft = 2,
+    /// Supervisor timer interrupt.
+    SupervisorTimer = 5,
+    /// Virtual supervisor timer interrupt.
+    VirtualSupervisorTimer = 6,
+    /// Supervisor external interrupt.
+    SupervisorExternal = 9,
+    /// Virtual supervisor external interrupt.
+    VirtualSupervisorExternal = 10,
+    /// Supervisor guest external interrupt.
+    SupervisorGuestExternal = 12,
+    /// Local counter overflow interrupt.
+    CounterOverflow = 13,
+}
+
+impl Interrupt {
+    /// Returns the interrupt with code `n`, if it is known.
     #[inline]
-    pub fn interrupt(&self) -> bool {
-        self.bits.get_bit(63)
+    pub fn from_number(n: usize) -> Option<Self> {
+        Some(match n {
+            1 => Self::SupervisorSoft,
+            2 => Self::VirtualSupervisorSoft,
+            5 => Self::SupervisorTimer,
+            6 => Self::VirtualSupervisorTimer,
+            9 => Self::SupervisorExternal,
+            10 => Self::VirtualSupervisorExternal,
+            12 => Self::SupervisorGuestExternal,
+            13 => Self::CounterOverflow,
+            _ => return None,
+        })
     }
-    /// Sets the interrupt cause status.
+    /// Returns the interrupt code.
     #[inline]
-    pub fn set_interrupt(&mut self, val: bool) {
-        self.bits.set_bit(63, val);
+    pub fn number(self) -> usize {
+        self as usize
     }
-    /// Returns the exception code.
+}
+
+/// Exception causes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum Exception {
+    /// Instruction address misaligned.
+    InstructionMisaligned = 0,
+    /// Instruction access fault.
+    InstructionFault = 1,
+    /// Illegal instruction.
+    IllegalInstruction = 2,
+    /// Breakpoint.
+    Breakpoint = 3,
+    /// Load address misaligned.
+    LoadMisaligned = 4,
+    /// Load access fault.
+    LoadFault = 5,
+    /// Store/AMO address misaligned.
+    StoreMisaligned = 6,
+    /// Store/AMO access fault.
+    StoreFault = 7,
+    /// Environment call from U-mode or VU-mode.
+    UserEnvCall = 8,
+    /// Environment call from HS-mode.
+    SupervisorEnvCall = 9,
+    /// Environment call from VS-mode.
+    VirtualSupervisorEnvCall = 10,
+    /// Instruction page fault.
+    InstructionPageFault = 12,
+    /// Load page fault.
+    LoadPageFault = 13,
+    /// Store/AMO page fault.
+    StorePageFault = 15,
+    /// Instruction guest-page fault.
+    InstructionGuestPageFault = 20,
+    /// Load guest-page fault.
+    LoadGuestPageFault = 21,
+    /// Virtual instruction.
+    VirtualInstruction = 22,
+    /// Store/AMO guest-page fault.
+    StoreGuestPageFault = 23,
+}
+
+impl Exception {
+    /// Returns the exception with code `n`, if it is known.
     #[inline]
-    pub fn code(&self) -> usize {
-        self.bits.get_bits(0..63)
+    pub fn from_number(n: usize) -> Option<Self> {
+        Some(match n {
+            0 => Self::InstructionMisaligned,
+            1 => Self::InstructionFault,
+            2 => Self::IllegalInstruction,
+            3 => Self::Breakpoint,
+            4 => Self::LoadMisaligned,
+            5 => Self::LoadFault,
+            6 => Self::StoreMisaligned,
+            7 => Self::StoreFault,
+            8 => Self::UserEnvCall,
+            9 => Self::SupervisorEnvCall,
+            10 => Self::VirtualSupervisorEnvCall,
+            12 => Self::InstructionPageFault,
+            13 => Self::LoadPageFault,
+            15 => Self::StorePageFault,
+            20 => Self::InstructionGuestPageFault,
+            21 => Self::LoadGuestPageFault,
+            22 => Self::VirtualInstruction,
+            23 => Self::StoreGuestPageFault,
+            _ => return None,
+        })
     }
-    /// Sets the exception code.
+    /// Returns the exception code.
     #[inline]
-    pub fn set_code(&mut self, val: usize) {
-        self.bits.set_bits(0..63, val);
+    pub fn number(self) -> usize {
+        self as usize
     }
 }
 
-read_csr_as!(Vscause, 0x242);
-write_csr!(0x242);
-set!(0x242);
-clear!(0x242);
-
 // bit ops
 set_clear_csr!(
     /// Interrupt cause enable.
     , set_interrupt, clear_interrupt, 1 << 63);
 
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cause() {
+        let vscause = Vscause::from_bits(13);
+        assert_eq!(
+            vscause.cause(),
+            Ok(Trap::Exception(Exception::LoadPageFault))
+        );
+        let vscause = Vscause::from_bits(22);
+        assert_eq!(
+            vscause.cause(),
+            Ok(Trap::Exception(Exception::VirtualInstruction))
+        );
+        let vscause = Vscause::from_bits((1 << 63) | 5);
+        assert_eq!(
+            vscause.cause(),
+            Ok(Trap::Interrupt(Interrupt::SupervisorTimer))
+        );
+    }
+
+    #[test]
+    fn test_unknown_cause() {
+        assert_eq!(
+            Vscause::from_bits(11).cause(),
+            Err(TrapError::InvalidException(11))
+        );
+        assert_eq!(
+            Vscause::from_bits((1 << 63) | 3).cause(),
+            Err(TrapError::InvalidInterrupt(3))
+        );
+    }
+
+    #[test]
+    fn test_from_cause() {
+        let vscause = Vscause::from_exception(Exception::Breakpoint).unwrap();
+        assert_eq!(vscause.bits(), 3);
+        let vscause = Vscause::from_interrupt(Interrupt::SupervisorExternal).unwrap();
+        assert_eq!(
+            vscause.cause(),
+            Ok(Trap::Interrupt(Interrupt::SupervisorExternal))
+        );
+        assert_eq!(
+            Vscause::from_exception(Exception::LoadGuestPageFault).unwrap_err(),
+            TrapError::InvalidException(21)
+        );
+        assert_eq!(
+            Vscause::from_interrupt(Interrupt::VirtualSupervisorTimer).unwrap_err(),
+            TrapError::InvalidInterrupt(6)
+        );
+    }
+
+    #[test]
+    fn test_numbers_round_trip() {
+        for n in 0..64 {
+            if let Some(e) = Exception::from_number(n) {
+                assert_eq!(e.number(), n);
+            }
+            if let Some(i) = Interrupt::from_number(n) {
+                assert_eq!(i.number(), n);
+            }
+        }
+    }
+}
@@ -11,9 +11,100 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
-
 //! Virtual Supervisor Exception Program Counter.
+//!
+//! `vsepc` must hold an address aligned to IALIGN: bit 0 is always zero, and
+//! bit 1 is masked on reads while compressed instructions are disabled.
+//! [`Vsepc::write`](crate::register::vsepc::Vsepc::write) legalizes the value
+//! for the given IALIGN instead of leaving it to the hardware, and
+//! [`Vsepc::advance`](crate::register::vsepc::Vsepc::advance) steps over the
+//! instruction at the exception address.
+
+/// Instruction address alignment of the guest.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Ialign {
+    /// 16-bit alignment, with the C extension enabled.
+    Bits16,
+    /// 32-bit alignment, without the C extension.
+    Bits32,
+}
+
+impl Ialign {
+    /// Returns the mask of the address bits that must be zero.
+    #[inline]
+    pub fn mask(self) -> usize {
+        match self {
+            Ialign::Bits16 => 0b1,
+            Ialign::Bits32 => 0b11,
+        }
+    }
+}
+
+/// Virtual Supervisor Exception Program Counter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Vsepc {
+    bits: usize,
+}
+
+impl Vsepc {
+    /// Returns the raw bits of the register.
+    #[inline]
+    pub fn bits(&self) -> usize {
+        self.bits
+    }
+    /// Creates a register value from raw bits.
+    #[inline]
+    pub fn from_bits(x: usize) -> Self {
+        Vsepc { bits: x }
+    }
+    /// Returns the value with the bits below `ialign` cleared.
+    #[inline]
+    pub fn legalize(&self, ialign: Ialign) -> Self {
+        Vsepc {
+            bits: self.bits & !ialign.mask(),
+        }
+    }
+    /// Moves the address past an instruction of `len` bytes.
+    #[inline]
+    pub fn advance(&mut self, len: usize) {
+        self.bits = self.bits.wrapping_add(len);
+    }
+    /// Writes the value, legalized for `ialign`, to the CSR.
+    ///
+    /// # Safety
+    ///
+    /// This function is unsafe because it changes where the guest resumes
+    /// after `sret`.
+    #[inline]
+    pub unsafe fn write(&self, ialign: Ialign) {
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe { _write(self.legalize(ialign).bits) };
+    }
+}
+
+riscv::read_csr_as!(Vsepc, 0x241);
+riscv::write_csr!(0x241);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legalize() {
+        let vsepc = Vsepc::from_bits(0x8020_0003);
+        assert_eq!(vsepc.legalize(Ialign::Bits16).bits(), 0x8020_0002);
+        assert_eq!(vsepc.legalize(Ialign::Bits32).bits(), 0x8020_0000);
+    }
 
-use riscv::{read_csr_as_usize, write_csr_as_usize};
-read_csr_as_usize!(0x241);
-write_csr_as_usize!(0x241);
+    #[test]
+    fn test_advance() {
+        let mut vsepc = Vsepc::from_bits(0x8020_0000);
+        vsepc.advance(2);
+        assert_eq!(vsepc.bits(), 0x8020_0002);
+        vsepc.advance(4);
+        assert_eq!(vsepc.bits(), 0x8020_0006);
+        let mut vsepc = Vsepc::from_bits(usize::MAX - 1);
+        vsepc.advance(4);
+        assert_eq!(vsepc.bits(), 2);
+    }
+}
@@ -13,74 +13,44 @@
 // limitations under the License.
 
 //! Virtual Supevisor Interrupt Enable Register.
+//!
+//! The guest sees the VS-level interrupts delegated by `hideleg` one bit
+//! position lower than in `hie`, e.g. VSTIE (bit 6) as STIE (bit 5). A
+//! delegated local counter-overflow interrupt keeps its position, bit 13.
+//! [`guest_view`](crate::register::vsie::guest_view) computes that view for
+//! emulating guest `sie` accesses.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::{hideleg, hie};
+use riscv::set_clear_csr;
 
-/// Virtual Supervisor Interrupt Enable Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Vsie {
-    bits: usize,
+crate::register! {
+    /// Virtual Supervisor Interrupt Enable Register.
+    Vsie: 0x204 {
+        /// supervisor software interrupt enable
+        ssie, set_ssie, SSIE: [1];
+        /// supervisor timer interrupt enable
+        stie, set_stie, STIE: [5];
+        /// supervisor external interrupt enable
+        seie, set_seie, SEIE: [9];
+        /// local counter overflow interrupt enable
+        lcofie, set_lcofie, LCOFIE: [13];
+    }
 }
 
-impl Vsie {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Vsie { bits: x }
-    }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
-    ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the supervisor software interrupt enable.
-    #[inline]
-    pub fn ssie(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-    /// Sets the supervisor software interrupt enable.
-    #[inline]
-    pub fn set_ssie(&mut self, val: bool) {
-        self.bits.set_bit(1, val);
-    }
-    /// Returns the supervisor timer interrupt enable.
-    #[inline]
-    pub fn stie(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-    /// Sets the supervisor timer interrupt enable.
-    #[inline]
-    pub fn set_stie(&mut self, val: bool) {
-        self.bits.set_bit(5, val);
-    }
-    /// Returns the supervisor external interrupt enable.
-    #[inline]
-    pub fn seie(&self) -> bool {
-        self.bits.get_bit(9)
-    }
-    /// Sets the supervisor external interrupt enable.
-    #[inline]
-    pub fn set_seie(&mut self, val: bool) {
-        self.bits.set_bit(9, val);
-    }
+/// Returns the guest's view of the `hie` bits delegated by `hideleg`.
+#[inline]
+pub fn compute_guest_view(hie: hie::Hie, hideleg: hideleg::Hideleg) -> Vsie {
+    let delegated = hideleg.bits() & hideleg::Hideleg::ALL_VS_INTERRUPTS;
+    let lcofi = hie.bits() & hideleg.bits() & Vsie::LCOFIE;
+    Vsie::from_bits(((hie.bits() & delegated) >> 1) | lcofi)
+}
+
+/// Reads `hie` and returns the guest's view of the bits delegated by `hideleg`.
+#[inline]
+pub fn guest_view(hideleg: hideleg::Hideleg) -> Vsie {
+    compute_guest_view(hie::read(), hideleg)
 }
 
-read_csr_as!(Vsie, 0x204);
-write_csr!(0x204);
-set!(0x204);
-clear!(0x204);
 // bit ops
 set_clear_csr!(
     /// Supervisor software interrupt enable.
@@ -91,5 +61,36 @@ set_clear_csr!(
 set_clear_csr!(
     /// Supervisor external interrupt enable.
     , set_seie, clear_seie, 1 << 9);
+set_clear_csr!(
+    /// Local counter overflow interrupt enable.
+    , set_lcofie, clear_lcofie, 1 << 13);
 
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_view_lcofi() {
+        let hie = hie::Hie::from_bits((1 << 10) | Vsie::LCOFIE);
+        let all = hideleg::Hideleg::all_vs_interrupts();
+        let vsie = compute_guest_view(hie, all);
+        assert!(vsie.seie() && !vsie.lcofie());
+
+        let hideleg = hideleg::Hideleg::from_bits(all.bits() | Vsie::LCOFIE);
+        let vsie = compute_guest_view(hie, hideleg);
+        assert_eq!(vsie.bits(), (1 << 9) | (1 << 13));
+        assert!(vsie.lcofie());
+    }
+
+    #[test]
+    fn test_lcofie_field() {
+        let mut vsie = Vsie::from_bits(0);
+        vsie.set_lcofie(true);
+        assert_eq!(vsie.bits(), 1 << 13);
+        assert!(vsie.lcofie() && !vsie.ssie());
+        vsie.set_lcofie(false);
+        assert_eq!(vsie.bits(), 0);
+    }
+}
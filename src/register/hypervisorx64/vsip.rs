@@ -13,74 +13,44 @@
 // limitations under the License.
 
 //! Virtual Supevisor Interrupt Pending Register.
+//!
+//! The guest sees the VS-level interrupts delegated by `hideleg` one bit
+//! position lower than in `hip`, e.g. VSTIP (bit 6) as STIP (bit 5). A
+//! delegated local counter-overflow interrupt keeps its position, bit 13.
+//! [`guest_view`](crate::register::vsip::guest_view) computes that view for
+//! emulating guest `sip` accesses.
 
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
+use crate::register::{hideleg, hip};
+use riscv::set_clear_csr;
 
-/// Virtual Supervisor Interrupt Pending Register.
-#[derive(Copy, Clone, Debug)]
-pub struct Vsip {
-    bits: usize,
+crate::register! {
+    /// Virtual Supervisor Interrupt Pending Register.
+    Vsip: 0x244 {
+        /// supervisor software interrupt pending
+        ssip, set_ssip, SSIP: [1];
+        /// supervisor timer interrupt pending
+        stip, set_stip, STIP: [5];
+        /// supervisor external interrupt pending
+        seip, set_seip, SEIP: [9];
+        /// local counter overflow interrupt pending
+        lcofip, set_lcofip, LCOFIP: [13];
+    }
 }
 
-impl Vsip {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Vsip { bits: x }
-    }
-    /// Writes the register value to the CSR.
-    ///
-    /// # Safety
-    ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the supervisor software interrupt pending.
-    #[inline]
-    pub fn ssip(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-    /// Sets the supervisor software interrupt pending.
-    #[inline]
-    pub fn set_ssip(&mut self, val: bool) {
-        self.bits.set_bit(1, val);
-    }
-    /// Returns the supervisor timer interrupt pending.
-    #[inline]
-    pub fn stip(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-    /// Sets the supervisor timer interrupt pending.
-    #[inline]
-    pub fn set_stip(&mut self, val: bool) {
-        self.bits.set_bit(5, val);
-    }
-    /// Returns the supervisor external interrupt pending.
-    #[inline]
-    pub fn seip(&self) -> bool {
-        self.bits.get_bit(9)
-    }
-    /// Sets the supervisor external interrupt pending.
-    #[inline]
-    pub fn set_seip(&mut self, val: bool) {
-        self.bits.set_bit(9, val);
-    }
+/// Returns the guest's view of the `hip` bits delegated by `hideleg`.
+#[inline]
+pub fn compute_guest_view(hip: hip::Hip, hideleg: hideleg::Hideleg) -> Vsip {
+    let delegated = hideleg.bits() & hideleg::Hideleg::ALL_VS_INTERRUPTS;
+    let lcofi = hip.bits() & hideleg.bits() & Vsip::LCOFIP;
+    Vsip::from_bits(((hip.bits() & delegated) >> 1) | lcofi)
+}
+
+/// Reads `hip` and returns the guest's view of the bits delegated by `hideleg`.
+#[inline]
+pub fn guest_view(hideleg: hideleg::Hideleg) -> Vsip {
+    compute_guest_view(hip::read(), hideleg)
 }
 
-read_csr_as!(Vsip, 0x244);
-write_csr!(0x244);
-set!(0x244);
-clear!(0x244);
 // bit ops
 set_clear_csr!(
     /// Supervisor software interrupt pending enable.
@@ -91,5 +61,49 @@ set_clear_csr!(
 set_clear_csr!(
     /// Supervisor external interrupt pending enable.
     , set_seip, clear_seip, 1 << 9);
+set_clear_csr!(
+    /// Local counter overflow interrupt pending enable.
+    , set_lcofip, clear_lcofip, 1 << 13);
 
 // enums
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_view() {
+        let hip = hip::Hip::from_bits((1 << 2) | (1 << 6) | (1 << 10) | (1 << 12));
+        let mut hideleg = hideleg::Hideleg::from_bits(1 << 13);
+        hideleg.set_vstip(true);
+        hideleg.set_vseip(true);
+        let vsip = compute_guest_view(hip, hideleg);
+        assert_eq!(vsip.bits(), (1 << 5) | (1 << 9));
+        assert!(vsip.stip() && vsip.seip() && !vsip.ssip());
+        let all = hideleg::Hideleg::all_vs_interrupts();
+        assert_eq!(compute_guest_view(hip, all).bits(), 0x222);
+    }
+
+    #[test]
+    fn test_guest_view_lcofi() {
+        let hip = hip::Hip::from_bits((1 << 6) | Vsip::LCOFIP);
+        let all = hideleg::Hideleg::all_vs_interrupts();
+        let vsip = compute_guest_view(hip, all);
+        assert!(vsip.stip() && !vsip.lcofip());
+
+        let hideleg = hideleg::Hideleg::from_bits(all.bits() | Vsip::LCOFIP);
+        let vsip = compute_guest_view(hip, hideleg);
+        assert_eq!(vsip.bits(), (1 << 5) | (1 << 13));
+        assert!(vsip.lcofip());
+    }
+
+    #[test]
+    fn test_lcofip_field() {
+        let mut vsip = Vsip::from_bits(0);
+        vsip.set_lcofip(true);
+        assert_eq!(vsip.bits(), 1 << 13);
+        assert!(vsip.lcofip() && !vsip.seip());
+        vsip.set_lcofip(false);
+        assert_eq!(vsip.bits(), 0);
+    }
+}
@@ -0,0 +1,120 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual Supervisor Indirect Register Alias (Sscsrind).
+//!
+//! `vsireg` accesses the register selected by `vsiselect`. A trap taken
+//! between writing `vsiselect` and accessing `vsireg` may change the
+//! selection, so the helpers here perform the whole sequence in a single
+//! instruction sequence with `sstatus.SIE` cleared and restore it afterwards.
+//!
+//! ```no_run
+//! use riscv_h::register::{vsireg, vsiselect};
+//!
+//! // Enable identity 5 in the guest interrupt file selected by VGEIN
+//! let (sel, bit) = vsiselect::eie_location(5);
+//! unsafe { vsireg::set_bits_indirect(sel, 1 << bit) };
+//! ```
+
+use riscv::{read_csr_as_usize, write_csr_as_usize};
+
+read_csr_as_usize!(0x251);
+write_csr_as_usize!(0x251);
+
+macro_rules! indirect_op {
+    ($(#[$doc:meta])* $name:ident, $op:literal, $value:ident) => {
+        $(#[$doc])*
+        ///
+        /// # Safety
+        ///
+        /// Clobbers `vsiselect`. `hstatus.VGEIN` must select a guest interrupt
+        /// file that belongs to the current guest, and `sel` must be a
+        /// register it implements.
+        #[cfg_attr(
+            not(any(target_arch = "riscv32", target_arch = "riscv64")),
+            allow(unused_variables)
+        )]
+        #[inline]
+        pub unsafe fn $name(sel: usize, $value: usize) {
+            #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+            // SAFETY: Caller ensures this is safe to execute
+            unsafe {
+                core::arch::asm!(
+                    "csrrci {sie}, sstatus, 2",
+                    "csrw 0x250, {sel}",
+                    concat!($op, " 0x251, {value}"),
+                    "andi {sie}, {sie}, 2",
+                    "csrs sstatus, {sie}",
+                    sel = in(reg) sel,
+                    value = in(reg) $value,
+                    sie = out(reg) _,
+                )
+            };
+            #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+            crate::unsupported_arch();
+        }
+    };
+}
+
+/// Reads the guest interrupt file register selected by `sel`.
+///
+/// # Safety
+///
+/// Clobbers `vsiselect`. `hstatus.VGEIN` must select a guest interrupt file
+/// that belongs to the current guest, and `sel` must be a register it
+/// implements.
+#[cfg_attr(
+    not(any(target_arch = "riscv32", target_arch = "riscv64")),
+    allow(unused_variables)
+)]
+#[inline]
+pub unsafe fn read_indirect(sel: usize) -> usize {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let value: usize;
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe {
+            core::arch::asm!(
+                "csrrci {sie}, sstatus, 2",
+                "csrw 0x250, {sel}",
+                "csrr {value}, 0x251",
+                "andi {sie}, {sie}, 2",
+                "csrs sstatus, {sie}",
+                sel = in(reg) sel,
+                value = out(reg) value,
+                sie = out(reg) _,
+            )
+        };
+        value
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+indirect_op!(
+    /// Writes `value` to the guest interrupt file register selected by `sel`.
+    write_indirect, "csrw", value
+);
+
+indirect_op!(
+    /// Sets the bits in `mask` in the guest interrupt file register selected
+    /// by `sel`, e.g. to enable identities in an `eieX` register.
+    set_bits_indirect, "csrs", mask
+);
+
+indirect_op!(
+    /// Clears the bits in `mask` in the guest interrupt file register
+    /// selected by `sel`.
+    clear_bits_indirect, "csrc", mask
+);
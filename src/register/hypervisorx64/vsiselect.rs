@@ -0,0 +1,72 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual Supervisor Indirect Register Select (Sscsrind).
+//!
+//! `vsiselect` chooses which register of the guest interrupt file selected by
+//! `hstatus.VGEIN` is accessed through `vsireg`. The selector numbers below are
+//! the IMSIC ones from the AIA specification. Accessing `vsiselect` and
+//! `vsireg` separately is racy; use the helpers in
+//! [`vsireg`](crate::register::vsireg) instead.
+
+use riscv::{read_csr_as_usize, write_csr_as_usize};
+
+read_csr_as_usize!(0x250);
+write_csr_as_usize!(0x250);
+
+/// Selector of the IMSIC `eidelivery` register.
+pub const EIDELIVERY: usize = 0x70;
+/// Selector of the IMSIC `eithreshold` register.
+pub const EITHRESHOLD: usize = 0x72;
+/// Selector of the first IMSIC interrupt-pending register, `eip0`.
+pub const EIP0: usize = 0x80;
+/// Selector of the first IMSIC interrupt-enable register, `eie0`.
+pub const EIE0: usize = 0xC0;
+
+/// Returns the selector and bit of interrupt identity `id` in the register
+/// array starting at `base` (`EIP0` or `EIE0`).
+///
+/// Each array register holds XLEN identities, and on RV64 only the even
+/// numbered registers exist, so the selector advances by `XLEN / 32`.
+#[inline]
+pub fn array_location(base: usize, id: usize) -> (usize, usize) {
+    let xlen = usize::BITS as usize;
+    (base + id / xlen * (xlen / 32), id % xlen)
+}
+
+/// Returns the `eipX` selector and bit of interrupt identity `id`.
+#[inline]
+pub fn eip_location(id: usize) -> (usize, usize) {
+    array_location(EIP0, id)
+}
+
+/// Returns the `eieX` selector and bit of interrupt identity `id`.
+#[inline]
+pub fn eie_location(id: usize) -> (usize, usize) {
+    array_location(EIE0, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_array_location() {
+        assert_eq!(eie_location(1), (EIE0, 1));
+        assert_eq!(eip_location(63), (EIP0, 63));
+        // The next identity skips the reserved odd register on RV64
+        assert_eq!(eie_location(64), (EIE0 + 2, 0));
+        assert_eq!(eie_location(2047), (0xFE, 63));
+    }
+}
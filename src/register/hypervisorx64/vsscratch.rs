@@ -13,8 +13,23 @@
 // limitations under the License.
 
 //! Virtual Supervisor Scratch Register.
+//!
+//! Besides plain reads and writes, [`swap`](crate::register::vsscratch::swap)
+//! exchanges the register in one `csrrw`, as trap entry and exit code needs
+//! when it has no free register to stage the value in.
 
 use riscv::{read_csr_as_usize, write_csr_as_usize};
 
 read_csr_as_usize!(0x240);
 write_csr_as_usize!(0x240);
+
+/// Writes `value` to `vsscratch` and returns the previous value.
+///
+/// # Safety
+///
+/// This function is unsafe because it replaces the guest's scratch value.
+#[inline]
+pub unsafe fn swap(value: usize) -> usize {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { crate::csr_swap!(0x240, value) }
+}
@@ -16,146 +16,66 @@
 //!
 //! The `vsstatus` register contains status and control fields for the virtual supervisor mode.
 //! This register controls various aspects of virtual machine execution including privilege levels,
-//! memory management, and floating-point state.
-
-use bit_field::BitField;
-use riscv::{clear, read_csr_as, set, set_clear_csr, write_csr};
-
-/// Virtual Supervisor Status Register
-#[derive(Copy, Clone, Debug)]
-pub struct Vsstatus {
-    bits: usize,
+//! memory management, and floating-point and vector state.
+//!
+//! The read-only SD bit is derived by the hardware from FS, XS and VS: it is
+//! set whenever any of them is
+//! [`Dirty`](crate::register::vsstatus::ExtensionState::Dirty), which lets a
+//! context switch skip all extension state with a single check.
+
+use riscv::set_clear_csr;
+
+crate::register! {
+    /// Virtual Supervisor Status Register
+    Vsstatus: 0x200 {
+        /// some of FS, XS or VS is dirty
+        sd, set_sd, SD: [63], ReadOnly;
+        /// effective user XLEN setting
+        uxl, set_uxl, UXL: [32..34] as UxlValues, Warl;
+        /// supervisor disable-trap (Ssdbltrp)
+        sdt, set_sdt, SDT: [24];
+        /// supervisor previous expected landing pad state (Zicfilp)
+        spelp, set_spelp, SPELP: [23];
+        /// status of the make executable readable bit
+        mxr, set_mxr, MXR: [19];
+        /// status of the supervisor user memory access bit
+        sum, set_sum, SUM: [18];
+        /// status of the extension state fields
+        xs, set_xs, XS: [15..17] as ExtensionState;
+        /// floating point state
+        fs, set_fs, FS: [13..15] as ExtensionState;
+        /// vector state
+        vs, set_vs, VS: [9..11] as ExtensionState;
+        /// supervisor previous privilege
+        spp, set_spp, SPP: [8];
+        /// user binary endianness
+        ube, set_ube, UBE: [6];
+        /// supervisor previous interrupt enable
+        spie, set_spie, SPIE: [5];
+        /// supervisor interrupt enable
+        sie, set_sie, SIE: [1];
+    }
 }
 
 impl Vsstatus {
-    /// Returns the raw bits of the register.
-    #[inline]
-    pub fn bits(&self) -> usize {
-        self.bits
-    }
-    /// Creates a register value from raw bits.
-    #[inline]
-    pub fn from_bits(x: usize) -> Self {
-        Vsstatus { bits: x }
-    }
-    /// Writes the register value to the CSR.
+    /// Returns bits 60..64 of the register.
     ///
-    /// # Safety
-    ///
-    /// This function is unsafe because writing to CSR registers can have
-    /// system-wide effects and may violate memory safety guarantees.
-    #[inline]
-    pub unsafe fn write(&self) {
-        // SAFETY: Caller ensures this is safe to execute
-        unsafe { _write(self.bits) };
-    }
-    /// Returns the status of the dirty state fields.
-    #[inline]
-    pub fn sd(&self) -> usize {
-        self.bits.get_bits(60..64)
-    }
-    /// Sets the status of the dirty state fields.
-    #[inline]
-    pub fn set_sd(&mut self, val: usize) {
-        self.bits.set_bits(60..64, val);
-    }
-    /// Returns the effective user XLEN setting.
-    #[inline]
-    pub fn uxl(&self) -> UxlValues {
-        UxlValues::from(self.bits.get_bits(32..34))
-    }
-    /// Sets the effective user XLEN setting.
-    #[inline]
-    pub fn set_uxl(&mut self, val: UxlValues) {
-        self.bits.set_bits(32..34, val as usize);
-    }
-    /// Returns the status of the make executable readable bit.
-    #[inline]
-    pub fn mxr(&self) -> bool {
-        self.bits.get_bit(19)
-    }
-    /// Sets the MXR (Make eXecutable Readable) bit.
-    #[inline]
-    pub fn set_mxr(&mut self, val: bool) {
-        self.bits.set_bit(19, val);
-    }
-    /// Returns the status of the supervisor user memory access bit.
-    #[inline]
-    pub fn sum(&self) -> bool {
-        self.bits.get_bit(18)
-    }
-    /// Sets the status of the supervisor user memory access bit.
-    #[inline]
-    pub fn set_sum(&mut self, val: bool) {
-        self.bits.set_bit(18, val);
-    }
-    /// Returns the status of the extension state fields.
-    #[inline]
-    pub fn xs(&self) -> usize {
-        self.bits.get_bits(15..17)
-    }
-    /// Sets the status of the extension state fields.
-    #[inline]
-    pub fn set_xs(&mut self, val: usize) {
-        self.bits.set_bits(15..17, val);
-    }
-    /// Returns the floating point state.
-    #[inline]
-    pub fn fs(&self) -> usize {
-        self.bits.get_bits(13..15)
-    }
-    /// Sets the floating point state.
-    #[inline]
-    pub fn set_fs(&mut self, val: usize) {
-        self.bits.set_bits(13..15, val);
-    }
-    /// Returns the supervisor previous privilege.
-    #[inline]
-    pub fn spp(&self) -> bool {
-        self.bits.get_bit(8)
-    }
-    /// Sets the supervisor previous privilege.
-    #[inline]
-    pub fn set_spp(&mut self, val: bool) {
-        self.bits.set_bit(8, val);
-    }
-    /// Returns the user binary endianness.
-    #[inline]
-    pub fn ube(&self) -> bool {
-        self.bits.get_bit(6)
-    }
-    /// Sets the user binary endianness.
-    #[inline]
-    pub fn set_ube(&mut self, val: bool) {
-        self.bits.set_bit(6, val);
-    }
-    /// Returns the supervisor previous interrupt enable.
+    /// Only bit 63 of this range is defined, as the SD bit; use
+    /// [`sd`](Vsstatus::sd) instead.
+    #[deprecated(note = "SD is the single bit 63, use `sd` instead")]
     #[inline]
-    pub fn spie(&self) -> bool {
-        self.bits.get_bit(5)
-    }
-    /// Sets the supervisor previous interrupt enable.
-    #[inline]
-    pub fn set_spie(&mut self, val: bool) {
-        self.bits.set_bit(5, val);
-    }
-    /// Returns the supervisor interrupt enable.
-    #[inline]
-    pub fn sie(&self) -> bool {
-        self.bits.get_bit(1)
-    }
-    /// Sets the supervisor interrupt enable.
-    #[inline]
-    pub fn set_sie(&mut self, val: bool) {
-        self.bits.set_bit(1, val);
+    pub fn sd_bits(&self) -> usize {
+        self.bits >> 60
     }
 }
 
-read_csr_as!(Vsstatus, 0x200);
-write_csr!(0x200);
-set!(0x200);
-clear!(0x200);
 // bit ops
+set_clear_csr!(
+    /// Supervisor disable-trap enable.
+    , set_sdt, clear_sdt, 1 << 24);
+set_clear_csr!(
+    /// Supervisor previous expected landing pad enable.
+    , set_spelp, clear_spelp, 1 << 23);
 set_clear_csr!(
     /// Make executable readable enable.
     , set_mxr, clear_mxr, 1 << 19);
@@ -198,6 +118,37 @@ impl UxlValues {
     }
 }
 
+/// State of the floating-point unit, the vector unit or of other user-mode
+/// extensions.
+///
+/// Lazy context switching saves the extension state only when it is
+/// [`Dirty`](ExtensionState::Dirty), and marks it
+/// [`Clean`](ExtensionState::Clean) once saved.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum ExtensionState {
+    /// The extension is disabled; accessing it raises an illegal-instruction
+    /// exception.
+    Off = 0,
+    /// The extension holds its initial state.
+    Initial = 1,
+    /// The state may differ from the initial one but matches the last save.
+    Clean = 2,
+    /// The state may have been modified since the last save.
+    Dirty = 3,
+}
+
+impl ExtensionState {
+    fn from(x: usize) -> Self {
+        match x {
+            0 => Self::Off,
+            1 => Self::Initial,
+            2 => Self::Clean,
+            _ => Self::Dirty,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,19 +161,18 @@ mod tests {
 
     #[test]
     fn test_vsstatus_sd() {
-        let mut vsstatus = Vsstatus::from_bits(0);
+        // SD (bit 63) is read-only
+        assert!(Vsstatus::from_bits(1 << 63).sd());
+        assert!(!Vsstatus::from_bits(0).sd());
 
-        // Test setting SD (4-bit field, bits 60-63)
-        vsstatus.set_sd(0xA);
-        assert_eq!(vsstatus.sd(), 0xA);
-        assert_eq!(vsstatus.bits() & (0xF << 60), 0xA << 60);
-
-        // Test boundary values
-        vsstatus.set_sd(0);
-        assert_eq!(vsstatus.sd(), 0);
+        // Bits 60-62 are not part of SD
+        assert!(!Vsstatus::from_bits(0x7 << 60).sd());
+    }
 
-        vsstatus.set_sd(0xF); // Maximum 4-bit value
-        assert_eq!(vsstatus.sd(), 0xF);
+    #[test]
+    #[allow(deprecated)]
+    fn test_vsstatus_sd_bits() {
+        assert_eq!(Vsstatus::from_bits(0xA << 60).sd_bits(), 0xA);
     }
 
     #[test]
@@ -286,21 +236,41 @@ mod tests {
         assert_eq!(vsstatus.bits() & (1 << 1), 1 << 1);
     }
 
+    #[test]
+    fn test_vsstatus_sdt_spelp() {
+        let mut vsstatus = Vsstatus::from_bits(0);
+
+        // Test SDT bit (bit 24)
+        vsstatus.set_sdt(true);
+        assert!(vsstatus.sdt());
+        assert_eq!(vsstatus.bits(), 1 << 24);
+
+        // Test SPELP bit (bit 23)
+        vsstatus.set_spelp(true);
+        assert!(vsstatus.spelp());
+        assert_eq!(vsstatus.bits(), (1 << 24) | (1 << 23));
+
+        vsstatus.set_sdt(false);
+        assert!(!vsstatus.sdt());
+        assert_eq!(vsstatus.bits(), 1 << 23);
+    }
+
     #[test]
     fn test_vsstatus_xs() {
         let mut vsstatus = Vsstatus::from_bits(0);
 
         // Test setting XS (2-bit field, bits 15-16)
-        vsstatus.set_xs(0x2);
-        assert_eq!(vsstatus.xs(), 0x2);
+        vsstatus.set_xs(ExtensionState::Clean);
+        assert_eq!(vsstatus.xs(), ExtensionState::Clean);
         assert_eq!(vsstatus.bits() & (0b11 << 15), 0x2 << 15);
 
         // Test boundary values
-        vsstatus.set_xs(0);
-        assert_eq!(vsstatus.xs(), 0);
+        vsstatus.set_xs(ExtensionState::Off);
+        assert_eq!(vsstatus.xs(), ExtensionState::Off);
 
-        vsstatus.set_xs(0x3); // Maximum 2-bit value
-        assert_eq!(vsstatus.xs(), 0x3);
+        vsstatus.set_xs(ExtensionState::Dirty);
+        assert_eq!(vsstatus.xs(), ExtensionState::Dirty);
+        assert_eq!(vsstatus.bits() & (0b11 << 15), 0x3 << 15);
     }
 
     #[test]
@@ -308,16 +278,36 @@ mod tests {
         let mut vsstatus = Vsstatus::from_bits(0);
 
         // Test setting FS (2-bit field, bits 13-14)
-        vsstatus.set_fs(0x2);
-        assert_eq!(vsstatus.fs(), 0x2);
+        vsstatus.set_fs(ExtensionState::Clean);
+        assert_eq!(vsstatus.fs(), ExtensionState::Clean);
         assert_eq!(vsstatus.bits() & (0b11 << 13), 0x2 << 13);
 
         // Test boundary values
-        vsstatus.set_fs(0);
-        assert_eq!(vsstatus.fs(), 0);
+        vsstatus.set_fs(ExtensionState::Off);
+        assert_eq!(vsstatus.fs(), ExtensionState::Off);
+
+        vsstatus.set_fs(ExtensionState::Initial);
+        assert_eq!(vsstatus.fs(), ExtensionState::Initial);
+        assert_eq!(vsstatus.bits() & (0b11 << 13), 0x1 << 13);
+
+        vsstatus.set_fs(ExtensionState::Dirty);
+        assert_eq!(vsstatus.fs(), ExtensionState::Dirty);
+    }
+
+    #[test]
+    fn test_vsstatus_vs() {
+        let mut vsstatus = Vsstatus::from_bits(0);
+
+        // Test setting VS (2-bit field, bits 9-10)
+        vsstatus.set_vs(ExtensionState::Initial);
+        assert_eq!(vsstatus.vs(), ExtensionState::Initial);
+        assert_eq!(vsstatus.bits(), 0x1 << 9);
 
-        vsstatus.set_fs(0x3); // Maximum 2-bit value
-        assert_eq!(vsstatus.fs(), 0x3);
+        vsstatus.set_vs(ExtensionState::Dirty);
+        assert_eq!(vsstatus.vs(), ExtensionState::Dirty);
+        assert_eq!(vsstatus.bits(), 0x3 << 9);
+        assert_eq!(vsstatus.fs(), ExtensionState::Off);
+        assert!(!vsstatus.spp());
     }
 
     #[test]
@@ -335,26 +325,25 @@ mod tests {
 
     #[test]
     fn test_vsstatus_all_fields() {
-        let mut vsstatus = Vsstatus::from_bits(0);
+        let mut vsstatus = Vsstatus::from_bits(1 << 63);
 
         // Set multiple fields and verify they don't interfere
-        vsstatus.set_sd(0xB);
         vsstatus.set_uxl(UxlValues::Uxl64);
         vsstatus.set_mxr(true);
         vsstatus.set_sum(true);
-        vsstatus.set_xs(0x2);
-        vsstatus.set_fs(0x3);
+        vsstatus.set_xs(ExtensionState::Clean);
+        vsstatus.set_fs(ExtensionState::Dirty);
         vsstatus.set_spp(true);
         vsstatus.set_ube(true);
         vsstatus.set_spie(true);
         vsstatus.set_sie(true);
 
-        assert_eq!(vsstatus.sd(), 0xB);
+        assert!(vsstatus.sd());
         assert!(matches!(vsstatus.uxl(), UxlValues::Uxl64));
         assert!(vsstatus.mxr());
         assert!(vsstatus.sum());
-        assert_eq!(vsstatus.xs(), 0x2);
-        assert_eq!(vsstatus.fs(), 0x3);
+        assert_eq!(vsstatus.xs(), ExtensionState::Clean);
+        assert_eq!(vsstatus.fs(), ExtensionState::Dirty);
         assert!(vsstatus.spp());
         assert!(vsstatus.ube());
         assert!(vsstatus.spie());
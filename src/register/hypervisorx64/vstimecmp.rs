@@ -0,0 +1,130 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual Supervisor Timer Compare Register (Sstc).
+//!
+//! The guest timer interrupt (VSTIP) is pending whenever the guest's view of
+//! `time` (host `time` plus `htimedelta`) is greater than or equal to this value.
+//! Requires `henvcfg.STCE` to be set.
+//!
+//! On RV32 the 64-bit compare value is split across `vstimecmp` (low half) and
+//! `vstimecmph` (high half). Use
+//! [`write64`](crate::register::vstimecmp::write64) to update
+//! it without ever exposing an intermediate value that fires early, and
+//! [`read64`](crate::register::vstimecmp::read64) to read it.
+//! The deadline helpers of [`Vstimecmp`](crate::register::vstimecmp::Vstimecmp)
+//! hold the whole value only on RV64 and are not available on RV32.
+
+crate::register! {
+    /// Virtual Supervisor Timer Compare Register.
+    Vstimecmp: 0x24D {}
+}
+
+#[cfg(not(target_arch = "riscv32"))]
+impl Vstimecmp {
+    /// Value that never fires.
+    pub const NEVER: Vstimecmp = Vstimecmp { bits: usize::MAX };
+
+    /// Returns a compare value for the given guest-time deadline.
+    #[inline]
+    pub fn from_deadline(deadline: u64) -> Self {
+        Vstimecmp {
+            bits: deadline as usize,
+        }
+    }
+    /// Returns the deadline in guest time.
+    #[inline]
+    pub fn deadline(&self) -> u64 {
+        self.bits as u64
+    }
+    /// Returns whether a guest timer interrupt is pending at guest time `now`.
+    #[inline]
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.deadline()
+    }
+}
+
+/// Writes the full 64-bit compare value without tearing.
+///
+/// On RV32 the low half is first set to its maximum, then the high half and
+/// finally the real low half are written, so no intermediate value is earlier
+/// than both the old and the new deadline. On RV64 this is a single CSR write.
+///
+/// # Safety
+///
+/// Same as [`Vstimecmp::write`].
+#[inline]
+pub unsafe fn write64(deadline: u64) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe {
+        match () {
+            #[cfg(target_arch = "riscv32")]
+            () => super::split_write(
+                deadline,
+                u32::MAX,
+                |lo| Vstimecmp { bits: lo }.write(),
+                |hi| super::vstimecmph::write(hi),
+            ),
+            #[cfg(not(target_arch = "riscv32"))]
+            () => Vstimecmp::from_deadline(deadline).write(),
+        }
+    }
+}
+
+/// Reads the full 64-bit compare value.
+///
+/// Only software changes the register, so the two halves need no retry loop.
+#[inline]
+pub fn read64() -> u64 {
+    match () {
+        #[cfg(target_arch = "riscv32")]
+        () => ((super::vstimecmph::read() as u32 as u64) << 32) | read().bits() as u32 as u64,
+        #[cfg(not(target_arch = "riscv32"))]
+        () => read().bits() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::cell::RefCell;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_split_write_order() {
+        let writes = RefCell::new(Vec::new());
+        super::super::split_write(
+            0x1_0000_0010,
+            u32::MAX,
+            |lo| writes.borrow_mut().push(('l', lo)),
+            |hi| writes.borrow_mut().push(('h', hi)),
+        );
+        assert_eq!(
+            writes.into_inner(),
+            [('l', 0xffff_ffff), ('h', 1), ('l', 0x10)]
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "riscv32"))]
+    fn test_deadline() {
+        let cmp = Vstimecmp::from_deadline(1000);
+        assert_eq!(cmp.bits(), 1000);
+        assert!(!cmp.is_due(999));
+        assert!(cmp.is_due(1000));
+        assert!(!Vstimecmp::NEVER.is_due(u64::MAX - 1));
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual Supervisor Timer Compare High Register (Sstc, RV32 only).
+//!
+//! Holds bits 63:32 of the guest timer compare value. Prefer
+//! [`vstimecmp::write64`](crate::register::vstimecmp::write64),
+//! which orders the two half writes so the guest timer never fires early.
+
+use riscv::{read_csr_as_usize, write_csr_as_usize};
+
+read_csr_as_usize!(0x25D);
+write_csr_as_usize!(0x25D);
@@ -0,0 +1,79 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual Supervisor Top External Interrupt Register (AIA).
+//!
+//! `vstopei` reports the highest-priority pending and enabled interrupt
+//! identity of the guest interrupt file selected by `hstatus.VGEIN`, or zero if
+//! there is none. Writing the register claims that interrupt by clearing its
+//! pending bit; [`claim`](crate::register::vstopei::claim) does both in one
+//! `csrrw` so no newer interrupt can be cleared by accident.
+
+crate::register! {
+    /// Virtual Supervisor Top External Interrupt Register.
+    Vstopei: 0x25C {
+        /// identity of the top pending and enabled interrupt
+        iid, set_iid, IID: [16..27];
+        /// priority of the top interrupt, equal to its identity
+        iprio, set_iprio, IPRIO: [0..11];
+    }
+}
+
+impl Vstopei {
+    /// Returns the identity of the top interrupt, or `None` if none is pending.
+    #[inline]
+    pub fn pending(&self) -> Option<usize> {
+        match self.iid() {
+            0 => None,
+            iid => Some(iid),
+        }
+    }
+}
+
+/// Claims the top external interrupt of the selected guest interrupt file.
+///
+/// Returns its identity, or `None` if no interrupt was pending and enabled.
+/// The caller completes the interrupt by handling the source; IMSIC files
+/// need no separate end-of-interrupt write.
+///
+/// # Safety
+///
+/// `hstatus.VGEIN` must select a guest interrupt file that belongs to the
+/// current guest, and the claimed interrupt must be handled or re-injected.
+#[inline]
+pub unsafe fn claim() -> Option<usize> {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let bits: usize;
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe { core::arch::asm!("csrrw {0}, 0x25C, zero", out(reg) bits) };
+        Vstopei::from_bits(bits).pending()
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vstopei_fields() {
+        let vstopei = Vstopei::from_bits((42 << 16) | 42);
+        assert_eq!(vstopei.iid(), 42);
+        assert_eq!(vstopei.iprio(), 42);
+        assert_eq!(vstopei.pending(), Some(42));
+        assert_eq!(Vstopei::from_bits(0).pending(), None);
+    }
+}
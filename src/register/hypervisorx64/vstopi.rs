@@ -0,0 +1,58 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual Supervisor Top Interrupt Register (AIA).
+//!
+//! `vstopi` is a read-only view of the highest-priority interrupt that is both
+//! pending and enabled for VS-mode, as computed by the hardware from `vsip`,
+//! `vsie`, `hvictl` and the `hviprio` registers. It reads as zero when no
+//! interrupt qualifies. On harts without AIA,
+//! [`interrupts::vstopi`](crate::register::interrupts::vstopi) computes the
+//! same value in software.
+
+crate::register! {
+    /// Virtual Supervisor Top Interrupt Register.
+    Vstopi: 0xEB0, ReadOnly {
+        /// identity of the top pending and enabled interrupt
+        iid, IID: [16..28];
+        /// priority number of the top interrupt
+        iprio, IPRIO: [0..8];
+    }
+}
+
+impl Vstopi {
+    /// Returns the identity of the top interrupt, or `None` if none is pending.
+    #[inline]
+    pub fn pending(&self) -> Option<usize> {
+        match self.iid() {
+            0 => None,
+            iid => Some(iid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::interrupts::{DEFAULT_PRIORITIES, top_interrupt};
+
+    #[test]
+    fn test_vstopi_matches_software_model() {
+        let top = top_interrupt(1 << 13, 1 << 13, &DEFAULT_PRIORITIES).unwrap();
+        let vstopi = Vstopi::from_bits(top.bits());
+        assert_eq!(vstopi.pending(), Some(13));
+        assert_eq!(vstopi.iprio(), 0);
+        assert_eq!(Vstopi::from_bits(0).pending(), None);
+    }
+}
@@ -0,0 +1,478 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interrupt Prioritization.
+//!
+//! Software model of the Advanced Interrupt Architecture (AIA) priority rules,
+//! so hypervisors running on plain H-extension hardware can emulate `vstopi`
+//! semantics: pick the highest-priority interrupt that is both pending and
+//! enabled, as the hardware would on an AIA implementation.
+//!
+//! Interrupts are ranked by priority number first (a smaller non-zero number
+//! is a higher priority, as in `hviprio1`/`hviprio2`), and by the AIA default
+//! priority order among interrupts with equal numbers. A priority number of
+//! zero means "default priority" and ranks below every non-zero number.
+//!
+//! On AIA hardware,
+//! [`inject_prioritized`](crate::register::interrupts::inject_prioritized)
+//! injects an interrupt with explicit identity and priority through `hvictl`
+//! instead.
+//!
+//! The hypervisor interrupt registers `hie`, `hip`, `hvip` and `hideleg` share
+//! the bit positions of the VS-level and guest external interrupts, named by
+//! [`HypervisorInterrupt`](crate::register::interrupts::HypervisorInterrupt)
+//! and accessed with their `is_set`/`set` methods.
+//! [`deliverable`](crate::register::interrupts::deliverable) lists those that
+//! are both pending and enabled.
+
+use super::{hie, hip, hvictl, vscause, vsie, vsip};
+use bit_field::BitField;
+
+/// Number of interrupt identities covered by the priority model.
+pub const INTERRUPT_COUNT: usize = 64;
+
+/// AIA default priority order of the standard interrupts, highest first.
+///
+/// Interrupts not listed here (custom interrupts 24–31 and 48–63) rank below
+/// all listed ones, in ascending numerical order.
+pub const DEFAULT_PRIORITY_ORDER: [u8; 37] = [
+    47, 23, 46, 45, 22, 44, 43, 21, 42, 41, 20, 40, // local, high
+    11, 3, 7, // machine external/software/timer
+    9, 1, 5,  // supervisor external/software/timer
+    12, // supervisor guest external
+    10, 2, 6, // VS external/software/timer
+    13, 14, 15, // counter overflow, reserved
+    39, 19, 38, 37, 18, 36, 35, 17, 34, 33, 16, 32, // local, low
+];
+
+/// Priority numbers indexed by interrupt identity; all zero by default.
+pub type Priorities = [u8; INTERRUPT_COUNT];
+
+/// Priority numbers of an implementation without configurable priorities.
+pub const DEFAULT_PRIORITIES: Priorities = [0; INTERRUPT_COUNT];
+
+/// Returns the priority byte of `iid` in an `hviprio*` value with lanes `iids`.
+pub(crate) fn lane_priority(bits: usize, iids: &[usize; 8], iid: usize) -> Option<u8> {
+    let lane = lane_of(iids, iid)?;
+    Some(bits.get_bits(lane * 8..lane * 8 + 8) as u8)
+}
+
+/// Sets the priority byte of `iid` in an `hviprio*` value with lanes `iids`.
+pub(crate) fn set_lane_priority(bits: &mut usize, iids: &[usize; 8], iid: usize, prio: u8) -> bool {
+    let Some(lane) = lane_of(iids, iid) else {
+        return false;
+    };
+    bits.set_bits(lane * 8..lane * 8 + 8, prio as usize);
+    true
+}
+
+/// Returns the byte lane of `iid`, limited to the lanes that fit in XLEN.
+fn lane_of(iids: &[usize; 8], iid: usize) -> Option<usize> {
+    iids[..core::mem::size_of::<usize>()]
+        .iter()
+        .position(|&n| n == iid)
+}
+
+/// Returns the position of `iid` in the default priority order (0 is highest).
+#[inline]
+pub fn default_rank(iid: usize) -> usize {
+    DEFAULT_PRIORITY_ORDER
+        .iter()
+        .position(|&n| n as usize == iid)
+        .unwrap_or(DEFAULT_PRIORITY_ORDER.len() + iid)
+}
+
+/// The highest-priority pending interrupt, as reported by `vstopi`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TopInterrupt {
+    iid: usize,
+    iprio: u8,
+}
+
+impl TopInterrupt {
+    /// Returns the interrupt identity.
+    #[inline]
+    pub fn iid(&self) -> usize {
+        self.iid
+    }
+    /// Returns the priority number, or 0 for default priority.
+    #[inline]
+    pub fn iprio(&self) -> u8 {
+        self.iprio
+    }
+    /// Returns the value in `vstopi` layout (IID in bits 16..28, IPRIO in bits 0..8).
+    #[inline]
+    pub fn bits(&self) -> usize {
+        let mut bits = 0;
+        bits.set_bits(16..28, self.iid);
+        bits.set_bits(0..8, self.iprio as usize);
+        bits
+    }
+}
+
+/// Selects the highest-priority interrupt among `pending & enabled`.
+///
+/// Returns `None` if no interrupt is both pending and enabled.
+pub fn top_interrupt(
+    pending: usize,
+    enabled: usize,
+    priorities: &Priorities,
+) -> Option<TopInterrupt> {
+    let candidates = pending & enabled;
+    (0..usize::BITS as usize)
+        .filter(|&iid| candidates.get_bit(iid))
+        .min_by_key(|&iid| {
+            let prio = priorities.get(iid).copied().unwrap_or(0);
+            // Zero (default priority) ranks below every configured number.
+            let key = if prio == 0 { 256 } else { prio as usize };
+            (key, default_rank(iid))
+        })
+        .map(|iid| TopInterrupt {
+            iid,
+            iprio: priorities.get(iid).copied().unwrap_or(0),
+        })
+}
+
+/// Computes the guest's `vstopi` from `vsip` and `vsie` values.
+#[inline]
+pub fn compute_vstopi(
+    vsip: vsip::Vsip,
+    vsie: vsie::Vsie,
+    priorities: &Priorities,
+) -> Option<TopInterrupt> {
+    top_interrupt(vsip.bits(), vsie.bits(), priorities)
+}
+
+/// Reads `vsip` and `vsie` and computes the guest's `vstopi` in software.
+#[inline]
+pub fn vstopi(priorities: &Priorities) -> Option<TopInterrupt> {
+    compute_vstopi(vsip::read(), vsie::read(), priorities)
+}
+
+/// Identity of the supervisor external interrupt, delivered via `hvip`/`hgeie`.
+const EXTERNAL_IID: usize = 9;
+
+/// Error returned when an interrupt cannot be injected through `hvictl`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InjectError {
+    /// The identity is 0, the external interrupt (9), or wider than 12 bits.
+    InvalidIid(usize),
+}
+
+/// Returns the `hvictl` value injecting interrupt `iid` with priority `prio`.
+///
+/// VTI is set so the interrupt takes part in VS-level interrupt selection,
+/// and IPRIOM is set so the guest observes `prio` in `vstopi`.
+pub fn injection_bits(iid: usize, prio: u8) -> Result<usize, InjectError> {
+    if iid == 0 || iid == EXTERNAL_IID || iid >= 1 << 12 {
+        return Err(InjectError::InvalidIid(iid));
+    }
+    let mut ctl = hvictl::Hvictl::from_bits(0);
+    ctl.set_vti(true);
+    ctl.set_ipriom(true);
+    ctl.set_iid(iid);
+    ctl.set_iprio(prio as usize);
+    Ok(ctl.bits())
+}
+
+/// Injects interrupt `iid` with priority `prio` into the current guest.
+///
+/// The interrupt stays asserted until [`clear_injected`] or
+/// [`clear_if_delivered`] is called.
+///
+/// # Safety
+///
+/// Requires AIA support for `hvictl`, and the target guest must be the one
+/// currently loaded on the hart.
+pub unsafe fn inject_prioritized(iid: usize, prio: u8) -> Result<(), InjectError> {
+    let bits = injection_bits(iid, prio)?;
+    // SAFETY: Caller ensures hvictl belongs to the current guest
+    unsafe { hvictl::Hvictl::from_bits(bits).write() };
+    Ok(())
+}
+
+/// Retracts any interrupt injected through `hvictl`.
+///
+/// # Safety
+///
+/// Requires AIA support for `hvictl`.
+#[inline]
+pub unsafe fn clear_injected() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { hvictl::Hvictl::from_bits(0).write() };
+}
+
+/// Retracts the injected interrupt once the guest has taken it.
+///
+/// Delivery is detected by `vscause` reporting the injected identity as an
+/// interrupt, so this must be called on the first exit after guest entry,
+/// before the guest could take another trap. Returns whether it was cleared.
+///
+/// # Safety
+///
+/// Requires AIA support for `hvictl`.
+pub unsafe fn clear_if_delivered() -> bool {
+    let ctl = hvictl::read();
+    if !ctl.vti() {
+        return false;
+    }
+    let cause = vscause::read();
+    if cause.interrupt() && cause.code() == ctl.iid() {
+        // SAFETY: Caller ensures this is safe to execute
+        unsafe { clear_injected() };
+        true
+    } else {
+        false
+    }
+}
+
+/// Interrupts with a bit in `hie`, `hip`, `hvip` and `hideleg`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(usize)]
+pub enum HypervisorInterrupt {
+    /// VS-level software interrupt.
+    Vssi = 2,
+    /// VS-level timer interrupt.
+    Vsti = 6,
+    /// VS-level external interrupt.
+    Vsei = 10,
+    /// Supervisor guest external interrupt.
+    Sgei = 12,
+}
+
+impl HypervisorInterrupt {
+    /// Every hypervisor interrupt, in ascending bit order.
+    pub const ALL: [HypervisorInterrupt; 4] = [Self::Vssi, Self::Vsti, Self::Vsei, Self::Sgei];
+
+    /// Returns the interrupt number.
+    #[inline]
+    pub fn number(self) -> usize {
+        self as usize
+    }
+    /// Returns the bit of the interrupt in the hypervisor interrupt registers.
+    #[inline]
+    pub fn mask(self) -> usize {
+        1 << self.number()
+    }
+}
+
+/// Implements `is_set`/`set` by
+/// [`HypervisorInterrupt`](crate::register::interrupts::HypervisorInterrupt)
+/// for a register generated by [`register!`](crate::register!).
+///
+/// `$implemented` masks the interrupt bits the register has; `set` leaves
+/// the others alone. `$state` completes "whether `irq` is", and doc comments
+/// before the register name are appended to the documentation of `set`.
+macro_rules! hypervisor_interrupt_bits {
+    ($(#[$set_doc:meta])* $reg:ident: $implemented:expr, $state:literal) => {
+        impl $reg {
+            #[doc = concat!("Returns whether `irq` is ", $state, ".")]
+            #[inline]
+            pub fn is_set(&self, irq: $crate::register::interrupts::HypervisorInterrupt) -> bool {
+                self.bits & irq.mask() != 0
+            }
+            #[doc = concat!("Sets whether `irq` is ", $state, ".")]
+            $(#[$set_doc])*
+            #[inline]
+            pub fn set(
+                &mut self,
+                irq: $crate::register::interrupts::HypervisorInterrupt,
+                value: bool,
+            ) {
+                let mask = irq.mask() & ($implemented);
+                if value {
+                    self.bits |= mask;
+                } else {
+                    self.bits &= !mask;
+                }
+            }
+        }
+    };
+}
+pub(crate) use hypervisor_interrupt_bits;
+
+/// Iterator over hypervisor interrupts, in ascending bit order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HypervisorInterrupts {
+    bits: usize,
+}
+
+impl Iterator for HypervisorInterrupts {
+    type Item = HypervisorInterrupt;
+
+    fn next(&mut self) -> Option<HypervisorInterrupt> {
+        let irq = HypervisorInterrupt::ALL
+            .into_iter()
+            .find(|irq| self.bits & irq.mask() != 0)?;
+        self.bits &= !irq.mask();
+        Some(irq)
+    }
+}
+
+/// Returns the hypervisor interrupts that are pending in `hip` and enabled in `hie`.
+#[inline]
+pub fn compute_deliverable(hip: hip::Hip, hie: hie::Hie) -> HypervisorInterrupts {
+    HypervisorInterrupts {
+        bits: hip.bits() & hie.bits(),
+    }
+}
+
+/// Reads `hip` and `hie` once each and returns the interrupts that are both
+/// pending and enabled.
+#[inline]
+pub fn deliverable() -> HypervisorInterrupts {
+    compute_deliverable(hip::read(), hie::read())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSI: usize = 1 << 1;
+    const STI: usize = 1 << 5;
+    const SEI: usize = 1 << 9;
+    const LCOFI: usize = 1 << 13;
+
+    #[test]
+    fn test_deliverable() {
+        let hip = hip::Hip::from_bits((1 << 2) | (1 << 10) | (1 << 12) | LCOFI);
+        let hie = hie::Hie::from_bits((1 << 12) | (1 << 6) | (1 << 2) | LCOFI);
+        let mut irqs = compute_deliverable(hip, hie);
+        assert_eq!(irqs.next(), Some(HypervisorInterrupt::Vssi));
+        assert_eq!(irqs.next(), Some(HypervisorInterrupt::Sgei));
+        assert_eq!(irqs.next(), None);
+        assert_eq!(
+            compute_deliverable(hip, hie::Hie::from_bits(0)).next(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_hypervisor_interrupt_bits() {
+        use crate::register::{hideleg, hie, hip, hvip};
+
+        let mut hie = hie::Hie::from_bits(0);
+        let mut hip = hip::Hip::from_bits(0);
+        let mut hvip = hvip::Hvip::from_bits(0);
+        let mut hideleg = hideleg::Hideleg::from_bits(0);
+        hie.set(HypervisorInterrupt::Sgei, true);
+        hip.set(HypervisorInterrupt::Vssi, true);
+        hvip.set(HypervisorInterrupt::Vsti, true);
+        hideleg.set(HypervisorInterrupt::Vsei, true);
+        assert!(hie.sgeie() && hip.vssip() && hvip.vstip() && hideleg.vseip());
+        for irq in HypervisorInterrupt::ALL {
+            let set = irq.mask() == hie.bits();
+            assert_eq!(hie.is_set(irq), set);
+        }
+
+        hvip.set(HypervisorInterrupt::Vsti, false);
+        assert_eq!(hvip.bits(), 0);
+
+        // SGEI has no bit in hvip or hideleg
+        hvip.set(HypervisorInterrupt::Sgei, true);
+        hideleg.set(HypervisorInterrupt::Sgei, true);
+        assert_eq!(hvip.bits(), 0);
+        assert_eq!(hideleg.bits(), HypervisorInterrupt::Vsei.mask());
+        assert!(!hvip.is_set(HypervisorInterrupt::Sgei));
+        assert_eq!(
+            HypervisorInterrupt::ALL.map(HypervisorInterrupt::mask),
+            [1 << 2, 1 << 6, 1 << 10, 1 << 12]
+        );
+    }
+
+    #[test]
+    fn test_default_order() {
+        assert!(default_rank(9) < default_rank(1));
+        assert!(default_rank(1) < default_rank(5));
+        assert!(default_rank(5) < default_rank(13));
+        assert!(default_rank(12) < default_rank(10));
+        // Custom interrupts rank below all standard ones
+        assert!(default_rank(24) > default_rank(32));
+    }
+
+    #[test]
+    fn test_top_interrupt_default_priority() {
+        let all = SSI | STI | SEI | LCOFI;
+        let top = top_interrupt(all, all, &DEFAULT_PRIORITIES).unwrap();
+        assert_eq!(top.iid(), 9);
+        assert_eq!(top.iprio(), 0);
+
+        let top = top_interrupt(SSI | STI, all, &DEFAULT_PRIORITIES).unwrap();
+        assert_eq!(top.iid(), 1);
+
+        // Pending but not enabled interrupts are ignored
+        let top = top_interrupt(SEI | STI, STI, &DEFAULT_PRIORITIES).unwrap();
+        assert_eq!(top.iid(), 5);
+
+        assert!(top_interrupt(SEI, SSI, &DEFAULT_PRIORITIES).is_none());
+    }
+
+    #[test]
+    fn test_top_interrupt_configured_priority() {
+        let mut prio = DEFAULT_PRIORITIES;
+        prio[5] = 10;
+        prio[13] = 3;
+        let all = SSI | STI | SEI | LCOFI;
+
+        let top = top_interrupt(all, all, &prio).unwrap();
+        assert_eq!(top.iid(), 13);
+        assert_eq!(top.iprio(), 3);
+
+        let top = top_interrupt(SSI | STI | SEI, all, &prio).unwrap();
+        assert_eq!(top.iid(), 5);
+
+        // Equal numbers fall back to the default order
+        prio[1] = 10;
+        let top = top_interrupt(SSI | STI, all, &prio).unwrap();
+        assert_eq!(top.iid(), 1);
+    }
+
+    #[test]
+    fn test_top_interrupt_bits() {
+        let top = top_interrupt(SEI, SEI, &DEFAULT_PRIORITIES).unwrap();
+        assert_eq!(top.bits(), 9 << 16);
+
+        let mut prio = DEFAULT_PRIORITIES;
+        prio[5] = 0x42;
+        let top = top_interrupt(STI, STI, &prio).unwrap();
+        assert_eq!(top.bits(), (5 << 16) | 0x42);
+    }
+
+    #[test]
+    fn test_injection_bits() {
+        let bits = injection_bits(13, 0x20).unwrap();
+        assert_eq!(bits, (1 << 30) | (13 << 16) | (1 << 8) | 0x20);
+        assert_eq!(bits.get_bits(16..28), 13);
+
+        assert_eq!(injection_bits(0, 1), Err(InjectError::InvalidIid(0)));
+        assert_eq!(injection_bits(9, 1), Err(InjectError::InvalidIid(9)));
+        assert_eq!(
+            injection_bits(1 << 12, 1),
+            Err(InjectError::InvalidIid(1 << 12))
+        );
+        assert!(injection_bits(0xFFF, 0).is_ok());
+    }
+
+    #[test]
+    fn test_compute_vstopi() {
+        let mut vsip = vsip::Vsip::from_bits(0);
+        let mut vsie = vsie::Vsie::from_bits(0);
+        vsip.set_ssip(true);
+        vsip.set_stip(true);
+        vsie.set_ssie(true);
+        vsie.set_stie(true);
+        let top = compute_vstopi(vsip, vsie, &DEFAULT_PRIORITIES).unwrap();
+        assert_eq!(top.iid(), 1);
+    }
+}
@@ -0,0 +1,80 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Exception Delegation in `medeleg`.
+//!
+//! A hypervisor running in HS-mode only sees guest-page faults and
+//! virtual-instruction exceptions if firmware delegates them in `medeleg`.
+//! The helpers here touch exactly those bits and read the register back, as
+//! `medeleg` is WARL and an implementation may hardwire some bits.
+
+use crate::trap::{
+    INSTRUCTION_GUEST_PAGE_FAULT, LOAD_GUEST_PAGE_FAULT, STORE_GUEST_PAGE_FAULT,
+    VIRTUAL_INSTRUCTION,
+};
+
+riscv::read_csr!(0x302);
+riscv::set!(0x302);
+riscv::clear!(0x302);
+
+/// `medeleg` bits of the exceptions a hypervisor must handle in HS-mode.
+pub const HYPERVISOR_EXCEPTIONS: usize = (1 << INSTRUCTION_GUEST_PAGE_FAULT)
+    | (1 << LOAD_GUEST_PAGE_FAULT)
+    | (1 << VIRTUAL_INSTRUCTION)
+    | (1 << STORE_GUEST_PAGE_FAULT);
+
+/// Delegates guest-page faults and virtual-instruction exceptions to HS-mode.
+///
+/// Returns the bits of [`HYPERVISOR_EXCEPTIONS`] that are delegated
+/// afterwards; any missing bit is hardwired to zero.
+///
+/// # Safety
+///
+/// Must be called from M-mode, and HS-mode must have a trap handler for
+/// these exceptions.
+#[inline]
+pub unsafe fn delegate_hypervisor_exceptions() -> usize {
+    // SAFETY: Caller ensures HS-mode handles these exceptions
+    unsafe {
+        _set(HYPERVISOR_EXCEPTIONS);
+        _read() & HYPERVISOR_EXCEPTIONS
+    }
+}
+
+/// Stops delegating guest-page faults and virtual-instruction exceptions.
+///
+/// Returns the bits of [`HYPERVISOR_EXCEPTIONS`] that are still delegated
+/// afterwards; any remaining bit is hardwired to one.
+///
+/// # Safety
+///
+/// Must be called from M-mode, which then has to handle these exceptions.
+#[inline]
+pub unsafe fn undelegate_hypervisor_exceptions() -> usize {
+    // SAFETY: Caller ensures M-mode handles these exceptions
+    unsafe {
+        _clear(HYPERVISOR_EXCEPTIONS);
+        _read() & HYPERVISOR_EXCEPTIONS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hypervisor_exceptions() {
+        assert_eq!(HYPERVISOR_EXCEPTIONS, 0xF0_0000);
+    }
+}
@@ -0,0 +1,51 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Bits of the Machine Interrupt Enable Register.
+//!
+//! The enable bits of the VS-level interrupts and the supervisor guest external
+//! interrupt in `mie` are aliases of the corresponding `hie` bits. Only the
+//! hypervisor bits have accessors;
+//! [`Mie::bits`](crate::register::machine::mie::Mie::bits) still holds the full
+//! register value.
+
+use riscv::set_clear_csr;
+
+crate::register! {
+    /// Machine Interrupt Enable Register.
+    Mie: 0x304 {
+        /// virtual supervisor software interrupt enable
+        vssie, set_vssie, VSSIE: [2];
+        /// virtual supervisor timer interrupt enable
+        vstie, set_vstie, VSTIE: [6];
+        /// virtual supervisor external interrupt enable
+        vseie, set_vseie, VSEIE: [10];
+        /// supervisor guest external interrupt enable
+        sgeie, set_sgeie, SGEIE: [12];
+    }
+}
+
+// bit ops
+set_clear_csr!(
+    /// Virtual supervisor software interrupt enable.
+    , set_vssie, clear_vssie, 1 << 2);
+set_clear_csr!(
+    /// Virtual supervisor timer interrupt enable.
+    , set_vstie, clear_vstie, 1 << 6);
+set_clear_csr!(
+    /// Virtual supervisor external interrupt enable.
+    , set_vseie, clear_vseie, 1 << 10);
+set_clear_csr!(
+    /// Supervisor guest external interrupt enable.
+    , set_sgeie, clear_sgeie, 1 << 12);
@@ -0,0 +1,60 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Bits of the Machine Interrupt Pending Register.
+//!
+//! With the hypervisor extension, `mip` carries the VS-level interrupts and
+//! the supervisor guest external interrupt. VSSIP is a writable alias of
+//! `hvip.VSSIP`; the other bits reflect `hvip`, `hgeip` and other sources and
+//! are read-only here. These interrupts are always delegated to HS-mode, as
+//! their `mideleg` bits are read-only one.
+//!
+//! Only the hypervisor bits have accessors;
+//! [`Mip::bits`](crate::register::machine::mip::Mip::bits) still holds the full
+//! register value, so a read-modify-write leaves the other bits intact.
+
+use riscv::set_clear_csr;
+
+crate::register! {
+    /// Machine Interrupt Pending Register.
+    Mip: 0x344 {
+        /// virtual supervisor software interrupt pending
+        vssip, set_vssip, VSSIP: [2];
+        /// virtual supervisor timer interrupt pending
+        vstip, set_vstip, VSTIP: [6], ReadOnly;
+        /// virtual supervisor external interrupt pending
+        vseip, set_vseip, VSEIP: [10], ReadOnly;
+        /// supervisor guest external interrupt pending
+        sgeip, set_sgeip, SGEIP: [12], ReadOnly;
+    }
+}
+
+// bit ops
+set_clear_csr!(
+    /// Virtual supervisor software interrupt pending.
+    , set_vssip, clear_vssip, 1 << 2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mip_hypervisor_bits() {
+        let mip = Mip::from_bits((1 << 12) | (1 << 7) | (1 << 2));
+        assert!(mip.vssip());
+        assert!(mip.sgeip());
+        assert!(!mip.vstip());
+        assert!(!mip.vseip());
+    }
+}
@@ -0,0 +1,34 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine-Level Hypervisor Registers.
+//!
+//! The hypervisor extension also adds state to M-mode: traps from a guest
+//! that are not delegated to HS-mode report the same information in
+//! machine-level CSRs that `htval`/`htinst` provide to a hypervisor. These
+//! registers are only accessible from M-mode and are compiled in with the
+//! `m-mode` feature, for firmware that handles hypervisor-related traps.
+
+/// Hypervisor exception delegation in the machine exception delegation register
+pub mod medeleg;
+/// Hypervisor bits of the machine interrupt enable register
+pub mod mie;
+/// Hypervisor bits of the machine interrupt pending register
+pub mod mip;
+/// Hypervisor fields of the machine status register
+pub mod mstatus;
+/// Machine trap instruction register
+pub mod mtinst;
+/// Machine second trap value register
+pub mod mtval2;
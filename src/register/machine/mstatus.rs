@@ -0,0 +1,134 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hypervisor Fields of the Machine Status Register.
+//!
+//! The hypervisor extension adds two fields to `mstatus` that the `riscv`
+//! crate does not expose. MPV holds the virtualization mode a trap to M-mode
+//! was taken from, and `mret` returns to V=1 when it is set and MPP is not
+//! M-mode. GVA reports whether `mtval` holds a guest virtual address. On RV32
+//! both fields live in `mstatush`.
+
+pub use riscv::register::mstatus::MPP;
+
+#[cfg(target_pointer_width = "64")]
+riscv::read_csr!(0x300);
+#[cfg(target_pointer_width = "64")]
+riscv::set!(0x300);
+#[cfg(target_pointer_width = "64")]
+riscv::clear!(0x300);
+/// `mstatus.GVA`
+#[cfg(target_pointer_width = "64")]
+const GVA: usize = 1 << 38;
+/// `mstatus.MPV`
+#[cfg(target_pointer_width = "64")]
+const MPV: usize = 1 << 39;
+
+#[cfg(target_pointer_width = "32")]
+riscv::read_csr!(0x310);
+#[cfg(target_pointer_width = "32")]
+riscv::set!(0x310);
+#[cfg(target_pointer_width = "32")]
+riscv::clear!(0x310);
+/// `mstatush.GVA`
+#[cfg(target_pointer_width = "32")]
+const GVA: usize = 1 << 6;
+/// `mstatush.MPV`
+#[cfg(target_pointer_width = "32")]
+const MPV: usize = 1 << 7;
+
+/// Returns whether the last trap to M-mode was taken from a virtualized mode.
+#[inline]
+pub fn mpv() -> bool {
+    // SAFETY: Reading mstatus has no side effects
+    unsafe { _read() & MPV != 0 }
+}
+
+/// Returns whether `mtval` holds a guest virtual address.
+#[inline]
+pub fn gva() -> bool {
+    // SAFETY: Reading mstatus has no side effects
+    unsafe { _read() & GVA != 0 }
+}
+
+/// Returns the privilege mode the last trap to M-mode was taken from.
+#[inline]
+pub fn mpp() -> MPP {
+    riscv::register::mstatus::read().mpp()
+}
+
+/// Returns whether `mret` will enter VS-mode or VU-mode.
+#[inline]
+pub fn returns_to_guest() -> bool {
+    mpv() && mpp() != MPP::Machine
+}
+
+/// Sets MPV so that `mret` enters a virtualized mode.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes the mode `mret` returns to.
+#[inline]
+pub unsafe fn set_mpv() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(MPV) };
+}
+
+/// Clears MPV so that `mret` enters a non-virtualized mode.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes the mode `mret` returns to.
+#[inline]
+pub unsafe fn clear_mpv() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(MPV) };
+}
+
+/// Sets GVA to report a guest virtual address in `mtval`.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes how `mtval` is interpreted.
+#[inline]
+pub unsafe fn set_gva() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _set(GVA) };
+}
+
+/// Clears GVA.
+///
+/// # Safety
+///
+/// This function is unsafe because it changes how `mtval` is interpreted.
+#[inline]
+pub unsafe fn clear_gva() {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _clear(GVA) };
+}
+
+/// Prepares `mret` to enter the guest in VS-mode (`vs`) or VU-mode.
+///
+/// # Safety
+///
+/// `mepc` and the hypervisor CSRs must describe a guest that is ready to run.
+#[inline]
+pub unsafe fn set_guest_return(vs: bool) {
+    let mpp = if vs { MPP::Supervisor } else { MPP::User };
+    // SAFETY: Caller ensures the guest is ready to be entered
+    unsafe {
+        riscv::register::mstatus::set_mpp(mpp);
+        set_mpv();
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine Trap Instruction Register.
+//!
+//! On a trap taken to M-mode, `mtinst` holds zero, a transformed version of the
+//! trapping instruction, or a pseudoinstruction for implicit accesses of a
+//! VS-stage page-table walk. The encoding is the same as that of `htinst`, so
+//! [`read`](crate::register::machine::mtinst::read) returns an
+//! [`Htinst`](crate::register::htinst::Htinst) whose
+//! [`decode`](crate::register::htinst::Htinst::decode) and
+//! [`classification`](crate::register::htinst::Htinst::classification) apply
+//! unchanged.
+use crate::register::htinst::Htinst;
+
+riscv::read_csr!(0x34A);
+riscv::write_csr!(0x34A);
+
+/// Reads the transformed instruction of the last trap taken to M-mode.
+#[inline]
+pub fn read() -> Htinst {
+    // SAFETY: Reading mtinst has no side effects
+    Htinst::from_bits(unsafe { _read() })
+}
+
+/// Writes `mtinst`.
+///
+/// # Safety
+///
+/// Must be executed in M-mode.
+#[inline]
+pub unsafe fn write(value: Htinst) {
+    // SAFETY: Caller ensures this is safe to execute
+    unsafe { _write(value.bits()) };
+}
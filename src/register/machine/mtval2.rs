@@ -0,0 +1,32 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Machine Second Trap Value Register.
+//!
+//! On a guest-page fault taken to M-mode, `mtval2` holds the faulting guest
+//! physical address shifted right by two, like `htval` does for HS-mode. It
+//! is zero for other traps.
+use riscv::{read_csr_as_usize, write_csr_as_usize};
+
+read_csr_as_usize!(0x34B);
+write_csr_as_usize!(0x34B);
+
+/// Returns the guest physical address encoded in an `mtval2` value.
+///
+/// The two low bits are always zero, as `mtval2` does not record them. On
+/// RV32 the address can exceed 32 bits, hence the `u64`.
+#[inline]
+pub fn gpa(mtval2: usize) -> u64 {
+    (mtval2 as u64) << 2
+}
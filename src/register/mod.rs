@@ -24,18 +24,28 @@
 //! These registers control hypervisor behavior and guest execution:
 //! - `hstatus` - Hypervisor status register
 //! - `hedeleg` - Hypervisor exception delegation register
+//! - `hedelegh` - Hypervisor exception delegation high register (RV32)
 //! - `hideleg` - Hypervisor interrupt delegation register
 //! - `hie` - Hypervisor interrupt enable register
 //! - `hip` - Hypervisor interrupt pending register
 //! - `hvip` - Hypervisor virtual interrupt pending register
+//! - `hvien` - Hypervisor virtual interrupt enable register (AIA)
+//! - `hvictl` - Hypervisor virtual interrupt control register (AIA)
+//! - `hviprio1`, `hviprio2` - Hypervisor virtual interrupt priority registers (AIA)
+//! - `hidelegh`, `hvienh`, `hviph`, `hviprio1h`, `hviprio2h`, `vsieh`, `vsiph` -
+//!   AIA high-half registers (RV32 with the `aia` feature)
 //! - `hcounteren` - Hypervisor counter enable register
+//! - `henvcfg` - Hypervisor environment configuration register
+//! - `henvcfgh` - Hypervisor environment configuration high register (RV32)
 //! - `hgatp` - Hypervisor guest address translation and protection register
+//! - `hcontext` - Hypervisor context register (Sdtrig)
 //! - `htimedelta` - Hypervisor time delta register
 //! - `htimedeltah` - Hypervisor time delta high register
 //! - `htval` - Hypervisor trap value register
 //! - `htinst` - Hypervisor trap instruction register
 //! - `hgeie` - Hypervisor guest external interrupt enable register
 //! - `hgeip` - Hypervisor guest external interrupt pending register
+//! - `hstateen0`..`hstateen3` - Hypervisor state enable registers (Smstateen)
 //!
 //! ### Virtual Supervisor Registers
 //! These registers manage virtual machine supervisor-level state:
@@ -46,13 +56,42 @@
 //! - `vscause` - Virtual supervisor cause register
 //! - `vstval` - Virtual supervisor trap value register
 //! - `vstvec` - Virtual supervisor trap vector register
+//! - `vstimecmp` - Virtual supervisor timer compare register (Sstc)
+//! - `vstimecmph` - Virtual supervisor timer compare high register (Sstc, RV32)
 //! - `vsscratch` - Virtual supervisor scratch register
+//! - `vstopei` - Virtual supervisor top external interrupt register (AIA)
+//! - `vstopi` - Virtual supervisor top interrupt register (AIA, read-only)
+//! - `vsiselect`, `vsireg` - Virtual supervisor indirect register access (Sscsrind)
 //! - `vsatp` - Virtual supervisor address translation and protection register
+//!
+//! ### Debug/Trace Registers
+//! The [`debug`](crate::register::debug) module provides the Sdtrig trigger
+//! registers and helpers for guest-only hardware breakpoints and watchpoints.
+//!
+//! ### Machine-Level Registers
+//! With the `m-mode` feature, the [`machine`](crate::register::machine) module
+//! provides the hypervisor-related M-mode registers for firmware, such as
+//! `mtval2` and `mtinst`.
+
+mod barrier;
+/// Compile-time-checked CSR access by number
+pub mod csr;
+mod field;
+pub use self::field::*;
 
 // Hypervisor Extension Registers
 /// Hypervisor x64 register implementations
 mod hypervisorx64;
 pub use self::hypervisorx64::*;
 
-// TODO: Debug/Trace Registers (shared with Debug Mode)
+/// Interrupt prioritization and injection helpers
+pub mod interrupts;
+
+/// Debug/trace trigger registers (Sdtrig)
+pub mod debug;
+
+/// Machine-level hypervisor registers for M-mode firmware
+#[cfg(feature = "m-mode")]
+pub mod machine;
+
 // TODO: Debug Mode Registers
@@ -0,0 +1,344 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Boot-Time Hypervisor Self-Test.
+//!
+//! Bring-up on new silicon or emulators tends to fail in confusing ways much
+//! later, the first time a guest runs. [`selftest`](crate::selftest::selftest)
+//! instead exercises the hypervisor extension once at boot and returns a
+//! [`SelftestReport`](crate::selftest::SelftestReport):
+//! - WARL probes of the implemented `hgatp` modes, VMID bits and guest
+//!   interrupt files,
+//! - write/readback checks of registers that must hold any value,
+//! - optionally, a trivial guest entry and exit with Bare translation.
+//!
+//! Every register touched is restored before returning.
+
+use crate::asm::hfence_gvma_all;
+use crate::register::{hgatp, hgeie, hstatus, htimedelta, vsatp, vsscratch};
+use bit_field::BitField;
+use riscv::register::sstatus;
+
+/// Test pattern written to read/write registers.
+const PATTERN: usize = 0x5a5a_a5a5_5a5a_a5a5u64 as usize;
+/// Encoding of the `ECALL` instruction.
+pub const ECALL: u32 = 0x0000_0073;
+/// Trap cause of an environment call from VS-mode.
+const VS_ECALL_CAUSE: usize = 10;
+
+/// Result of a write/readback check.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Readback {
+    /// Value written to the register.
+    pub written: usize,
+    /// Value read back from the register.
+    pub read: usize,
+}
+
+impl Readback {
+    /// Returns whether the register held the written value.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.written == self.read
+    }
+}
+
+/// Result of entering a trivial guest and trapping back out of it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuestEntry {
+    /// No guest entry was attempted.
+    Skipped,
+    /// The guest ran and trapped back with an environment call from VS-mode.
+    Passed,
+    /// The guest trapped back with an unexpected `scause`.
+    Failed {
+        /// Value of `scause` on the exit.
+        scause: usize,
+    },
+}
+
+/// Structured result of [`selftest`].
+#[derive(Copy, Clone, Debug)]
+pub struct SelftestReport {
+    /// Whether `hgatp` accepts the Sv39x4 translation mode.
+    pub sv39x4: bool,
+    /// Whether `hgatp` accepts the Sv48x4 translation mode.
+    pub sv48x4: bool,
+    /// Whether `hgatp` accepts the Sv57x4 translation mode.
+    pub sv57x4: bool,
+    /// Number of implemented VMID bits.
+    pub vmidlen: usize,
+    /// Number of implemented guest interrupt files.
+    pub geilen: usize,
+    /// Readback of `vsscratch`.
+    pub vsscratch: Readback,
+    /// Readback of `htimedelta`.
+    pub htimedelta: Readback,
+    /// Outcome of the guest entry check.
+    pub guest_entry: GuestEntry,
+}
+
+impl SelftestReport {
+    /// Returns whether every check passed.
+    ///
+    /// A hypervisor needs at least one paged G-stage mode and registers that
+    /// hold their values; VMIDs and guest interrupt files are optional.
+    pub fn passed(&self) -> bool {
+        let paged = self.sv39x4 || self.sv48x4 || self.sv57x4;
+        paged
+            && self.vsscratch.passed()
+            && self.htimedelta.passed()
+            && !matches!(self.guest_entry, GuestEntry::Failed { .. })
+    }
+}
+
+/// Runs the hypervisor self-test on the current hart.
+///
+/// `guest_ecall` is the physical address of an [`ECALL`] instruction used for
+/// the guest entry check, or `None` to skip it. With Bare translation the guest
+/// fetches it directly by physical address.
+///
+/// # Safety
+///
+/// Must be called in HS-mode with no guest running on this hart and with
+/// interrupts disabled. `guest_ecall`, if given, must point to an `ECALL`
+/// instruction that PMP allows the guest to execute.
+pub unsafe fn selftest(guest_ecall: Option<usize>) -> SelftestReport {
+    // SAFETY: Caller ensures no guest is using the probed registers
+    unsafe {
+        SelftestReport {
+            sv39x4: probe_hgatp_mode(hgatp::HgatpValues::Sv39x4 as usize),
+            sv48x4: probe_hgatp_mode(hgatp::HgatpValues::Sv48x4 as usize),
+            sv57x4: probe_hgatp_mode(hgatp::HgatpValues::Sv57x4 as usize),
+            vmidlen: hgatp::vmid_len(),
+            geilen: probe_geilen(),
+            vsscratch: readback(vsscratch::read, |x| vsscratch::write(x)),
+            htimedelta: readback(htimedelta::read, |x| htimedelta::write(x)),
+            guest_entry: match guest_ecall {
+                Some(pc) => enter_bare_guest(pc),
+                None => GuestEntry::Skipped,
+            },
+        }
+    }
+}
+
+/// Returns whether `hgatp` accepts the given translation mode.
+///
+/// # Safety
+///
+/// No guest may be running on this hart.
+pub(crate) unsafe fn probe_hgatp_mode(mode: usize) -> bool {
+    let saved = hgatp::read();
+    let probe = hgatp::Hgatp::from_bits(*0usize.set_bits(60..64, mode));
+    // SAFETY: Caller ensures no guest is using hgatp
+    unsafe { probe.write() };
+    let accepted = hgatp::read().bits().get_bits(60..64) == mode;
+    // SAFETY: Restores the original value
+    unsafe { saved.write() };
+    accepted
+}
+
+/// Returns the number of implemented guest interrupt files (GEILEN).
+///
+/// # Safety
+///
+/// Guest external interrupts may be briefly enabled; interrupts must be
+/// disabled on this hart.
+pub(crate) unsafe fn probe_geilen() -> usize {
+    let saved = hgeie::read();
+    // SAFETY: Caller ensures interrupts are disabled
+    unsafe { hgeie::Hgeie::from_bits(usize::MAX).write() };
+    let files = hgeie::read().bits();
+    // SAFETY: Restores the original value
+    unsafe { saved.write() };
+    files.count_ones() as usize
+}
+
+/// Returns whether reading `CSR` succeeds, i.e. the hart implements it.
+///
+/// The read runs under a local trap vector, so a missing CSR shows up as a
+/// caught illegal-instruction exception instead of reaching the kernel's trap
+/// handler. `sepc`, `hstatus` and `sstatus` are restored afterwards.
+///
+/// # Safety
+///
+/// Must be called in HS-mode with interrupts disabled, and reading `CSR` must
+/// have no side effects.
+pub(crate) unsafe fn probe_csr<const CSR: u16>() -> bool {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let present: usize;
+        // SAFETY: Caller ensures the read has no side effects; the local trap
+        // vector catches the exception of a missing CSR
+        unsafe {
+            core::arch::asm!(
+                "csrrci {sstatus}, sstatus, 2",
+                "csrr {hstatus}, hstatus",
+                "csrr {sepc}, sepc",
+                "la {tmp}, 2f",
+                "csrrw {stvec}, stvec, {tmp}",
+                "li {present}, 1",
+                "csrr {tmp}, {csr}",
+                "j 3f",
+                ".align 2",
+                "2:",
+                "li {present}, 0",
+                "3:",
+                "csrw stvec, {stvec}",
+                "csrw sepc, {sepc}",
+                "csrw hstatus, {hstatus}",
+                "csrw sstatus, {sstatus}",
+                present = out(reg) present,
+                sstatus = out(reg) _,
+                hstatus = out(reg) _,
+                sepc = out(reg) _,
+                stvec = out(reg) _,
+                tmp = out(reg) _,
+                csr = const CSR,
+                options(nostack),
+            )
+        };
+        present != 0
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+/// Writes the test pattern to a register, reads it back and restores it.
+fn readback(read: fn() -> usize, write: impl Fn(usize)) -> Readback {
+    let saved = read();
+    write(PATTERN);
+    let result = Readback {
+        written: PATTERN,
+        read: read(),
+    };
+    write(saved);
+    result
+}
+
+/// Enters VS-mode at `pc` with Bare translation and returns how it exited.
+///
+/// # Safety
+///
+/// `pc` must be the physical address of an `ECALL` instruction.
+unsafe fn enter_bare_guest(pc: usize) -> GuestEntry {
+    let saved_hgatp = hgatp::read();
+    let saved_vsatp = vsatp::read();
+    let saved_hstatus = hstatus::read();
+
+    let mut hs = saved_hstatus;
+    hs.set_spv(true);
+    hs.set_spvp(true);
+    // SAFETY: Caller ensures no guest is running on this hart
+    let scause = unsafe {
+        hgatp::Hgatp::bare().write();
+        vsatp::Vsatp::from_bits(0).write();
+        hs.write();
+        sstatus::set_spp(sstatus::SPP::Supervisor);
+        let scause = enter_and_trap(pc);
+        saved_hstatus.write();
+        saved_vsatp.write();
+        saved_hgatp.write();
+        hfence_gvma_all();
+        scause
+    };
+    if scause == VS_ECALL_CAUSE {
+        GuestEntry::Passed
+    } else {
+        GuestEntry::Failed { scause }
+    }
+}
+
+/// Executes `sret` into the guest at `pc` and catches the first trap.
+///
+/// `stvec` temporarily points just past the `sret`, so the guest's `ECALL`
+/// lands back here with every register other than the ones used below intact.
+#[cfg_attr(
+    not(any(target_arch = "riscv32", target_arch = "riscv64")),
+    allow(unused_variables)
+)]
+unsafe fn enter_and_trap(pc: usize) -> usize {
+    #[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+    {
+        let scause: usize;
+        // SAFETY: Caller ensures the guest only executes `ECALL`
+        unsafe {
+            core::arch::asm!(
+                "csrr {saved}, stvec",
+                "la {tmp}, 1f",
+                "csrw stvec, {tmp}",
+                "csrw sepc, {pc}",
+                "sret",
+                ".align 2",
+                "1:",
+                "csrw stvec, {saved}",
+                "csrr {cause}, scause",
+                saved = out(reg) _,
+                tmp = out(reg) _,
+                pc = in(reg) pc,
+                cause = lateout(reg) scause,
+            )
+        };
+        scause
+    }
+    #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+    crate::unsupported_arch();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> SelftestReport {
+        let ok = Readback {
+            written: PATTERN,
+            read: PATTERN,
+        };
+        SelftestReport {
+            sv39x4: true,
+            sv48x4: false,
+            sv57x4: false,
+            vmidlen: 14,
+            geilen: 0,
+            vsscratch: ok,
+            htimedelta: ok,
+            guest_entry: GuestEntry::Skipped,
+        }
+    }
+
+    #[test]
+    fn test_report_passed() {
+        assert!(report().passed());
+
+        let mut r = report();
+        r.sv39x4 = false;
+        assert!(!r.passed());
+
+        let mut r = report();
+        r.htimedelta.read = 0;
+        assert!(!r.passed());
+
+        let mut r = report();
+        r.guest_entry = GuestEntry::Failed { scause: 20 };
+        assert!(!r.passed());
+        r.guest_entry = GuestEntry::Passed;
+        assert!(r.passed());
+    }
+
+    #[test]
+    fn test_ecall_encoding() {
+        assert_eq!(ECALL & 0x7f, 0x73);
+        assert_eq!(ECALL >> 7, 0);
+    }
+}
@@ -0,0 +1,378 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simulated Hypervisor CSR File.
+//!
+//! [`SimCsrs`](crate::sim::SimCsrs) is an in-memory implementation of the
+//! hypervisor and VS-level CSRs behind the [`GuestCsrs`](crate::gdb::GuestCsrs)
+//! access trait, so hypervisor logic written against that trait can be
+//! unit-tested on any host. It models the parts of the CSR semantics that such
+//! logic tends to depend on:
+//!
+//! - writable masks and WARL fields: unimplemented bits read as zero,
+//!   `hgatp`/`vsatp` ignore writes with an unsupported mode, and VMID and
+//!   guest external interrupt bits are limited to VMIDLEN and GEILEN;
+//! - read-only bits: `hgeip` and the derived `hip` bits cannot be written,
+//!   and `vsstatus.SD` reflects the FS/VS/XS fields;
+//! - aliasing: `hip.VSSIP` is `hvip.VSSIP`, `hip.VSEIP` includes the guest
+//!   interrupt file selected by `hstatus.VGEIN`, and `vsip`/`vsie` are the
+//!   delegated VS bits of `hip`/`hie` shifted down by one;
+//! - virtualization: with
+//!   [`SimCsrs::set_virtualized`](crate::sim::SimCsrs::set_virtualized), the
+//!   supervisor CSR numbers (`sstatus`, `sie`, ...) access their VS-level
+//!   counterparts and hypervisor CSRs become inaccessible.
+//!
+//! Guest external interrupts are raised with
+//! [`SimCsrs::set_hgeip`](crate::sim::SimCsrs::set_hgeip).
+//!
+//! The model is that of an RV64 hart.
+
+use crate::diag;
+use crate::gdb::GuestCsrs;
+use crate::register::{henvcfg::Henvcfg, hgatp, hideleg, hie, hip, hstatus, vsie, vsip};
+use bit_field::BitField;
+
+const VSSI: usize = 1 << 2;
+const VSTI: usize = 1 << 6;
+const VSEI: usize = 1 << 10;
+const SGEI: usize = 1 << 12;
+const VS_INTERRUPTS: usize = VSSI | VSTI | VSEI;
+
+const HSTATUS_WRITABLE: usize = (1 << 5)
+    | (1 << 6)
+    | (1 << 7)
+    | (1 << 8)
+    | (1 << 9)
+    | hstatus::Hstatus::VGEIN
+    | (1 << 20)
+    | (1 << 21)
+    | (1 << 22);
+const HEDELEG_WRITABLE: usize = 0b1011_0001_1111_1111;
+const VSSTATUS_WRITABLE: usize =
+    (1 << 1) | (1 << 5) | (1 << 6) | (1 << 8) | (0b11 << 9) | (0b11 << 13) | (1 << 18) | (1 << 19);
+const HENVCFG_WRITABLE: usize = Henvcfg::FIOM
+    | Henvcfg::LPE
+    | Henvcfg::SSE
+    | Henvcfg::CBIE
+    | Henvcfg::CBCFE
+    | Henvcfg::CBZE
+    | Henvcfg::PMM
+    | Henvcfg::ADUE
+    | Henvcfg::PBMTE
+    | Henvcfg::STCE;
+/// `vsstatus.UXL` and `hstatus.VSXL` are fixed to 64 bits.
+const XL64: usize = 2 << 32;
+
+/// In-memory hypervisor CSR file.
+#[derive(Clone, Debug)]
+pub struct SimCsrs {
+    geilen: usize,
+    vmidlen: usize,
+    sv39x4: bool,
+    sv48x4: bool,
+    virtualized: bool,
+    vsstatus: usize,
+    vstvec: usize,
+    vsscratch: usize,
+    vsepc: usize,
+    vscause: usize,
+    vstval: usize,
+    vsatp: usize,
+    hstatus: usize,
+    hedeleg: usize,
+    hideleg: usize,
+    hie: usize,
+    htimedelta: usize,
+    hcounteren: usize,
+    hgeie: usize,
+    henvcfg: usize,
+    htval: usize,
+    hvip: usize,
+    htinst: usize,
+    hgatp: usize,
+    hgeip: usize,
+}
+
+impl Default for SimCsrs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimCsrs {
+    /// Creates a CSR file in its reset state, with GEILEN 7, VMIDLEN 14 and
+    /// support for Sv39x4 and Sv48x4.
+    pub fn new() -> Self {
+        SimCsrs {
+            geilen: 7,
+            vmidlen: 14,
+            sv39x4: true,
+            sv48x4: true,
+            virtualized: false,
+            vsstatus: 0,
+            vstvec: 0,
+            vsscratch: 0,
+            vsepc: 0,
+            vscause: 0,
+            vstval: 0,
+            vsatp: 0,
+            hstatus: 0,
+            hedeleg: 0,
+            hideleg: 0,
+            hie: 0,
+            htimedelta: 0,
+            hcounteren: 0,
+            hgeie: 0,
+            henvcfg: 0,
+            htval: 0,
+            hvip: 0,
+            htinst: 0,
+            hgatp: 0,
+            hgeip: 0,
+        }
+    }
+    /// Sets the number of implemented guest interrupt files.
+    pub fn with_geilen(mut self, geilen: usize) -> Self {
+        self.geilen = geilen;
+        self
+    }
+    /// Sets the number of implemented VMID bits.
+    pub fn with_vmidlen(mut self, vmidlen: usize) -> Self {
+        self.vmidlen = vmidlen;
+        self
+    }
+    /// Sets which `hgatp` translation modes besides Bare are implemented.
+    pub fn with_modes(mut self, sv39x4: bool, sv48x4: bool) -> Self {
+        self.sv39x4 = sv39x4;
+        self.sv48x4 = sv48x4;
+        self
+    }
+    /// Returns whether the simulated hart is in a virtualized mode.
+    #[inline]
+    pub fn virtualized(&self) -> bool {
+        self.virtualized
+    }
+    /// Enters (`true`) or leaves (`false`) a virtualized mode.
+    #[inline]
+    pub fn set_virtualized(&mut self, virtualized: bool) {
+        self.virtualized = virtualized;
+    }
+    /// Sets the pending guest external interrupts, as an IMSIC would.
+    #[inline]
+    pub fn set_hgeip(&mut self, hgeip: usize) {
+        self.hgeip = hgeip & self.geie_mask();
+    }
+
+    fn geie_mask(&self) -> usize {
+        diag::geilen_mask(self.geilen)
+    }
+    fn hip(&self) -> usize {
+        let vgein = hstatus::Hstatus::from_bits(self.hstatus).vgein_raw();
+        let mut hip = self.hvip & VS_INTERRUPTS;
+        if vgein != 0 && self.hgeip.get_bit(vgein) {
+            hip |= VSEI;
+        }
+        if self.hgeip & self.hgeie != 0 {
+            hip |= SGEI;
+        }
+        hip
+    }
+    fn vsstatus(&self) -> usize {
+        let dirty = |field: usize| self.vsstatus & field == field;
+        let sd = dirty(0b11 << 9) || dirty(0b11 << 13) || dirty(0b11 << 15);
+        self.vsstatus | XL64 | ((sd as usize) << 63)
+    }
+    fn mode_supported(&self, mode: usize) -> bool {
+        match mode {
+            0 => true,
+            8 => self.sv39x4,
+            9 => self.sv48x4,
+            _ => false,
+        }
+    }
+    /// Maps a supervisor CSR number to its VS-level counterpart in a
+    /// virtualized mode; returns `None` for CSRs inaccessible in that mode.
+    fn redirect(&self, csr: u16) -> Option<u16> {
+        match (self.virtualized, csr) {
+            (true, 0x100..=0x1ff) => Some(csr + 0x100),
+            (true, 0x200..=0x2ff | 0x600..=0x6ff | 0xe00..=0xeff) => None,
+            (true, _) => Some(csr),
+            (false, 0x100..=0x1ff) => None,
+            (false, _) => Some(csr),
+        }
+    }
+}
+
+impl GuestCsrs for SimCsrs {
+    fn read_csr(&self, csr: u16) -> Option<usize> {
+        let hideleg = hideleg::Hideleg::from_bits(self.hideleg);
+        Some(match self.redirect(csr)? {
+            0x200 => self.vsstatus(),
+            0x204 => vsie::compute_guest_view(hie::Hie::from_bits(self.hie), hideleg).bits(),
+            0x205 => self.vstvec,
+            0x240 => self.vsscratch,
+            0x241 => self.vsepc,
+            0x242 => self.vscause,
+            0x243 => self.vstval,
+            0x244 => vsip::compute_guest_view(hip::Hip::from_bits(self.hip()), hideleg).bits(),
+            0x280 => self.vsatp,
+            0x600 => self.hstatus | XL64,
+            0x602 => self.hedeleg,
+            0x603 => self.hideleg,
+            0x604 => self.hie,
+            0x605 => self.htimedelta,
+            0x606 => self.hcounteren,
+            0x607 => self.hgeie,
+            0x60A => self.henvcfg,
+            0x643 => self.htval,
+            0x644 => self.hip(),
+            0x645 => self.hvip,
+            0x64A => self.htinst,
+            0x680 => self.hgatp,
+            0xE12 => self.hgeip,
+            _ => return None,
+        })
+    }
+
+    fn write_csr(&mut self, csr: u16, value: usize) -> bool {
+        let Some(csr) = self.redirect(csr) else {
+            return false;
+        };
+        let delegated = self.hideleg & VS_INTERRUPTS;
+        match csr {
+            0x200 => self.vsstatus = value & VSSTATUS_WRITABLE,
+            0x204 => self.hie = (self.hie & !delegated) | ((value << 1) & delegated),
+            0x205 => self.vstvec = value & !0b10,
+            0x240 => self.vsscratch = value,
+            0x241 => self.vsepc = value & !1,
+            0x242 => self.vscause = value,
+            0x243 => self.vstval = value,
+            0x244 => {
+                let writable = delegated & VSSI;
+                self.hvip = (self.hvip & !writable) | ((value << 1) & writable);
+            }
+            0x280 => {
+                let mode = value >> 60;
+                if matches!(mode, 0 | 8 | 9) {
+                    self.vsatp = value;
+                }
+            }
+            0x600 => {
+                let mut value = value & HSTATUS_WRITABLE;
+                let vgein = hstatus::Hstatus::from_bits(value).vgein_raw();
+                if vgein > self.geilen {
+                    value &= !hstatus::Hstatus::VGEIN;
+                }
+                self.hstatus = value;
+            }
+            0x602 => self.hedeleg = value & HEDELEG_WRITABLE,
+            0x603 => self.hideleg = value & VS_INTERRUPTS,
+            0x604 => self.hie = value & (VS_INTERRUPTS | SGEI),
+            0x605 => self.htimedelta = value,
+            0x606 => self.hcounteren = value & 0xffff_ffff,
+            0x607 => self.hgeie = value & self.geie_mask(),
+            0x60A => self.henvcfg = value & HENVCFG_WRITABLE,
+            0x643 => self.htval = value,
+            0x644 => self.hvip = (self.hvip & !VSSI) | (value & VSSI),
+            0x645 => self.hvip = value & VS_INTERRUPTS,
+            0x64A => self.htinst = value,
+            0x680 => {
+                let mut hgatp = hgatp::Hgatp::from_bits(value);
+                if !self.mode_supported(value >> 60) {
+                    return true;
+                }
+                hgatp.set_vmid(hgatp.vmid() & ((1 << self.vmidlen) - 1));
+                self.hgatp = hgatp.bits();
+            }
+            // hgeip is read-only
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diag::{InterruptState, Issue, VsInterrupt};
+
+    extern crate std;
+    use std::vec::Vec;
+
+    #[test]
+    fn test_warl_and_read_only() {
+        let mut csrs = SimCsrs::new().with_vmidlen(7).with_modes(true, false);
+        assert!(csrs.write_csr(0x602, usize::MAX));
+        assert_eq!(csrs.read_csr(0x602), Some(HEDELEG_WRITABLE));
+
+        // Unsupported mode: the whole write is ignored
+        let sv39 = (8 << 60) | (0x3fff << 44) | 0x1234;
+        assert!(csrs.write_csr(0x680, sv39));
+        assert!(csrs.write_csr(0x680, 9 << 60));
+        let hgatp = hgatp::Hgatp::from_bits(csrs.read_csr(0x680).unwrap());
+        assert_eq!(hgatp.vmid(), 0x7f);
+        assert_eq!(hgatp.ppn(), 0x1234);
+
+        assert!(!csrs.write_csr(0xE12, 1));
+        csrs.set_hgeip(usize::MAX);
+        assert_eq!(csrs.read_csr(0xE12), Some(0xfe));
+
+        assert!(csrs.write_csr(0x200, usize::MAX));
+        let vss = csrs.read_csr(0x200).unwrap();
+        assert_eq!(vss & !(VSSTATUS_WRITABLE | XL64), 1 << 63);
+    }
+
+    #[test]
+    fn test_without_guest_interrupt_files() {
+        let mut csrs = SimCsrs::new().with_geilen(0);
+        assert!(csrs.write_csr(0x607, usize::MAX));
+        assert_eq!(csrs.read_csr(0x607), Some(0));
+        csrs.set_hgeip(usize::MAX);
+        assert_eq!(csrs.read_csr(0xE12), Some(0));
+    }
+
+    #[test]
+    fn test_interrupt_aliasing() {
+        let mut csrs = SimCsrs::new();
+        csrs.write_csr(0x603, VSSI | VSEI);
+        csrs.write_csr(0x645, VSSI | VSTI);
+        assert_eq!(csrs.read_csr(0x644), Some(VSSI | VSTI));
+        // vsip only shows delegated interrupts
+        assert_eq!(csrs.read_csr(0x244), Some(VSSI >> 1));
+
+        // Guest interrupt file 3 drives VSEIP through hstatus.VGEIN
+        csrs.set_hgeip(1 << 3);
+        let mut hstatus = hstatus::Hstatus::from_bits(0);
+        hstatus.set_vgein(3);
+        csrs.write_csr(0x600, hstatus.bits());
+        assert_eq!(csrs.read_csr(0x644).unwrap() & VSEI, VSEI);
+        assert_eq!(csrs.read_csr(0x244).unwrap() & (VSEI >> 1), VSEI >> 1);
+
+        // The guest clears its software interrupt through sip
+        csrs.set_virtualized(true);
+        assert!(csrs.write_csr(0x144, 0));
+        assert!(csrs.read_csr(0x600).is_none());
+        csrs.set_virtualized(false);
+        assert_eq!(csrs.read_csr(0x645), Some(VSTI));
+    }
+
+    #[test]
+    fn test_diagnostics_on_simulated_state() {
+        let mut csrs = SimCsrs::new();
+        csrs.write_csr(0x645, VSTI);
+        let state = InterruptState::capture_from(&csrs, 7);
+        let issues: Vec<_> = state.check().collect();
+        assert_eq!(issues, [Issue::InjectedUnreachable(VsInterrupt::Timer)]);
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! VM-Exit Statistics.
+//!
+//! [`ExitStats`](crate::stats::ExitStats) counts guest exits and the time spent
+//! handling them per [`GuestTrap`](crate::trap::GuestTrap) kind. Each hart
+//! keeps its own instance, so recording needs no atomics;
+//! [`ExitStats::merge`](crate::stats::ExitStats::merge) combines the per-hart
+//! instances for a report.
+
+use crate::trap::GuestTrap;
+use core::fmt;
+
+/// Exit count and handling time of one trap kind.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExitCounter {
+    /// Number of exits.
+    pub count: u64,
+    /// Total handling time, in the caller's time unit.
+    pub ticks: u64,
+}
+
+impl ExitCounter {
+    /// Returns the mean handling time per exit, or 0 without exits.
+    #[inline]
+    pub fn mean_ticks(&self) -> u64 {
+        self.ticks.checked_div(self.count).unwrap_or(0)
+    }
+}
+
+/// Per-hart VM-exit statistics indexed by [`GuestTrap::index`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExitStats {
+    counters: [ExitCounter; GuestTrap::KINDS],
+}
+
+impl ExitStats {
+    /// Creates empty statistics.
+    pub const fn new() -> Self {
+        ExitStats {
+            counters: [ExitCounter { count: 0, ticks: 0 }; GuestTrap::KINDS],
+        }
+    }
+    /// Records an exit that took `ticks` to handle.
+    #[inline]
+    pub fn record(&mut self, trap: &GuestTrap, ticks: u64) {
+        let counter = &mut self.counters[trap.index()];
+        counter.count = counter.count.wrapping_add(1);
+        counter.ticks = counter.ticks.wrapping_add(ticks);
+    }
+    /// Returns the counter of the trap kind with the given index.
+    #[inline]
+    pub fn get(&self, index: usize) -> ExitCounter {
+        self.counters[index]
+    }
+    /// Returns the total number of exits.
+    pub fn total(&self) -> u64 {
+        self.counters.iter().map(|c| c.count).sum()
+    }
+    /// Adds the counters of `other`, e.g. from another hart.
+    pub fn merge(&mut self, other: &ExitStats) {
+        for (a, b) in self.counters.iter_mut().zip(&other.counters) {
+            a.count = a.count.wrapping_add(b.count);
+            a.ticks = a.ticks.wrapping_add(b.ticks);
+        }
+    }
+    /// Resets all counters to zero.
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+    /// Returns `(name, counter)` for every trap kind.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, ExitCounter)> + '_ {
+        GuestTrap::NAMES.into_iter().zip(self.counters)
+    }
+}
+
+impl fmt::Display for ExitStats {
+    /// Formats one line per trap kind with exits, busiest first.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut order: [usize; GuestTrap::KINDS] = core::array::from_fn(|i| i);
+        order.sort_unstable_by_key(|&i| core::cmp::Reverse(self.counters[i].ticks));
+        writeln!(
+            f,
+            "{:<20} {:>12} {:>16} {:>10}",
+            "exit", "count", "ticks", "mean"
+        )?;
+        for i in order {
+            let c = self.counters[i];
+            if c.count != 0 {
+                writeln!(
+                    f,
+                    "{:<20} {:>12} {:>16} {:>10}",
+                    GuestTrap::NAMES[i],
+                    c.count,
+                    c.ticks,
+                    c.mean_ticks()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    extern crate std;
+    use std::format;
+
+    #[test]
+    fn test_record_and_merge() {
+        let mut hart0 = ExitStats::new();
+        hart0.record(&GuestTrap::EnvCall, 100);
+        hart0.record(&GuestTrap::EnvCall, 300);
+        let mut hart1 = ExitStats::new();
+        hart1.record(&GuestTrap::Interrupt(5), 50);
+
+        hart0.merge(&hart1);
+        let ecall = hart0.get(GuestTrap::EnvCall.index());
+        assert_eq!(
+            ecall,
+            ExitCounter {
+                count: 2,
+                ticks: 400
+            }
+        );
+        assert_eq!(ecall.mean_ticks(), 200);
+        assert_eq!(hart0.total(), 3);
+        assert_eq!(
+            hart0.iter().next(),
+            Some((
+                "interrupt",
+                ExitCounter {
+                    count: 1,
+                    ticks: 50
+                }
+            ))
+        );
+
+        hart0.reset();
+        assert_eq!(hart0.total(), 0);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut stats = ExitStats::new();
+        stats.record(&GuestTrap::Interrupt(9), 10);
+        stats.record(&GuestTrap::VirtualInstruction { insn: 0 }, 90);
+        let report = format!("{}", stats);
+        let lines: std::vec::Vec<_> = report.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("virtual-instruction"));
+        assert!(lines[2].starts_with("interrupt"));
+    }
+
+    #[test]
+    fn test_index_matches_names() {
+        assert_eq!(GuestTrap::EnvCall.name(), "ecall");
+        assert_eq!(
+            GuestTrap::Exception { code: 2, tval: 0 }.index(),
+            GuestTrap::KINDS - 1
+        );
+    }
+}
@@ -0,0 +1,99 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Atomic Multi-CSR Updates.
+//!
+//! Some hypervisor operations only make sense if several CSRs change together:
+//! migrating a vCPU moves its exception and interrupt delegation, its VS-level
+//! interrupt enables and its pending virtual interrupts at once. A host
+//! interrupt handler running in between would observe, and possibly act on,
+//! a mix of the old and new state.
+//!
+//! The helpers here take a
+//! [`CriticalSection`](critical_section::CriticalSection) token, so they can
+//! only be called with host interrupts masked by whatever `critical-section`
+//! implementation the platform provides.
+
+use crate::register::{hedeleg, hideleg, hie, hvip};
+use critical_section::CriticalSection;
+
+/// Delegation and virtual interrupt state that moves with a vCPU.
+#[derive(Copy, Clone, Debug)]
+pub struct InterruptRouting {
+    /// Exception delegation.
+    pub hedeleg: hedeleg::Hedeleg,
+    /// Interrupt delegation.
+    pub hideleg: hideleg::Hideleg,
+    /// VS-level and guest external interrupt enables.
+    pub hie: hie::Hie,
+    /// Pending virtual interrupts.
+    pub hvip: hvip::Hvip,
+}
+
+impl InterruptRouting {
+    /// Reads the routing state of the current hart.
+    #[inline]
+    pub fn capture(_cs: CriticalSection<'_>) -> Self {
+        InterruptRouting {
+            hedeleg: hedeleg::read(),
+            hideleg: hideleg::read(),
+            hie: hie::read(),
+            hvip: hvip::read(),
+        }
+    }
+    /// Installs the routing state on the current hart.
+    ///
+    /// # Safety
+    ///
+    /// Changes which mode handles guest traps; the state must belong to the
+    /// vCPU about to run on this hart.
+    pub unsafe fn apply(&self, _cs: CriticalSection<'_>) {
+        // SAFETY: Caller ensures the state belongs to this hart's vCPU
+        unsafe {
+            self.hedeleg.write();
+            self.hideleg.write();
+            self.hvip.write();
+            self.hie.write();
+        }
+    }
+    /// Reads the routing state inside a critical section.
+    #[inline]
+    pub fn capture_atomic() -> Self {
+        critical_section::with(Self::capture)
+    }
+    /// Installs the routing state inside a critical section.
+    ///
+    /// # Safety
+    ///
+    /// See [`InterruptRouting::apply`].
+    #[inline]
+    pub unsafe fn apply_atomic(&self) {
+        // SAFETY: Caller upholds the contract of `apply`
+        critical_section::with(|cs| unsafe { self.apply(cs) })
+    }
+}
+
+/// Replaces the routing state of the current hart and returns the previous one.
+///
+/// # Safety
+///
+/// See [`InterruptRouting::apply`].
+pub unsafe fn swap_routing(new: &InterruptRouting) -> InterruptRouting {
+    critical_section::with(|cs| {
+        let old = InterruptRouting::capture(cs);
+        // SAFETY: Caller upholds the contract of `apply`
+        unsafe { new.apply(cs) };
+        old
+    })
+}
@@ -0,0 +1,468 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest Timer Virtualization.
+//!
+//! The [`GuestClock`](crate::timer::GuestClock) trait hides how a guest's
+//! supervisor timer is provided, so timer virtualization code works across
+//! hardware generations:
+//! - [`SstcClock`](crate::timer::SstcClock) programs `vstimecmp` directly on
+//!   Sstc-capable harts.
+//! - [`EmulatedClock`](crate::timer::EmulatedClock) emulates the SBI timer
+//!   without Sstc, translating guest deadlines into host time through
+//!   `htimedelta` and injecting VSTIP via `hvip` when the backing
+//!   [`HostTimer`](crate::timer::HostTimer) fires.
+//!
+//! When Sstc is absent and the hypervisor needs the single hardware timer for
+//! its own deadlines too, [`TimerArbiter`](crate::timer::TimerArbiter)
+//! multiplexes it between HS-mode deadlines and the guest deadline.
+//!
+//! All times are in ticks of the `time` CSR. Guest time is host time plus
+//! `htimedelta` (modulo 2^64).
+
+use crate::register::{htimedelta, hvip, vstimecmp};
+use core::cmp::min;
+use riscv::register::time;
+
+/// Deadline value that never fires.
+pub const NEVER: u64 = u64::MAX;
+
+/// A guest's supervisor timer.
+pub trait GuestClock {
+    /// Returns the current guest time.
+    fn now(&self) -> u64;
+    /// Arms the timer to fire at guest time `deadline`.
+    ///
+    /// Any previously armed deadline is replaced.
+    fn set_timer(&mut self, deadline: u64);
+    /// Disarms the timer.
+    fn cancel(&mut self);
+}
+
+/// Host timer backing an [`EmulatedClock`].
+pub trait HostTimer {
+    /// Programs the host timer to fire at host time `deadline`.
+    ///
+    /// A deadline of [`NEVER`] disarms the timer.
+    fn program(&mut self, deadline: u64);
+}
+
+/// Reads the current guest time.
+#[inline]
+pub fn guest_time() -> u64 {
+    time::read64().wrapping_add(htimedelta::read64())
+}
+
+/// Converts a guest deadline to host time given the guest's `htimedelta`.
+#[inline]
+pub fn guest_to_host(deadline: u64, delta: u64) -> u64 {
+    if deadline == NEVER {
+        NEVER
+    } else {
+        deadline.wrapping_sub(delta)
+    }
+}
+
+/// Guest timer backed by the Sstc `vstimecmp` register.
+#[derive(Debug)]
+pub struct SstcClock {
+    _priv: (),
+}
+
+impl SstcClock {
+    /// Creates a clock driving the current hart's `vstimecmp`.
+    ///
+    /// # Safety
+    ///
+    /// The hart must implement Sstc with `henvcfg.STCE` set, and the guest
+    /// owning this clock must be the one currently loaded on the hart.
+    #[inline]
+    pub unsafe fn new() -> Self {
+        SstcClock { _priv: () }
+    }
+}
+
+impl GuestClock for SstcClock {
+    #[inline]
+    fn now(&self) -> u64 {
+        guest_time()
+    }
+    fn set_timer(&mut self, deadline: u64) {
+        // SAFETY: `SstcClock::new` guarantees Sstc is usable
+        unsafe { vstimecmp::write64(deadline) };
+    }
+    #[inline]
+    fn cancel(&mut self) {
+        self.set_timer(NEVER);
+    }
+}
+
+/// Guest timer emulated with a host timer, for harts without Sstc.
+///
+/// Call [`EmulatedClock::expire`] from the host timer interrupt handler to
+/// raise the guest timer interrupt once the deadline has passed.
+#[derive(Debug)]
+pub struct EmulatedClock<T: HostTimer> {
+    host: T,
+    deadline: u64,
+}
+
+impl<T: HostTimer> EmulatedClock<T> {
+    /// Creates a disarmed clock on top of `host`.
+    ///
+    /// # Safety
+    ///
+    /// The guest owning this clock must be the one currently loaded on the
+    /// hart, as the clock reads `htimedelta` and writes `hvip`.
+    #[inline]
+    pub unsafe fn new(host: T) -> Self {
+        EmulatedClock {
+            host,
+            deadline: NEVER,
+        }
+    }
+    /// Returns the armed guest deadline, if any.
+    #[inline]
+    pub fn deadline(&self) -> Option<u64> {
+        (self.deadline != NEVER).then_some(self.deadline)
+    }
+    /// Returns the backing host timer.
+    #[inline]
+    pub fn host(&mut self) -> &mut T {
+        &mut self.host
+    }
+    /// Handles a host timer interrupt.
+    ///
+    /// Injects VSTIP and disarms the clock if the guest deadline has passed;
+    /// returns whether the interrupt was injected.
+    pub fn expire(&mut self) -> bool {
+        if self.deadline == NEVER || guest_time() < self.deadline {
+            return false;
+        }
+        self.deadline = NEVER;
+        self.host.program(NEVER);
+        // SAFETY: `EmulatedClock::new` guarantees this guest owns `hvip`
+        unsafe { hvip::set_vstip() };
+        true
+    }
+}
+
+impl<T: HostTimer> GuestClock for EmulatedClock<T> {
+    #[inline]
+    fn now(&self) -> u64 {
+        guest_time()
+    }
+    fn set_timer(&mut self, deadline: u64) {
+        // Per the SBI specification, setting a new deadline clears any
+        // pending timer interrupt.
+        // SAFETY: `EmulatedClock::new` guarantees this guest owns `hvip`
+        unsafe { hvip::clear_vstip() };
+        self.deadline = deadline;
+        let host_deadline = guest_to_host(deadline, htimedelta::read64());
+        self.host.program(host_deadline);
+    }
+    fn cancel(&mut self) {
+        // SAFETY: `EmulatedClock::new` guarantees this guest owns `hvip`
+        unsafe { hvip::clear_vstip() };
+        self.deadline = NEVER;
+        self.host.program(NEVER);
+    }
+}
+
+/// Deadlines that expired on a host timer interrupt.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Expired {
+    /// An HS-mode deadline expired.
+    pub host: bool,
+    /// The guest deadline expired and VSTIP was injected.
+    pub guest: bool,
+}
+
+/// Multiplexes one hardware timer between HS-mode and guest deadlines.
+///
+/// The underlying [`HostTimer`] is always programmed with the earlier of the
+/// two deadlines. Call [`TimerArbiter::handle_interrupt`] from the supervisor
+/// timer interrupt handler; it injects VSTIP when the guest deadline passes and
+/// reports whether the hypervisor's own deadline expired.
+///
+/// The arbiter acts as the [`GuestClock`] of the guest currently loaded on the
+/// hart; switch guests by re-arming it with the incoming guest's deadline.
+#[derive(Debug)]
+pub struct TimerArbiter<T: HostTimer> {
+    timer: T,
+    host_deadline: u64,
+    /// Guest deadline converted to host time.
+    guest_deadline: u64,
+    programmed: u64,
+}
+
+impl<T: HostTimer> TimerArbiter<T> {
+    /// Creates an arbiter with no deadlines armed.
+    ///
+    /// # Safety
+    ///
+    /// The arbiter must be the only user of `timer` on this hart, and the
+    /// guest it serves must be the one currently loaded, as it reads
+    /// `htimedelta` and writes `hvip`.
+    #[inline]
+    pub unsafe fn new(timer: T) -> Self {
+        TimerArbiter {
+            timer,
+            host_deadline: NEVER,
+            guest_deadline: NEVER,
+            programmed: NEVER,
+        }
+    }
+    /// Returns the armed HS-mode deadline, if any.
+    #[inline]
+    pub fn host_deadline(&self) -> Option<u64> {
+        (self.host_deadline != NEVER).then_some(self.host_deadline)
+    }
+    /// Returns the armed guest deadline in host time, if any.
+    #[inline]
+    pub fn guest_deadline(&self) -> Option<u64> {
+        (self.guest_deadline != NEVER).then_some(self.guest_deadline)
+    }
+    /// Arms the HS-mode deadline at host time `deadline`.
+    #[inline]
+    pub fn set_host_timer(&mut self, deadline: u64) {
+        self.host_deadline = deadline;
+        self.reprogram();
+    }
+    /// Disarms the HS-mode deadline.
+    #[inline]
+    pub fn cancel_host_timer(&mut self) {
+        self.set_host_timer(NEVER);
+    }
+    /// Arms the guest deadline, already converted to host time.
+    #[inline]
+    pub fn set_guest_host_deadline(&mut self, deadline: u64) {
+        self.guest_deadline = deadline;
+        self.reprogram();
+    }
+    /// Expires all deadlines at or before host time `now` and reprograms the
+    /// hardware timer for the remaining ones.
+    ///
+    /// This does not inject VSTIP; see [`TimerArbiter::handle_interrupt`].
+    pub fn expire(&mut self, now: u64) -> Expired {
+        let expired = Expired {
+            host: self.host_deadline <= now,
+            guest: self.guest_deadline <= now,
+        };
+        if expired.host {
+            self.host_deadline = NEVER;
+        }
+        if expired.guest {
+            self.guest_deadline = NEVER;
+        }
+        self.reprogram();
+        expired
+    }
+    /// Handles a supervisor timer interrupt.
+    ///
+    /// Injects VSTIP into the guest if its deadline has passed.
+    pub fn handle_interrupt(&mut self) -> Expired {
+        let expired = self.expire(time::read64());
+        if expired.guest {
+            // SAFETY: `TimerArbiter::new` guarantees this guest owns `hvip`
+            unsafe { hvip::set_vstip() };
+        }
+        expired
+    }
+    fn reprogram(&mut self) {
+        let next = min(self.host_deadline, self.guest_deadline);
+        if next != self.programmed {
+            self.timer.program(next);
+            self.programmed = next;
+        }
+    }
+}
+
+impl<T: HostTimer> GuestClock for TimerArbiter<T> {
+    #[inline]
+    fn now(&self) -> u64 {
+        guest_time()
+    }
+    fn set_timer(&mut self, deadline: u64) {
+        // SAFETY: `TimerArbiter::new` guarantees this guest owns `hvip`
+        unsafe { hvip::clear_vstip() };
+        self.set_guest_host_deadline(guest_to_host(deadline, htimedelta::read64()));
+    }
+    fn cancel(&mut self) {
+        // SAFETY: `TimerArbiter::new` guarantees this guest owns `hvip`
+        unsafe { hvip::clear_vstip() };
+        self.set_guest_host_deadline(NEVER);
+    }
+}
+
+/// Host timer programmed through the HS-mode `stimecmp` register (Sstc).
+#[derive(Copy, Clone, Debug, Default)]
+pub struct StimecmpTimer;
+
+impl HostTimer for StimecmpTimer {
+    #[cfg_attr(
+        not(any(target_arch = "riscv32", target_arch = "riscv64")),
+        allow(unused_variables)
+    )]
+    fn program(&mut self, deadline: u64) {
+        #[cfg(target_arch = "riscv64")]
+        // SAFETY: stimecmp only affects the supervisor timer
+        unsafe {
+            core::arch::asm!("csrw 0x14D, {0}", in(reg) deadline as usize, options(nostack));
+        }
+        #[cfg(target_arch = "riscv32")]
+        // SAFETY: stimecmp only affects the supervisor timer
+        unsafe {
+            core::arch::asm!(
+                "csrw 0x14D, {max}",
+                "csrw 0x15D, {hi}",
+                "csrw 0x14D, {lo}",
+                max = in(reg) usize::MAX,
+                hi = in(reg) (deadline >> 32) as usize,
+                lo = in(reg) deadline as usize,
+                options(nostack),
+            );
+        }
+        #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+        crate::unsupported_arch();
+    }
+}
+
+/// Host timer programmed through the SBI TIME extension.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SbiTimer;
+
+/// SBI TIME extension ID ("TIME").
+#[cfg(any(target_arch = "riscv32", target_arch = "riscv64"))]
+const SBI_EXT_TIME: usize = 0x5449_4D45;
+
+impl HostTimer for SbiTimer {
+    #[cfg_attr(
+        not(any(target_arch = "riscv32", target_arch = "riscv64")),
+        allow(unused_variables)
+    )]
+    fn program(&mut self, deadline: u64) {
+        #[cfg(target_arch = "riscv64")]
+        // SAFETY: sbi_set_timer only affects the supervisor timer
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                inlateout("a0") deadline as usize => _,
+                lateout("a1") _,
+                in("a6") 0usize,
+                in("a7") SBI_EXT_TIME,
+                options(nostack),
+            );
+        }
+        #[cfg(target_arch = "riscv32")]
+        // SAFETY: sbi_set_timer only affects the supervisor timer
+        unsafe {
+            core::arch::asm!(
+                "ecall",
+                inlateout("a0") deadline as usize => _,
+                inlateout("a1") (deadline >> 32) as usize => _,
+                in("a6") 0usize,
+                in("a7") SBI_EXT_TIME,
+                options(nostack),
+            );
+        }
+        #[cfg(not(any(target_arch = "riscv32", target_arch = "riscv64")))]
+        crate::unsupported_arch();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MockTimer {
+        programmed: [u64; 8],
+        count: usize,
+    }
+
+    impl HostTimer for MockTimer {
+        fn program(&mut self, deadline: u64) {
+            self.programmed[self.count] = deadline;
+            self.count += 1;
+        }
+    }
+
+    impl MockTimer {
+        fn last(&self) -> u64 {
+            self.programmed[self.count - 1]
+        }
+    }
+
+    #[test]
+    fn test_arbiter_programs_earliest() {
+        let mut arb = unsafe { TimerArbiter::new(MockTimer::default()) };
+        arb.set_host_timer(500);
+        assert_eq!(arb.timer.last(), 500);
+        arb.set_guest_host_deadline(300);
+        assert_eq!(arb.timer.last(), 300);
+        // A later guest deadline leaves the host deadline in charge
+        arb.set_guest_host_deadline(900);
+        assert_eq!(arb.timer.last(), 500);
+        assert_eq!(arb.guest_deadline(), Some(900));
+    }
+
+    #[test]
+    fn test_arbiter_skips_redundant_programming() {
+        let mut arb = unsafe { TimerArbiter::new(MockTimer::default()) };
+        arb.set_host_timer(500);
+        arb.set_guest_host_deadline(700);
+        arb.set_guest_host_deadline(800);
+        assert_eq!(arb.timer.count, 1);
+    }
+
+    #[test]
+    fn test_arbiter_expire() {
+        let mut arb = unsafe { TimerArbiter::new(MockTimer::default()) };
+        arb.set_host_timer(500);
+        arb.set_guest_host_deadline(300);
+
+        assert_eq!(arb.expire(299), Expired::default());
+
+        let expired = arb.expire(300);
+        assert!(expired.guest && !expired.host);
+        assert_eq!(arb.guest_deadline(), None);
+        assert_eq!(arb.timer.last(), 500);
+
+        let expired = arb.expire(600);
+        assert!(expired.host && !expired.guest);
+        assert_eq!(arb.host_deadline(), None);
+        assert_eq!(arb.timer.last(), NEVER);
+    }
+
+    #[test]
+    fn test_arbiter_expire_both() {
+        let mut arb = unsafe { TimerArbiter::new(MockTimer::default()) };
+        arb.set_host_timer(100);
+        arb.set_guest_host_deadline(100);
+        let expired = arb.expire(100);
+        assert!(expired.host && expired.guest);
+        assert_eq!(arb.timer.last(), NEVER);
+    }
+
+    #[test]
+    fn test_guest_to_host() {
+        assert_eq!(guest_to_host(1000, 0), 1000);
+        assert_eq!(guest_to_host(1000, 400), 600);
+        // Negative delta (guest time behind host time)
+        assert_eq!(guest_to_host(1000, (-500i64) as u64), 1500);
+        // Disarming is preserved regardless of the delta
+        assert_eq!(guest_to_host(NEVER, 12345), NEVER);
+    }
+}
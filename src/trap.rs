@@ -0,0 +1,414 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Guest Trap Decoding.
+//!
+//! A trap taken from VS-mode or VU-mode into HS-mode is described by several
+//! CSRs: `scause`, `stval`, `htval`, `htinst` and `hstatus`.
+//! [`TrapInfo`](crate::trap::TrapInfo) captures them verbatim and
+//! [`TrapInfo::decode`](crate::trap::TrapInfo::decode) turns them into a
+//! [`GuestTrap`](crate::trap::GuestTrap) the hypervisor can dispatch on.
+
+use crate::register::{hstatus, htinst, htval};
+use core::fmt;
+use riscv::register::{scause, stval};
+
+/// Exception code of an environment call from VS-mode.
+pub const VIRTUAL_SUPERVISOR_ENV_CALL: usize = 10;
+/// Exception code of an instruction guest-page fault.
+pub const INSTRUCTION_GUEST_PAGE_FAULT: usize = 20;
+/// Exception code of a load guest-page fault.
+pub const LOAD_GUEST_PAGE_FAULT: usize = 21;
+/// Exception code of a virtual instruction exception.
+pub const VIRTUAL_INSTRUCTION: usize = 22;
+/// Exception code of a store/AMO guest-page fault.
+pub const STORE_GUEST_PAGE_FAULT: usize = 23;
+
+/// Raw trap CSR values of a trap taken into HS-mode.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrapInfo {
+    /// Value of `scause`.
+    pub scause: usize,
+    /// Value of `stval`.
+    pub stval: usize,
+    /// Value of `htval`.
+    pub htval: usize,
+    /// Value of `htinst`.
+    pub htinst: usize,
+    /// Value of `hstatus`.
+    pub hstatus: usize,
+}
+
+impl TrapInfo {
+    /// Reads the trap CSRs.
+    ///
+    /// Must be called from the trap handler before another trap overwrites
+    /// them.
+    pub fn capture() -> Self {
+        TrapInfo {
+            scause: scause::read().bits(),
+            stval: stval::read(),
+            htval: htval::read().bits(),
+            htinst: htinst::read().bits(),
+            hstatus: hstatus::read().bits(),
+        }
+    }
+    /// Returns whether the trap was taken from VS-mode or VU-mode.
+    #[inline]
+    pub fn from_guest(&self) -> bool {
+        hstatus::Hstatus::from_bits(self.hstatus).spv()
+    }
+    /// Returns whether `scause` reports an interrupt.
+    #[inline]
+    pub fn is_interrupt(&self) -> bool {
+        self.scause >> (usize::BITS - 1) != 0
+    }
+    /// Returns the interrupt or exception code.
+    #[inline]
+    pub fn code(&self) -> usize {
+        self.scause & !(1 << (usize::BITS - 1))
+    }
+    /// Decodes the trap.
+    pub fn decode(&self) -> GuestTrap {
+        let code = self.code();
+        if self.is_interrupt() {
+            return GuestTrap::Interrupt(code);
+        }
+        let access = match code {
+            VIRTUAL_SUPERVISOR_ENV_CALL => return GuestTrap::EnvCall,
+            VIRTUAL_INSTRUCTION => return GuestTrap::VirtualInstruction { insn: self.stval },
+            INSTRUCTION_GUEST_PAGE_FAULT => FaultAccess::Fetch,
+            LOAD_GUEST_PAGE_FAULT => FaultAccess::Load,
+            STORE_GUEST_PAGE_FAULT => FaultAccess::Store,
+            _ => {
+                return GuestTrap::Exception {
+                    code,
+                    tval: self.stval,
+                };
+            }
+        };
+        GuestTrap::GuestPageFault(GuestPageFault {
+            access,
+            stval: self.stval,
+            htval: self.htval,
+            htinst: self.htinst,
+            gva: hstatus::Hstatus::from_bits(self.hstatus).gva(),
+        })
+    }
+}
+
+/// Kind of access that caused a guest-page fault.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FaultAccess {
+    /// Instruction fetch.
+    Fetch,
+    /// Load.
+    Load,
+    /// Store or AMO.
+    Store,
+}
+
+/// A G-stage translation fault.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GuestPageFault {
+    access: FaultAccess,
+    stval: usize,
+    htval: usize,
+    htinst: usize,
+    gva: bool,
+}
+
+impl GuestPageFault {
+    /// Returns the kind of the faulting access.
+    #[inline]
+    pub fn access(&self) -> FaultAccess {
+        self.access
+    }
+    /// Returns the faulting guest virtual address, if `stval` holds one.
+    #[inline]
+    pub fn gva(&self) -> Option<usize> {
+        self.gva.then_some(self.stval)
+    }
+    /// Returns the value of `stval`.
+    #[inline]
+    pub fn stval(&self) -> usize {
+        self.stval
+    }
+    /// Returns the faulting guest physical address as reported by `htval`.
+    ///
+    /// `htval` holds the address shifted right by two, so the two low bits
+    /// are always zero here.
+    #[inline]
+    pub fn gpa(&self) -> u64 {
+        htval::Htval::from_bits(self.htval).guest_physical_address()
+    }
+    /// Returns the transformed instruction reported in `htinst`.
+    #[inline]
+    pub fn htinst(&self) -> usize {
+        self.htinst
+    }
+    /// Returns whether the fault was raised by an implicit access of the
+    /// VS-stage page-table walk, as reported by a pseudoinstruction in `htinst`.
+    #[inline]
+    pub fn is_implicit(&self) -> bool {
+        htinst::Htinst::from_bits(self.htinst).classification() == htinst::Classification::Pseudo
+    }
+    /// Returns the faulting guest physical address including its low bits.
+    ///
+    /// `htval` drops the two low bits of the address, and some implementations
+    /// only report the page number. Implicit VS-stage page-table accesses are
+    /// naturally aligned, so their address is exact; `stval` then holds the
+    /// guest virtual address of the original access, not of the page-table
+    /// entry, and is ignored. Otherwise, when `stval` holds the faulting guest
+    /// virtual address, its page offset is also the page offset of the guest
+    /// physical address and the result is exact. In the remaining cases the
+    /// result is flagged with the precision `htval` provides.
+    pub fn full_gpa(&self) -> FullGpa {
+        const PAGE_OFFSET: u64 = 0xfff;
+        let gpa = self.gpa();
+        let (addr, precision) = match self.gva() {
+            _ if self.is_implicit() => (gpa, GpaPrecision::Exact),
+            Some(gva) => (
+                (gpa & !PAGE_OFFSET) | (gva as u64 & PAGE_OFFSET),
+                GpaPrecision::Exact,
+            ),
+            None if gpa & PAGE_OFFSET != 0 => (gpa, GpaPrecision::WordAligned),
+            None => (gpa, GpaPrecision::PageAligned),
+        };
+        FullGpa { addr, precision }
+    }
+}
+
+/// How much of a reconstructed guest physical address is known.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GpaPrecision {
+    /// Every bit of the address is known.
+    Exact,
+    /// The two low bits are unknown and reported as zero.
+    WordAligned,
+    /// Only the page number may be known; the page offset is reported as zero.
+    PageAligned,
+}
+
+/// A guest physical address reconstructed by [`GuestPageFault::full_gpa`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FullGpa {
+    addr: u64,
+    precision: GpaPrecision,
+}
+
+impl FullGpa {
+    /// Returns the guest physical address.
+    #[inline]
+    pub fn addr(&self) -> u64 {
+        self.addr
+    }
+    /// Returns how much of the address is known.
+    #[inline]
+    pub fn precision(&self) -> GpaPrecision {
+        self.precision
+    }
+    /// Returns whether the address is exact.
+    #[inline]
+    pub fn is_exact(&self) -> bool {
+        self.precision == GpaPrecision::Exact
+    }
+}
+
+/// A decoded trap from VS-mode or VU-mode.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GuestTrap {
+    /// An interrupt with the given code.
+    Interrupt(usize),
+    /// An environment call from VS-mode, e.g. an SBI call.
+    EnvCall,
+    /// A virtual instruction exception; `insn` is the faulting instruction
+    /// when the implementation reports it in `stval`.
+    VirtualInstruction {
+        /// Value of `stval`.
+        insn: usize,
+    },
+    /// A guest-page fault.
+    GuestPageFault(GuestPageFault),
+    /// Any other exception.
+    Exception {
+        /// Exception code.
+        code: usize,
+        /// Value of `stval`.
+        tval: usize,
+    },
+}
+
+impl GuestTrap {
+    /// Number of trap kinds, i.e. of distinct [`GuestTrap::index`] values.
+    pub const KINDS: usize = 5;
+    /// Names of the trap kinds, indexed by [`GuestTrap::index`].
+    pub const NAMES: [&'static str; Self::KINDS] = [
+        "interrupt",
+        "ecall",
+        "virtual-instruction",
+        "guest-page-fault",
+        "exception",
+    ];
+
+    /// Returns the index of the trap kind, in `0..GuestTrap::KINDS`.
+    #[inline]
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Interrupt(_) => 0,
+            Self::EnvCall => 1,
+            Self::VirtualInstruction { .. } => 2,
+            Self::GuestPageFault(_) => 3,
+            Self::Exception { .. } => 4,
+        }
+    }
+    /// Returns a short name of the trap kind.
+    #[inline]
+    pub fn name(&self) -> &'static str {
+        Self::NAMES[self.index()]
+    }
+}
+
+impl fmt::Display for GuestTrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interrupt(code) => write!(f, "interrupt {}", code),
+            Self::EnvCall => f.write_str("ecall from VS-mode"),
+            Self::VirtualInstruction { insn } => {
+                write!(f, "virtual instruction {:#010x}", insn)
+            }
+            Self::GuestPageFault(fault) => {
+                write!(
+                    f,
+                    "{:?} guest-page fault at gpa {:#x}",
+                    fault.access,
+                    fault.gpa()
+                )?;
+                if let Some(gva) = fault.gva() {
+                    write!(f, " (gva {:#x})", gva)?;
+                }
+                Ok(())
+            }
+            Self::Exception { code, tval } => {
+                write!(f, "exception {} (tval {:#x})", code, tval)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERRUPT_BIT: usize = 1 << (usize::BITS - 1);
+
+    fn guest_trap(scause: usize, stval: usize, htval: usize, gva: bool) -> TrapInfo {
+        let mut hstatus = hstatus::Hstatus::from_bits(0);
+        hstatus.set_spv(true);
+        hstatus.set_gva(gva);
+        TrapInfo {
+            scause,
+            stval,
+            htval,
+            htinst: 0,
+            hstatus: hstatus.bits(),
+        }
+    }
+
+    #[test]
+    fn test_decode_interrupt() {
+        let trap = guest_trap(INTERRUPT_BIT | 5, 0, 0, false);
+        assert!(trap.from_guest());
+        assert_eq!(trap.decode(), GuestTrap::Interrupt(5));
+    }
+
+    #[test]
+    fn test_decode_ecall_and_virtual_instruction() {
+        assert_eq!(guest_trap(10, 0, 0, false).decode(), GuestTrap::EnvCall);
+        assert_eq!(
+            guest_trap(22, 0x1050_0073, 0, false).decode(),
+            GuestTrap::VirtualInstruction { insn: 0x1050_0073 }
+        );
+    }
+
+    #[test]
+    fn test_decode_guest_page_fault() {
+        let trap = guest_trap(21, 0xffff_ffc0_0000_1234, 0x1000_0400, true);
+        let GuestTrap::GuestPageFault(fault) = trap.decode() else {
+            panic!("not a guest-page fault");
+        };
+        assert_eq!(fault.access(), FaultAccess::Load);
+        assert_eq!(fault.gpa(), 0x4000_1000);
+        assert_eq!(fault.gva(), Some(0xffff_ffc0_0000_1234));
+
+        let trap = guest_trap(23, 0, 0x1000_0400, false);
+        let GuestTrap::GuestPageFault(fault) = trap.decode() else {
+            panic!("not a guest-page fault");
+        };
+        assert_eq!(fault.access(), FaultAccess::Store);
+        assert_eq!(fault.gva(), None);
+    }
+
+    #[test]
+    fn test_full_gpa() {
+        let fault = |trap: TrapInfo| match trap.decode() {
+            GuestTrap::GuestPageFault(fault) => fault,
+            _ => panic!("not a guest-page fault"),
+        };
+
+        // Low bits and page offset come from the guest virtual address
+        let f = fault(guest_trap(21, 0xffff_ffc0_0000_1236, 0x1000_0400, true));
+        let gpa = f.full_gpa();
+        assert_eq!(gpa.addr(), 0x4000_1236);
+        assert!(gpa.is_exact());
+
+        // Implementation reporting only the page number
+        let f = fault(guest_trap(21, 0x1236, 0x1000_0400, true));
+        assert_eq!(f.full_gpa().addr(), 0x4000_1236);
+
+        let f = fault(guest_trap(23, 0, 0x1000_0401, false));
+        assert_eq!(f.full_gpa().addr(), 0x4000_1004);
+        assert_eq!(f.full_gpa().precision(), GpaPrecision::WordAligned);
+
+        let f = fault(guest_trap(23, 0, 0x1000_0400, false));
+        assert_eq!(f.full_gpa().precision(), GpaPrecision::PageAligned);
+
+        // Implicit VS-stage page-table read
+        let mut trap = guest_trap(21, 0, 0x1000_0400, false);
+        trap.htinst = 0x3000;
+        let f = fault(trap);
+        assert!(f.is_implicit());
+        assert!(f.full_gpa().is_exact());
+
+        // Implicit access with GVA set: stval is the original guest VA and
+        // must not be spliced into the page-table entry's address
+        let mut trap = guest_trap(21, 0xffff_ffc0_0000_1236, 0x1000_0402, true);
+        trap.htinst = 0x3000;
+        let f = fault(trap);
+        assert!(f.is_implicit());
+        assert_eq!(f.full_gpa().addr(), 0x4000_1008);
+        assert!(f.full_gpa().is_exact());
+    }
+
+    #[test]
+    fn test_decode_other_exception() {
+        assert_eq!(
+            guest_trap(2, 0xdead, 0, false).decode(),
+            GuestTrap::Exception {
+                code: 2,
+                tval: 0xdead
+            }
+        );
+    }
+}
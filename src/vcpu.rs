@@ -0,0 +1,162 @@
+// Copyright 2025 The Axvisor Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Virtual CPU State.
+//!
+//! [`VsCpuContext`](crate::vcpu::VsCpuContext) holds the VS-level CSRs that
+//! belong to a guest hart and must be saved and restored when switching between
+//! guests on a physical hart.
+
+use crate::gdb::GuestCsrs;
+use crate::register::{hvip, vsatp, vscause, vsepc, vsie, vsscratch, vsstatus, vstval, vstvec};
+
+/// VS-level CSR state of a guest hart.
+#[derive(Copy, Clone, Debug)]
+pub struct VsCpuContext {
+    /// Virtual supervisor status.
+    pub vsstatus: vsstatus::Vsstatus,
+    /// Virtual supervisor interrupt enable.
+    pub vsie: vsie::Vsie,
+    /// Virtual supervisor trap vector.
+    pub vstvec: vstvec::Vstvec,
+    /// Virtual supervisor scratch.
+    pub vsscratch: usize,
+    /// Virtual supervisor exception program counter.
+    pub vsepc: usize,
+    /// Virtual supervisor cause.
+    pub vscause: vscause::Vscause,
+    /// Virtual supervisor trap value.
+    pub vstval: usize,
+    /// Virtual supervisor address translation and protection.
+    pub vsatp: vsatp::Vsatp,
+    /// Virtual interrupts pending for the guest.
+    pub hvip: hvip::Hvip,
+}
+
+impl Default for VsCpuContext {
+    fn default() -> Self {
+        VsCpuContext {
+            vsstatus: vsstatus::Vsstatus::from_bits(0),
+            vsie: vsie::Vsie::from_bits(0),
+            vstvec: vstvec::Vstvec::from_bits(0),
+            vsscratch: 0,
+            vsepc: 0,
+            vscause: vscause::Vscause::from_bits(0),
+            vstval: 0,
+            vsatp: vsatp::Vsatp::from_bits(0),
+            hvip: hvip::Hvip::from_bits(0),
+        }
+    }
+}
+
+impl VsCpuContext {
+    /// CSRs held by the context, as `(name, csr number)`.
+    pub const CSRS: [(&'static str, u16); 9] = [
+        ("vsstatus", 0x200),
+        ("vsie", 0x204),
+        ("vstvec", 0x205),
+        ("vsscratch", 0x240),
+        ("vsepc", 0x241),
+        ("vscause", 0x242),
+        ("vstval", 0x243),
+        ("vsatp", 0x280),
+        ("hvip", 0x645),
+    ];
+
+    /// Reads the VS-level CSRs of the guest loaded on this hart.
+    pub fn save() -> Self {
+        VsCpuContext {
+            vsstatus: vsstatus::read(),
+            vsie: vsie::read(),
+            vstvec: vstvec::read(),
+            vsscratch: vsscratch::read(),
+            vsepc: vsepc::read().bits(),
+            vscause: vscause::read(),
+            vstval: vstval::read(),
+            vsatp: vsatp::read(),
+            hvip: hvip::read(),
+        }
+    }
+    /// Loads the context into the VS-level CSRs of this hart.
+    ///
+    /// # Safety
+    ///
+    /// No guest may be running on this hart, and the G-stage translation of
+    /// the guest owning the context must be installed before it runs.
+    pub unsafe fn restore(&self) {
+        // SAFETY: Caller ensures no guest is running
+        unsafe {
+            self.vsstatus.write();
+            self.vsie.write();
+            self.vstvec.write();
+            vsscratch::write(self.vsscratch);
+            vsepc::Vsepc::from_bits(self.vsepc).write(vsepc::Ialign::Bits16);
+            self.vscause.write();
+            vstval::write(self.vstval);
+            self.vsatp.write();
+            self.hvip.write();
+        }
+    }
+}
+
+impl GuestCsrs for VsCpuContext {
+    fn read_csr(&self, csr: u16) -> Option<usize> {
+        Some(match csr {
+            0x200 => self.vsstatus.bits(),
+            0x204 => self.vsie.bits(),
+            0x205 => self.vstvec.bits(),
+            0x240 => self.vsscratch,
+            0x241 => self.vsepc,
+            0x242 => self.vscause.bits(),
+            0x243 => self.vstval,
+            0x280 => self.vsatp.bits(),
+            0x645 => self.hvip.bits(),
+            _ => return None,
+        })
+    }
+    fn write_csr(&mut self, csr: u16, value: usize) -> bool {
+        match csr {
+            0x200 => self.vsstatus = vsstatus::Vsstatus::from_bits(value),
+            0x204 => self.vsie = vsie::Vsie::from_bits(value),
+            0x205 => self.vstvec = vstvec::Vstvec::from_bits(value),
+            0x240 => self.vsscratch = value,
+            0x241 => self.vsepc = value,
+            0x242 => self.vscause = vscause::Vscause::from_bits(value),
+            0x243 => self.vstval = value,
+            0x280 => self.vsatp = vsatp::Vsatp::from_bits(value),
+            0x645 => self.hvip = hvip::Hvip::from_bits(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_csrs() {
+        let mut ctx = VsCpuContext::default();
+        for (i, &(_, csr)) in VsCpuContext::CSRS.iter().enumerate() {
+            assert!(ctx.write_csr(csr, i + 1));
+        }
+        for (i, &(name, csr)) in VsCpuContext::CSRS.iter().enumerate() {
+            assert_eq!(ctx.read_csr(csr), Some(i + 1), "{}", name);
+        }
+        assert_eq!(ctx.vsepc, 5);
+        assert_eq!(ctx.read_csr(0x600), None);
+        assert!(!ctx.write_csr(0x600, 0));
+    }
+}
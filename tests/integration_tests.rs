@@ -38,7 +38,7 @@ fn test_register_independence() {
 
     // Verify that each register maintains its state independently
     assert!(hstatus_reg.vtsr());
-    assert_eq!(hstatus_reg.vgein(), 0x15);
+    assert_eq!(hstatus_reg.vgein_raw(), 0x15);
 
     assert!(matches!(hgatp_reg.mode(), hgatp::HgatpValues::Sv48x4));
     assert_eq!(hgatp_reg.vmid(), 0x1234);
@@ -94,7 +94,7 @@ fn test_bit_field_isolation() {
     // Verify all fields are set correctly and independently
     assert!(matches!(hstatus_reg.vsxl(), hstatus::VsxlValues::Vsxl64));
     assert!(hstatus_reg.vtsr());
-    assert_eq!(hstatus_reg.vgein(), 0x2A);
+    assert_eq!(hstatus_reg.vgein_raw(), 0x2A);
     assert!(hstatus_reg.hu());
     assert!(hstatus_reg.gva());
 
@@ -103,7 +103,7 @@ fn test_bit_field_isolation() {
 
     assert!(matches!(hstatus_reg.vsxl(), hstatus::VsxlValues::Vsxl64));
     assert!(!hstatus_reg.vtsr()); // This should be false now
-    assert_eq!(hstatus_reg.vgein(), 0x2A);
+    assert_eq!(hstatus_reg.vgein_raw(), 0x2A);
     assert!(hstatus_reg.hu());
     assert!(hstatus_reg.gva());
 }
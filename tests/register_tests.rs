@@ -160,40 +160,29 @@ mod hip_tests {
     fn test_hip_bit_fields() {
         let mut hip = hip::Hip::from_bits(0);
 
-        // Test VSSIP (bit 2)
+        // Test VSSIP (bit 2), the only writable bit
         hip.set_vssip(true);
         assert!(hip.vssip());
+        assert_eq!(hip.bits(), 1 << 2);
 
-        // Test VSTIP (bit 6)
-        hip.set_vstip(true);
+        // VSTIP (bit 6), VSEIP (bit 10) and SGEIP (bit 12) are read-only
+        let hip = hip::Hip::from_bits((1 << 6) | (1 << 10) | (1 << 12));
         assert!(hip.vstip());
-
-        // Test VSEIP (bit 10)
-        hip.set_vseip(true);
         assert!(hip.vseip());
-
-        // Test SGEIP (bit 12)
-        hip.set_sgeip(true);
         assert!(hip.sgeip());
-
-        assert_eq!(hip.bits(), (1 << 2) | (1 << 6) | (1 << 10) | (1 << 12));
+        assert!(!hip.vssip());
     }
 
     #[test]
     fn test_hip_bit_isolation() {
-        let mut hip = hip::Hip::from_bits(0);
-
-        hip.set_vssip(true);
-        hip.set_vstip(true);
-        hip.set_vseip(true);
-        hip.set_sgeip(true);
+        let mut hip = hip::Hip::from_bits((1 << 2) | (1 << 6) | (1 << 10) | (1 << 12));
 
-        // Clear one at a time
-        hip.set_sgeip(false);
-        assert!(hip.vssip());
+        // Clear VSSIP
+        hip.set_vssip(false);
+        assert!(!hip.vssip());
         assert!(hip.vstip());
         assert!(hip.vseip());
-        assert!(!hip.sgeip());
+        assert!(hip.sgeip());
     }
 }
 
@@ -208,14 +197,14 @@ mod vsatp_tests {
     fn test_vsatp_mode() {
         let mut vsatp = vsatp::Vsatp::from_bits(0);
 
-        vsatp.set_mode(vsatp::HgatpValues::Bare);
-        assert!(matches!(vsatp.mode(), vsatp::HgatpValues::Bare));
+        vsatp.set_mode(vsatp::VsatpMode::Bare);
+        assert!(matches!(vsatp.mode(), vsatp::VsatpMode::Bare));
 
-        vsatp.set_mode(vsatp::HgatpValues::Sv39x4);
-        assert!(matches!(vsatp.mode(), vsatp::HgatpValues::Sv39x4));
+        vsatp.set_mode(vsatp::VsatpMode::Sv39);
+        assert!(matches!(vsatp.mode(), vsatp::VsatpMode::Sv39));
 
-        vsatp.set_mode(vsatp::HgatpValues::Sv48x4);
-        assert!(matches!(vsatp.mode(), vsatp::HgatpValues::Sv48x4));
+        vsatp.set_mode(vsatp::VsatpMode::Sv48);
+        assert!(matches!(vsatp.mode(), vsatp::VsatpMode::Sv48));
     }
 
     #[test]
@@ -249,18 +238,18 @@ mod vsatp_tests {
     fn test_vsatp_field_isolation() {
         let mut vsatp = vsatp::Vsatp::from_bits(0);
 
-        vsatp.set_mode(vsatp::HgatpValues::Sv48x4);
+        vsatp.set_mode(vsatp::VsatpMode::Sv48);
         vsatp.set_asid(0xABCD);
         vsatp.set_ppn(0x123456789);
 
         // Verify all fields are independent
-        assert!(matches!(vsatp.mode(), vsatp::HgatpValues::Sv48x4));
+        assert!(matches!(vsatp.mode(), vsatp::VsatpMode::Sv48));
         assert_eq!(vsatp.asid(), 0xABCD);
         assert_eq!(vsatp.ppn(), 0x123456789);
 
         // Modify one field shouldn't affect others
         vsatp.set_asid(0);
-        assert!(matches!(vsatp.mode(), vsatp::HgatpValues::Sv48x4));
+        assert!(matches!(vsatp.mode(), vsatp::VsatpMode::Sv48));
         assert_eq!(vsatp.asid(), 0);
         assert_eq!(vsatp.ppn(), 0x123456789);
     }